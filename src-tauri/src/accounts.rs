@@ -0,0 +1,190 @@
+use crate::dates::parse_date;
+use crate::loans::MAX_TERM_MONTHS;
+use crate::models::{
+    Account, ACCOUNT_KINDS, DEPRECIATION_PERCENTAGE, DEPRECIATION_STRAIGHT_LINE, KIND_ASSET, KIND_CASH, KIND_CHECKING,
+    KIND_CREDIT_CARD, KIND_INVESTMENT, KIND_LOAN, KIND_SAVINGS,
+};
+
+/// Kinds stored before they were validated, when the type held the French
+/// label picked in the account form.
+pub const LEGACY_KINDS: [(&str, &str); 4] = [
+    ("Courant", KIND_CHECKING),
+    ("Épargne", KIND_SAVINGS),
+    ("Investissement", KIND_INVESTMENT),
+    ("Espèces", KIND_CASH),
+];
+
+/// Kind of an account from older data: legacy labels are mapped and
+/// anything else unknown becomes a checking account.
+pub fn known_kind(kind: &str) -> String {
+    if ACCOUNT_KINDS.contains(&kind) {
+        return kind.to_string();
+    }
+    match LEGACY_KINDS.iter().find(|(label, _)| *label == kind) {
+        Some((_, known)) => known.to_string(),
+        None => {
+            log::warn!("Unknown account kind {kind} replaced by {KIND_CHECKING}");
+            KIND_CHECKING.to_string()
+        }
+    }
+}
+
+/// Checks an account before it is saved.
+pub fn validate(account: &Account) -> Result<(), String> {
+    if account.name.trim().is_empty() {
+        return Err("Le nom du compte est obligatoire.".to_string());
+    }
+    if !ACCOUNT_KINDS.contains(&account.account_type.as_str()) {
+        return Err(format!("Type de compte inconnu : {}", account.account_type));
+    }
+    if let Some(opening) = account.opening_date.as_deref().filter(|opening| parse_date(opening).is_none()) {
+        return Err(format!("Date invalide : {opening}"));
+    }
+    if let Some(limit) = account.credit_limit {
+        if account.account_type != KIND_CREDIT_CARD {
+            return Err("Seule une carte de crédit peut avoir un plafond.".to_string());
+        }
+        if limit <= 0.0 {
+            return Err("Le plafond doit être positif.".to_string());
+        }
+    }
+    for day in [account.statement_closing_day, account.payment_due_day].into_iter().flatten() {
+        if account.account_type != KIND_CREDIT_CARD {
+            return Err("Seule une carte de crédit peut avoir un relevé.".to_string());
+        }
+        if !(1..=31).contains(&day) {
+            return Err(format!("Jour du mois invalide : {day}"));
+        }
+    }
+    validate_loan(account)?;
+    validate_depreciation(account)
+}
+
+/// Loan terms are all given or all left out, and only on loans.
+fn validate_loan(account: &Account) -> Result<(), String> {
+    let (principal, rate, term, start) = match (
+        account.loan_principal,
+        account.loan_rate,
+        account.loan_term_months,
+        &account.loan_start_date,
+    ) {
+        (None, None, None, None) => return Ok(()),
+        (Some(principal), Some(rate), Some(term), Some(start)) => (principal, rate, term, start),
+        _ => return Err("Le capital, le taux, la durée et la première échéance du prêt vont ensemble.".to_string()),
+    };
+    if account.account_type != KIND_LOAN {
+        return Err("Seul un prêt peut avoir un échéancier.".to_string());
+    }
+    if principal <= 0.0 {
+        return Err("Le capital emprunté doit être positif.".to_string());
+    }
+    if !(0.0..100.0).contains(&rate) {
+        return Err(format!("Taux d'intérêt invalide : {rate}"));
+    }
+    if !(1..=MAX_TERM_MONTHS).contains(&term) {
+        return Err(format!("La durée du prêt doit être comprise entre 1 et {MAX_TERM_MONTHS} mois."));
+    }
+    if parse_date(start).is_none() {
+        return Err(format!("Date invalide : {start}"));
+    }
+    Ok(())
+}
+
+/// Depreciation comes with its rate, only on assets.
+fn validate_depreciation(account: &Account) -> Result<(), String> {
+    let (method, rate) = match (&account.depreciation_method, account.depreciation_rate) {
+        (None, None) => return Ok(()),
+        (Some(method), Some(rate)) => (method.as_str(), rate),
+        _ => return Err("La méthode et le taux d'amortissement vont ensemble.".to_string()),
+    };
+    if account.account_type != KIND_ASSET {
+        return Err("Seul un bien peut être amorti.".to_string());
+    }
+    match method {
+        DEPRECIATION_STRAIGHT_LINE if rate > 0.0 => Ok(()),
+        DEPRECIATION_PERCENTAGE if rate > 0.0 && rate < 100.0 => Ok(()),
+        DEPRECIATION_STRAIGHT_LINE | DEPRECIATION_PERCENTAGE => Err(format!("Taux d'amortissement invalide : {rate}")),
+        method => Err(format!("Méthode d'amortissement inconnue : {method}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(kind: &str, credit_limit: Option<f64>) -> Account {
+        let mut account: Account =
+            serde_json::from_value(serde_json::json!({"id": "a1", "name": "Compte", "type": kind})).unwrap();
+        account.credit_limit = credit_limit;
+        account
+    }
+
+    #[test]
+    fn legacy_labels_map_to_kinds() {
+        assert_eq!(known_kind(KIND_SAVINGS), KIND_SAVINGS);
+        assert_eq!(known_kind("Épargne"), KIND_SAVINGS);
+        assert_eq!(known_kind("Espèces"), KIND_CASH);
+        assert_eq!(known_kind("Compte joint"), KIND_CHECKING);
+    }
+
+    #[test]
+    fn only_credit_cards_have_a_positive_limit() {
+        assert!(validate(&account(KIND_CHECKING, None)).is_ok());
+        assert!(validate(&account(KIND_CREDIT_CARD, Some(1500.0))).is_ok());
+        assert!(validate(&account("Courant", None)).is_err());
+        assert!(validate(&account(KIND_CHECKING, Some(1500.0))).is_err());
+        assert!(validate(&account(KIND_CREDIT_CARD, Some(0.0))).is_err());
+        assert!(validate(&Account {
+            name: " ".to_string(),
+            ..account(KIND_CHECKING, None)
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn opening_date_must_be_a_date() {
+        let opening = |date: &str| Account {
+            opening_date: Some(date.to_string()),
+            ..account(KIND_CHECKING, None)
+        };
+        assert!(validate(&opening("2026-01-15")).is_ok());
+        assert!(validate(&opening("15/01/2026")).is_err());
+        assert!(validate(&opening("2026-02-30")).is_err());
+    }
+
+    #[test]
+    fn loan_terms_come_together_and_only_on_loans() {
+        let loan = Account {
+            loan_principal: Some(10_000.0),
+            loan_rate: Some(3.5),
+            loan_term_months: Some(120),
+            loan_start_date: Some("2026-01-15".to_string()),
+            ..account(KIND_LOAN, None)
+        };
+        assert!(validate(&loan).is_ok());
+        assert!(validate(&account(KIND_LOAN, None)).is_ok());
+        assert!(validate(&Account { loan_rate: None, ..loan.clone() }).is_err());
+        assert!(validate(&Account { account_type: KIND_CHECKING.to_string(), ..loan.clone() }).is_err());
+        assert!(validate(&Account { loan_principal: Some(-1.0), ..loan.clone() }).is_err());
+        assert!(validate(&Account { loan_rate: Some(100.0), ..loan.clone() }).is_err());
+        assert!(validate(&Account { loan_term_months: Some(0), ..loan.clone() }).is_err());
+        assert!(validate(&Account { loan_term_months: Some(MAX_TERM_MONTHS + 1), ..loan.clone() }).is_err());
+        assert!(validate(&Account { loan_start_date: Some("2026-02-30".to_string()), ..loan }).is_err());
+    }
+
+    #[test]
+    fn depreciation_needs_a_valid_rate_on_an_asset() {
+        let asset = |method: &str, rate: f64| Account {
+            depreciation_method: Some(method.to_string()),
+            depreciation_rate: Some(rate),
+            ..account(KIND_ASSET, None)
+        };
+        assert!(validate(&asset(DEPRECIATION_STRAIGHT_LINE, 1200.0)).is_ok());
+        assert!(validate(&asset(DEPRECIATION_PERCENTAGE, 20.0)).is_ok());
+        assert!(validate(&asset(DEPRECIATION_PERCENTAGE, 100.0)).is_err());
+        assert!(validate(&asset(DEPRECIATION_STRAIGHT_LINE, 0.0)).is_err());
+        assert!(validate(&asset("sum_of_years", 10.0)).is_err());
+        assert!(validate(&Account { depreciation_rate: None, ..asset(DEPRECIATION_PERCENTAGE, 20.0) }).is_err());
+        assert!(validate(&Account { account_type: KIND_CASH.to_string(), ..asset(DEPRECIATION_PERCENTAGE, 20.0) }).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use crate::audit;
+use crate::balances::round_cents;
+use crate::dates::{months_between, parse_date};
+use crate::models::{AssetValue, Valuation, DEPRECIATION_PERCENTAGE, DEPRECIATION_STRAIGHT_LINE};
+use sqlx::SqliteConnection;
+
+pub async fn fetch_valuations(
+    conn: &mut SqliteConnection,
+    account_id: Option<&str>,
+) -> Result<Vec<Valuation>, sqlx::Error> {
+    sqlx::query_as::<_, Valuation>("SELECT * FROM valuations WHERE $1 IS NULL OR \"accountId\" = $1 ORDER BY \"accountId\", date")
+        .bind(account_id)
+        .fetch_all(&mut *conn)
+        .await
+}
+
+/// `value` at `date` after depreciating from `since` by whole months, with
+/// the depreciation method and rate of an asset account.
+pub fn depreciate(method: Option<&str>, rate: Option<f64>, value: f64, since: &str, date: &str) -> f64 {
+    let (Some(method), Some(rate), Some(since), Some(date)) = (
+        method,
+        rate,
+        parse_date(since),
+        parse_date(date),
+    ) else {
+        return value;
+    };
+    let years = months_between(since, date) as f64 / 12.0;
+    match method {
+        DEPRECIATION_STRAIGHT_LINE => (value - rate * years).max(0.0),
+        DEPRECIATION_PERCENTAGE => value * (1.0 - rate / 100.0).powf(years),
+        _ => value,
+    }
+}
+
+/// Value at the end of `as_of` (YYYY-MM-DD) of every account valued on or
+/// before that date. Accounts without valuations are left out: their
+/// balance stands.
+pub async fn values(conn: &mut SqliteConnection, as_of: &str) -> Result<Vec<AssetValue>, sqlx::Error> {
+    let valuations = sqlx::query_as::<_, Valuation>(
+        "SELECT v.* FROM valuations v
+         WHERE v.date = (SELECT MAX(date) FROM valuations WHERE \"accountId\" = v.\"accountId\" AND date <= $1)
+         ORDER BY v.\"accountId\"",
+    )
+    .bind(as_of)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut values = Vec::with_capacity(valuations.len());
+    for valuation in valuations {
+        let Some(account) = audit::fetch_account(conn, &valuation.account_id).await? else {
+            continue;
+        };
+        values.push(AssetValue {
+            value: round_cents(depreciate(
+                account.depreciation_method.as_deref(),
+                account.depreciation_rate,
+                valuation.value,
+                &valuation.date,
+                as_of,
+            )),
+            date: as_of.to_string(),
+            valuation_date: valuation.date,
+            valuation: valuation.value,
+            account_id: valuation.account_id,
+        });
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn straight_line_depreciates_by_whole_months() {
+        let straight = Some(DEPRECIATION_STRAIGHT_LINE);
+        assert_eq!(depreciate(straight, Some(1200.0), 10_000.0, "2025-01-15", "2026-07-14"), 8300.0);
+        assert_eq!(depreciate(straight, Some(1200.0), 10_000.0, "2026-01-31", "2026-02-28"), 9900.0);
+        assert_eq!(depreciate(straight, Some(1200.0), 1000.0, "2020-01-01", "2026-01-01"), 0.0);
+    }
+
+    #[test]
+    fn percentage_compounds_yearly() {
+        let percentage = Some(DEPRECIATION_PERCENTAGE);
+        assert_eq!(round_cents(depreciate(percentage, Some(20.0), 10_000.0, "2024-03-01", "2026-03-01")), 6400.0);
+        assert_eq!(round_cents(depreciate(percentage, Some(20.0), 10_000.0, "2026-03-01", "2026-09-01")), 8944.27);
+    }
+
+    #[test]
+    fn value_stands_without_depreciation() {
+        assert_eq!(depreciate(None, Some(20.0), 500.0, "2024-01-01", "2026-01-01"), 500.0);
+        assert_eq!(depreciate(Some(DEPRECIATION_PERCENTAGE), None, 500.0, "2024-01-01", "2026-01-01"), 500.0);
+        assert_eq!(depreciate(Some(DEPRECIATION_STRAIGHT_LINE), Some(100.0), 500.0, "2026-01-01", "2025-01-01"), 500.0);
+    }
+}
@@ -0,0 +1,252 @@
+use crate::audit::{self, Operation};
+use crate::models::Attachment;
+use sha2::{Digest, Sha256};
+use sqlx::SqliteConnection;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+
+/// Files touched more recently than this are left alone by
+/// [`collect_garbage`]: they may belong to an attachment whose row is not
+/// committed yet, or be a copy still being written.
+pub const GC_MIN_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Folder of the attachment files, created on first use.
+pub fn dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("attachments");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Impossible de créer le dossier des pièces jointes : {e}"))?;
+    Ok(dir)
+}
+
+/// Mime type read from the file signature rather than trusted from the name.
+pub fn detect_mime_type(content: &[u8]) -> Option<&'static str> {
+    if content.starts_with(b"%PDF-") {
+        Some("application/pdf")
+    } else if content.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if content.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else {
+        None
+    }
+}
+
+fn extension(mime_type: &str) -> &'static str {
+    match mime_type {
+        "application/pdf" => "pdf",
+        "image/jpeg" => "jpg",
+        "image/png" => "png",
+        _ => "bin",
+    }
+}
+
+/// Stored files are named `<sha256>.<ext>`; the extension lets the system
+/// pick the right viewer.
+pub fn file_path(dir: &Path, attachment: &Attachment) -> PathBuf {
+    dir.join(format!("{}.{}", attachment.hash, extension(&attachment.mime_type)))
+}
+
+/// Copies `content` into the folder unless a file with the same hash is
+/// already there. Returns its hash.
+pub fn store(dir: &Path, content: &[u8], mime_type: &str) -> Result<String, std::io::Error> {
+    let hash = format!("{:x}", Sha256::digest(content));
+    let target = dir.join(format!("{hash}.{}", extension(mime_type)));
+    if target.exists() {
+        // Reused files are touched too, so the garbage collector spares them until the row is committed
+        std::fs::File::options()
+            .append(true)
+            .open(&target)?
+            .set_modified(SystemTime::now())?;
+    } else {
+        // Write then rename so a crash never leaves a truncated file under its final name
+        let temp = dir.join(format!("{hash}.tmp"));
+        std::fs::write(&temp, content)?;
+        std::fs::rename(&temp, &target)?;
+    }
+    Ok(hash)
+}
+
+pub async fn fetch_attachments(
+    conn: &mut SqliteConnection,
+    transaction_id: Option<&str>,
+) -> Result<Vec<Attachment>, sqlx::Error> {
+    match transaction_id {
+        Some(transaction_id) => {
+            sqlx::query_as::<_, Attachment>("SELECT * FROM attachments WHERE \"transactionId\" = $1 ORDER BY \"createdAt\"")
+                .bind(transaction_id)
+                .fetch_all(&mut *conn)
+                .await
+        }
+        None => {
+            sqlx::query_as::<_, Attachment>("SELECT * FROM attachments ORDER BY \"createdAt\"")
+                .fetch_all(&mut *conn)
+                .await
+        }
+    }
+}
+
+/// Deletes an attachment row. The file is left in place so the deletion can
+/// be undone; [`collect_garbage`] removes it once nothing refers to it.
+pub async fn delete(conn: &mut SqliteConnection, op: &mut Operation, id: &str) -> Result<bool, sqlx::Error> {
+    let Some(before) = audit::fetch_attachment(conn, id).await? else {
+        return Ok(false);
+    };
+    sqlx::query("DELETE FROM attachments WHERE id = $1")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    audit::record(conn, op, audit::ENTITY_ATTACHMENT, id, audit::ACTION_DELETE, Some(&before), None)
+        .await?;
+    Ok(true)
+}
+
+/// Deletes the attachments of a transaction about to be deleted, so undoing
+/// the deletion brings them back.
+pub async fn delete_for_transaction(
+    conn: &mut SqliteConnection,
+    op: &mut Operation,
+    transaction_id: &str,
+) -> Result<(), sqlx::Error> {
+    let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM attachments WHERE \"transactionId\" = $1")
+        .bind(transaction_id)
+        .fetch_all(&mut *conn)
+        .await?;
+    for id in ids {
+        delete(conn, op, &id).await?;
+    }
+    Ok(())
+}
+
+/// Hashes still in use: current attachments, plus those an undo or redo
+/// could bring back, including through an undone import.
+async fn referenced_hashes(conn: &mut SqliteConnection) -> Result<HashSet<String>, sqlx::Error> {
+    let hashes: Vec<Option<String>> = sqlx::query_scalar(
+        "WITH snapshots AS (
+             SELECT entity, \"before\" AS snapshot FROM audit_log WHERE \"operationId\" IS NOT NULL
+             UNION ALL
+             SELECT entity, \"after\" FROM audit_log WHERE \"operationId\" IS NOT NULL
+         )
+         SELECT hash FROM attachments
+         UNION SELECT json_extract(snapshot, '$.hash') FROM snapshots WHERE entity = $1
+         UNION SELECT json_extract(a.value, '$.hash') FROM snapshots, json_each(snapshots.snapshot, '$.attachments') AS a
+               WHERE entity = $2",
+    )
+    .bind(audit::ENTITY_ATTACHMENT)
+    .bind(audit::ENTITY_IMPORT)
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(hashes.into_iter().flatten().collect())
+}
+
+/// Removes the files of the attachments folder that nothing refers to,
+/// including leftovers of interrupted copies, once they are older than
+/// [`GC_MIN_AGE`]. Returns how many were removed.
+pub async fn collect_garbage(conn: &mut SqliteConnection, dir: &Path) -> Result<u64, String> {
+    let referenced = referenced_hashes(conn)
+        .await
+        .map_err(|e| format!("Erreur BDD (lecture des pièces jointes): {e}"))?;
+
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("Lecture du dossier des pièces jointes impossible : {e}"))?;
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let recent = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .map_or(true, |modified| modified.elapsed().map_or(true, |age| age < GC_MIN_AGE));
+        if recent {
+            continue;
+        }
+        let is_temp = path.extension().is_some_and(|ext| ext == "tmp");
+        let in_use = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|hash| referenced.contains(hash));
+        if in_use && !is_temp {
+            continue;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => removed += 1,
+            Err(e) => log::warn!("Could not remove attachment file {}: {e}", path.display()),
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("attachments-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn mime_type_comes_from_the_signature() {
+        assert_eq!(detect_mime_type(b"%PDF-1.7\n..."), Some("application/pdf"));
+        assert_eq!(detect_mime_type(&[0xFF, 0xD8, 0xFF, 0xE0]), Some("image/jpeg"));
+        assert_eq!(detect_mime_type(b"\x89PNG\r\n\x1a\n\0\0"), Some("image/png"));
+        assert_eq!(detect_mime_type(b"MZ\x90\0"), None);
+    }
+
+    #[test]
+    fn identical_content_is_stored_once() {
+        let dir = temp_dir("store");
+        let first = store(&dir, b"%PDF-1.7 facture", "application/pdf").unwrap();
+        let second = store(&dir, b"%PDF-1.7 facture", "application/pdf").unwrap();
+        assert_eq!(first, second);
+        assert!(dir.join(format!("{first}.pdf")).is_file());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn garbage_collection_keeps_referenced_files() {
+        let dir = temp_dir("gc");
+        let kept = store(&dir, b"%PDF-1.7 kept", "application/pdf").unwrap();
+        store(&dir, b"%PDF-1.7 orphan", "application/pdf").unwrap();
+        std::fs::write(dir.join(format!("{kept}.tmp")), b"interrupted").unwrap();
+
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        for sql in [
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'Compte', 'checking', 0)".to_string(),
+            "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount) VALUES ('t1', '2026-01-02', 'a1', 'expense', 1)".to_string(),
+            format!("INSERT INTO attachments (id, \"transactionId\", hash, \"mimeType\", \"originalName\", size) VALUES ('f1', 't1', '{kept}', 'application/pdf', 'facture.pdf', 13)"),
+        ] {
+            sqlx::query(&sql).execute(&mut *conn).await.unwrap();
+        }
+
+        // Nothing is old enough yet
+        assert_eq!(collect_garbage(&mut conn, &dir).await.unwrap(), 0);
+
+        let old = SystemTime::now() - 2 * GC_MIN_AGE;
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            std::fs::File::options().append(true).open(entry.unwrap().path()).unwrap().set_modified(old).unwrap();
+        }
+        // Storing the content again refreshes the file
+        let fresh = store(&dir, b"%PDF-1.7 fresh", "application/pdf").unwrap();
+        std::fs::File::options().append(true).open(dir.join(format!("{fresh}.pdf"))).unwrap().set_modified(old).unwrap();
+        store(&dir, b"%PDF-1.7 fresh", "application/pdf").unwrap();
+
+        assert_eq!(collect_garbage(&mut conn, &dir).await.unwrap(), 2);
+        let mut left: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name()).collect();
+        left.sort();
+        let mut expected = [format!("{kept}.pdf"), format!("{fresh}.pdf")].map(std::ffi::OsString::from);
+        expected.sort();
+        assert_eq!(left, expected);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
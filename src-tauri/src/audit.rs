@@ -0,0 +1,447 @@
+use crate::models::{
+    Account, AppData, Attachment, BalanceAssertion, Category, Goal, Payee, Price, Reconciliation, Rule, ScheduledTransaction, Security, Settings, SettingsRow, Tag,
+    Trade, Transaction, Valuation,
+};
+use serde::Serialize;
+use sqlx::SqliteConnection;
+
+// Entity names stored in audit_log.entity
+pub const ENTITY_ACCOUNT: &str = "account";
+pub const ENTITY_TRANSACTION: &str = "transaction";
+pub const ENTITY_CATEGORY: &str = "category";
+pub const ENTITY_SCHEDULED: &str = "scheduled";
+pub const ENTITY_RULE: &str = "rule";
+pub const ENTITY_PAYEE: &str = "payee";
+pub const ENTITY_TAG: &str = "tag";
+// Link between a transaction and a tag, id "transactionId:tagId"
+pub const ENTITY_TRANSACTION_TAG: &str = "transaction_tag";
+pub const ENTITY_ATTACHMENT: &str = "attachment";
+pub const ENTITY_RECONCILIATION: &str = "reconciliation";
+pub const ENTITY_BALANCE_ASSERTION: &str = "balance_assertion";
+pub const ENTITY_SECURITY: &str = "security";
+pub const ENTITY_TRADE: &str = "trade";
+pub const ENTITY_PRICE: &str = "price";
+pub const ENTITY_VALUATION: &str = "valuation";
+pub const ENTITY_GOAL: &str = "goal";
+pub const ENTITY_SETTINGS: &str = "settings";
+pub const ENTITY_IMPORT: &str = "import";
+
+// Actions stored in audit_log.action
+pub const ACTION_CREATE: &str = "create";
+pub const ACTION_UPDATE: &str = "update";
+pub const ACTION_DELETE: &str = "delete";
+pub const ACTION_IMPORT: &str = "import";
+pub const ACTION_UNDO: &str = "undo";
+pub const ACTION_REDO: &str = "redo";
+
+/// Groups the audit entries written by one command so they can be undone
+/// and redone together. The operation row is created lazily on the first
+/// recorded change, so commands that end up changing nothing leave no trace
+/// on the undo stack.
+pub struct Operation {
+    label: &'static str,
+    id: Option<i64>,
+    undoable: bool,
+}
+
+impl Operation {
+    pub fn new(label: &'static str) -> Self {
+        Self {
+            label,
+            id: None,
+            undoable: true,
+        }
+    }
+
+    /// Changes that are audited but never part of the undo stack (UI preferences).
+    pub fn untracked(label: &'static str) -> Self {
+        Self {
+            label,
+            id: None,
+            undoable: false,
+        }
+    }
+
+    async fn id(&mut self, conn: &mut SqliteConnection) -> Result<Option<i64>, sqlx::Error> {
+        if !self.undoable {
+            return Ok(None);
+        }
+        if self.id.is_none() {
+            // A new change invalidates everything that was undone before it
+            sqlx::query(
+                "UPDATE audit_log SET \"operationId\" = NULL WHERE \"operationId\" IN (SELECT id FROM operations WHERE undone = 1)",
+            )
+            .execute(&mut *conn)
+            .await?;
+            sqlx::query("DELETE FROM operations WHERE undone = 1")
+                .execute(&mut *conn)
+                .await?;
+
+            let id: i64 = sqlx::query_scalar("INSERT INTO operations (label) VALUES ($1) RETURNING id")
+                .bind(self.label)
+                .fetch_one(&mut *conn)
+                .await?;
+            self.id = Some(id);
+        }
+        Ok(self.id)
+    }
+}
+
+fn to_json<T: Serialize>(value: Option<&T>) -> Result<Option<String>, sqlx::Error> {
+    value
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| sqlx::Error::Encode(Box::new(e)))
+}
+
+/// Writes one row to the audit log. Must be called inside the same SQL
+/// transaction as the change it describes so both succeed or fail together.
+/// Updates that leave the row unchanged are not recorded.
+pub async fn record<T: Serialize>(
+    conn: &mut SqliteConnection,
+    operation: &mut Operation,
+    entity: &str,
+    entity_id: &str,
+    action: &str,
+    before: Option<&T>,
+    after: Option<&T>,
+) -> Result<(), sqlx::Error> {
+    let before = to_json(before)?;
+    let after = to_json(after)?;
+
+    if action == ACTION_UPDATE && before == after {
+        return Ok(());
+    }
+
+    record_json(conn, operation, entity, entity_id, action, before, after).await
+}
+
+/// Same as [`record`] for snapshots that are already serialized.
+pub async fn record_json(
+    conn: &mut SqliteConnection,
+    operation: &mut Operation,
+    entity: &str,
+    entity_id: &str,
+    action: &str,
+    before: Option<String>,
+    after: Option<String>,
+) -> Result<(), sqlx::Error> {
+    let operation_id = operation.id(conn).await?;
+
+    sqlx::query(
+        "INSERT INTO audit_log (entity, \"entityId\", action, \"before\", \"after\", \"operationId\") VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(entity)
+    .bind(entity_id)
+    .bind(action)
+    .bind(before)
+    .bind(after)
+    .bind(operation_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+// --- Snapshots ---
+// Current state of a row, read inside the caller's transaction before it is modified.
+
+pub async fn fetch_account(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<Account>, sqlx::Error> {
+    sqlx::query_as::<_, Account>("SELECT * FROM accounts WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+pub async fn fetch_transaction(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<Transaction>, sqlx::Error> {
+    sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+pub async fn fetch_category(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<Category>, sqlx::Error> {
+    sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+pub async fn fetch_scheduled(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<ScheduledTransaction>, sqlx::Error> {
+    sqlx::query_as::<_, ScheduledTransaction>("SELECT * FROM scheduled_transactions WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+pub async fn fetch_rule(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<Rule>, sqlx::Error> {
+    sqlx::query_as::<_, Rule>("SELECT * FROM rules WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+pub async fn fetch_payee(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<Payee>, sqlx::Error> {
+    sqlx::query_as::<_, Payee>("SELECT * FROM payees WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+pub async fn fetch_tag(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<Tag>, sqlx::Error> {
+    sqlx::query_as::<_, Tag>("SELECT * FROM tags WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+pub async fn fetch_attachment(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<Attachment>, sqlx::Error> {
+    sqlx::query_as::<_, Attachment>("SELECT * FROM attachments WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+pub async fn fetch_reconciliation(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<Reconciliation>, sqlx::Error> {
+    sqlx::query_as::<_, Reconciliation>("SELECT * FROM reconciliations WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+pub async fn fetch_balance_assertion(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<BalanceAssertion>, sqlx::Error> {
+    sqlx::query_as::<_, BalanceAssertion>("SELECT * FROM balance_assertions WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+pub async fn fetch_security(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<Security>, sqlx::Error> {
+    sqlx::query_as::<_, Security>("SELECT * FROM securities WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+pub async fn fetch_trade(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<Trade>, sqlx::Error> {
+    sqlx::query_as::<_, Trade>("SELECT * FROM trades WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+pub async fn fetch_price(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<Price>, sqlx::Error> {
+    sqlx::query_as::<_, Price>("SELECT * FROM prices WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+pub async fn fetch_valuation(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<Valuation>, sqlx::Error> {
+    sqlx::query_as::<_, Valuation>("SELECT * FROM valuations WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+pub async fn fetch_goal(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<Goal>, sqlx::Error> {
+    sqlx::query_as::<_, Goal>("SELECT * FROM goals WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+pub async fn fetch_settings(conn: &mut SqliteConnection) -> Result<Option<Settings>, sqlx::Error> {
+    let row = sqlx::query_as::<_, SettingsRow>("SELECT * FROM settings WHERE id = 1")
+        .fetch_optional(&mut *conn)
+        .await?;
+    Ok(row.map(Settings::from))
+}
+
+/// Full snapshot of the user data replaced by an import.
+pub async fn fetch_app_data(conn: &mut SqliteConnection) -> Result<AppData, sqlx::Error> {
+    let accounts = sqlx::query_as::<_, Account>("SELECT * FROM accounts")
+        .fetch_all(&mut *conn)
+        .await?;
+    let transactions = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions")
+        .fetch_all(&mut *conn)
+        .await?;
+    let categories = sqlx::query_as::<_, Category>("SELECT * FROM categories")
+        .fetch_all(&mut *conn)
+        .await?;
+    let scheduled = sqlx::query_as::<_, ScheduledTransaction>("SELECT * FROM scheduled_transactions")
+        .fetch_all(&mut *conn)
+        .await?;
+    let rules = crate::rules::fetch_rules(conn).await?;
+    let payees = crate::payees::fetch_payees(conn).await?;
+    let tags = crate::tags::fetch_tags(conn).await?;
+    let transaction_tags = crate::tags::fetch_links(conn).await?;
+    let attachments = crate::attachments::fetch_attachments(conn, None).await?;
+    let reconciliations = crate::reconciliation::fetch_reconciliations(conn, None).await?;
+    let balance_assertions = crate::balances::fetch_assertions(conn, None).await?;
+    let securities = crate::investments::fetch_securities(conn).await?;
+    let trades = crate::investments::fetch_trades(conn, None).await?;
+    let prices = crate::investments::fetch_prices(conn, None).await?;
+    let valuations = crate::assets::fetch_valuations(conn, None).await?;
+    let goals = crate::goals::fetch_goals(conn).await?;
+
+    Ok(AppData {
+        accounts,
+        transactions,
+        categories,
+        scheduled,
+        rules: Some(rules),
+        payees: Some(payees),
+        tags: Some(tags),
+        transaction_tags: Some(transaction_tags),
+        reconciliations: Some(reconciliations),
+        balance_assertions: Some(balance_assertions),
+        attachments: Some(attachments),
+        securities: Some(securities),
+        trades: Some(trades),
+        prices: Some(prices),
+        valuations: Some(valuations),
+        goals: Some(goals),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    fn category(name: &str) -> Category {
+        serde_json::from_value(serde_json::json!({"id": "c1", "name": name, "icon": "Tag", "color": "#fff"})).unwrap()
+    }
+
+    async fn entries(conn: &mut SqliteConnection) -> Vec<(String, String, Option<String>, Option<String>)> {
+        sqlx::query_as("SELECT action, \"entityId\", \"before\", \"after\" FROM audit_log ORDER BY id")
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn records_snapshots_as_json() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        let mut op = Operation::new("test");
+        let before = category("Food");
+        let after = category("Groceries");
+        record(&mut conn, &mut op, ENTITY_CATEGORY, "c1", ACTION_CREATE, None, Some(&before)).await.unwrap();
+        record(&mut conn, &mut op, ENTITY_CATEGORY, "c1", ACTION_UPDATE, Some(&before), Some(&after)).await.unwrap();
+
+        let rows = entries(&mut conn).await;
+        assert_eq!(rows.len(), 2);
+        assert_eq!((rows[0].0.as_str(), rows[0].1.as_str(), rows[0].2.as_deref()), (ACTION_CREATE, "c1", None));
+        let logged: Category = serde_json::from_str(rows[1].3.as_deref().unwrap()).unwrap();
+        assert_eq!(logged.name, "Groceries");
+    }
+
+    #[tokio::test]
+    async fn skips_updates_that_change_nothing() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        let mut op = Operation::new("test");
+        let same = category("Food");
+        record(&mut conn, &mut op, ENTITY_CATEGORY, "c1", ACTION_UPDATE, Some(&same), Some(&same)).await.unwrap();
+        record(&mut conn, &mut op, ENTITY_CATEGORY, "c1", ACTION_DELETE, Some(&same), None).await.unwrap();
+
+        let rows = entries(&mut conn).await;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].0, ACTION_DELETE);
+    }
+
+    #[tokio::test]
+    async fn groups_the_changes_of_an_operation() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        let (food, groceries) = (category("Food"), category("Groceries"));
+        let mut op = Operation::new("test");
+        record(&mut conn, &mut op, ENTITY_CATEGORY, "c1", ACTION_CREATE, None, Some(&food)).await.unwrap();
+        record(&mut conn, &mut op, ENTITY_CATEGORY, "c1", ACTION_UPDATE, Some(&food), Some(&groceries)).await.unwrap();
+        let mut untracked = Operation::untracked("settings");
+        record(&mut conn, &mut untracked, ENTITY_CATEGORY, "c1", ACTION_DELETE, Some(&groceries), None).await.unwrap();
+
+        let operations: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM operations").fetch_one(&mut *conn).await.unwrap();
+        assert_eq!(operations, 1);
+        let ids: Vec<Option<i64>> = sqlx::query_scalar("SELECT \"operationId\" FROM audit_log ORDER BY id")
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(ids, [Some(1), Some(1), None]);
+    }
+
+    #[tokio::test]
+    async fn operations_without_changes_leave_no_trace() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        let mut op = Operation::new("test");
+        let same = category("Food");
+        record(&mut conn, &mut op, ENTITY_CATEGORY, "c1", ACTION_UPDATE, Some(&same), Some(&same)).await.unwrap();
+
+        let operations: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM operations").fetch_one(&mut *conn).await.unwrap();
+        assert_eq!(operations, 0);
+    }
+
+    #[tokio::test]
+    async fn settings_gather_the_window_geometry() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        assert!(fetch_settings(&mut conn).await.unwrap().is_none());
+
+        sqlx::query("INSERT INTO settings (id, \"windowPositionX\", \"windowPositionY\", \"windowSizeWidth\") VALUES (1, 10, 20, 800)")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        let settings = fetch_settings(&mut conn).await.unwrap().unwrap();
+        assert_eq!(settings.display_style.as_deref(), Some("modern"));
+        assert_eq!(settings.window_position.map(|p| (p.x, p.y)), Some((10, 20)));
+        assert!(settings.window_size.is_none());
+    }
+}
@@ -0,0 +1,533 @@
+use crate::models::{
+    is_liability, Account, AccountBalance, AccountStatusBalances, BalanceAssertion, BalanceCheck, NetWorth, NetWorthPoint,
+    RunningBalance,
+    STATUS_PENDING, STATUS_RECONCILED,
+};
+use crate::investments::TRADE_CASH;
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+use std::collections::HashMap;
+
+/// Signed amount of a row of `transactions`: income adds to the balance of
+/// its account, anything else (expenses, outgoing transfer legs) subtracts.
+pub const SIGNED_AMOUNT: &str = "(CASE WHEN t.\"type\" = 'income' THEN t.amount ELSE -t.amount END)";
+
+/// True for the rows of `transactions` `t` that count towards the balance of
+/// their account `a`: those from its opening date on.
+pub const FROM_OPENING: &str = "(a.\"openingDate\" IS NULL OR t.date >= a.\"openingDate\")";
+
+pub fn round_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+/// Balances of every account split by transaction status. Archived accounts
+/// are left out unless `include_archived` is set.
+pub async fn status_balances(
+    conn: &mut SqliteConnection,
+    include_archived: bool,
+) -> Result<Vec<AccountStatusBalances>, sqlx::Error> {
+    let balances = sqlx::query_as::<_, AccountStatusBalances>(&format!(
+        "SELECT a.id AS \"accountId\",
+                a.\"initialBalance\" + TOTAL(CASE WHEN t.status = $1 THEN {SIGNED_AMOUNT} END) AS reconciled,
+                a.\"initialBalance\" + TOTAL(CASE WHEN t.status <> $2 THEN {SIGNED_AMOUNT} END) AS cleared,
+                TOTAL(CASE WHEN t.status = $2 THEN {SIGNED_AMOUNT} END) AS pending,
+                a.\"initialBalance\" + TOTAL({SIGNED_AMOUNT}) AS total
+         FROM accounts a
+         LEFT JOIN transactions t ON t.\"accountId\" = a.id AND {FROM_OPENING}
+         WHERE $3 OR NOT a.archived
+         GROUP BY a.id"
+    ))
+    .bind(STATUS_RECONCILED)
+    .bind(STATUS_PENDING)
+    .bind(include_archived)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(balances
+        .into_iter()
+        .map(|b| AccountStatusBalances {
+            reconciled: round_cents(b.reconciled),
+            cleared: round_cents(b.cleared),
+            pending: round_cents(b.pending),
+            total: round_cents(b.total),
+            ..b
+        })
+        .collect())
+}
+
+/// Balance of every account, counting transactions up to `as_of` (inclusive)
+/// when given, and only cleared or reconciled ones with `cleared_only`.
+/// Archived accounts are left out unless `include_archived` is set.
+pub async fn account_balances(
+    conn: &mut SqliteConnection,
+    as_of: Option<String>,
+    cleared_only: bool,
+    include_archived: bool,
+) -> Result<Vec<AccountBalance>, sqlx::Error> {
+    let mut query: QueryBuilder<Sqlite> = QueryBuilder::new(format!(
+        "SELECT a.id AS \"accountId\", a.\"initialBalance\" + TOTAL({SIGNED_AMOUNT}) AS balance,
+                a.\"creditLimit\" + a.\"initialBalance\" + TOTAL({SIGNED_AMOUNT}) AS \"availableCredit\"
+         FROM accounts a
+         LEFT JOIN transactions t ON t.\"accountId\" = a.id AND {FROM_OPENING}"
+    ));
+    // In the join condition so that accounts without matching rows keep their initial balance
+    if let Some(as_of) = as_of {
+        query.push(" AND t.date <= ").push_bind(as_of);
+    }
+    if cleared_only {
+        query.push(" AND t.status <> ").push_bind(STATUS_PENDING);
+    }
+    if !include_archived {
+        query.push(" WHERE NOT a.archived");
+    }
+    query.push(" GROUP BY a.id");
+
+    let balances = query.build_query_as::<AccountBalance>().fetch_all(&mut *conn).await?;
+    Ok(balances
+        .into_iter()
+        .map(|b| AccountBalance {
+            balance: round_cents(b.balance),
+            available_credit: b.available_credit.map(round_cents),
+            ..b
+        })
+        .collect())
+}
+
+/// Net worth at the end of `as_of` when given, from every account including
+/// archived ones: a closed account still holds what was not transferred.
+/// Trades don't move the balance of an investment account: once it has
+/// trades, it counts the cash they left in it (its balance minus purchases
+/// plus sales, fees included) plus the market value of its holdings. Valued
+/// assets count at their depreciated value instead of their balance.
+pub async fn net_worth(conn: &mut SqliteConnection, as_of: Option<String>) -> Result<NetWorth, sqlx::Error> {
+    let holdings = crate::investments::holdings(conn, None, as_of.as_deref()).await?;
+    let trade_cash = crate::investments::trade_cash(conn, as_of.as_deref()).await?;
+    let valued_on = match &as_of {
+        Some(as_of) => as_of.clone(),
+        None => crate::reconciliation::today(conn).await?,
+    };
+    // Values replacing the balance of their account
+    let values: HashMap<String, f64> = crate::assets::values(conn, &valued_on)
+        .await?
+        .into_iter()
+        .map(|v| (v.account_id, v.value))
+        .collect();
+    let market_values: HashMap<String, f64> = crate::investments::summaries(&holdings)
+        .into_iter()
+        .map(|s| (s.account_id, s.market_value))
+        .collect();
+    let balances: Vec<(String, String, f64)> = sqlx::query_as(&format!(
+        "SELECT a.id, a.\"type\", a.\"initialBalance\" + TOTAL({SIGNED_AMOUNT})
+         FROM accounts a
+         LEFT JOIN transactions t ON t.\"accountId\" = a.id AND {FROM_OPENING} AND ($1 IS NULL OR t.date <= $1)
+         WHERE a.\"openingDate\" IS NULL OR $1 IS NULL OR a.\"openingDate\" <= $1
+         GROUP BY a.id"
+    ))
+    .bind(as_of)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut worth = NetWorth::default();
+    for (id, kind, balance) in balances {
+        let balance = match market_values.get(&id) {
+            Some(market_value) => balance + trade_cash.get(&id).copied().unwrap_or(0.0) + market_value,
+            None => values.get(&id).copied().unwrap_or(balance),
+        };
+        if is_liability(&kind) {
+            worth.liabilities -= balance;
+        } else {
+            worth.assets += balance;
+        }
+    }
+    worth.assets = round_cents(worth.assets);
+    worth.liabilities = round_cents(worth.liabilities);
+    worth.net_worth = round_cents(worth.assets - worth.liabilities);
+    Ok(worth)
+}
+
+#[derive(sqlx::FromRow)]
+struct HistoryRow {
+    date: String,
+    #[sqlx(rename = "accountId")]
+    account_id: String,
+    kind: String,
+    balance: f64,
+    valuation: Option<f64>,
+    #[sqlx(rename = "valuationDate")]
+    valuation_date: Option<String>,
+    #[sqlx(rename = "depreciationMethod")]
+    depreciation_method: Option<String>,
+    #[sqlx(rename = "depreciationRate")]
+    depreciation_rate: Option<f64>,
+    #[sqlx(rename = "tradeCash")]
+    trade_cash: f64,
+}
+
+/// Net worth at the end of each of `dates` (YYYY-MM-DD), in the same order,
+/// counted like net_worth. Balances, cash moved by trades and valuations of
+/// every account at every date come from one query over the running totals
+/// of the transactions and trades, and holdings from a single replay of the
+/// trades.
+pub async fn net_worth_history(conn: &mut SqliteConnection, dates: &[String]) -> Result<Vec<NetWorthPoint>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, HistoryRow>(&format!(
+        "WITH dates(date) AS (SELECT DISTINCT value FROM json_each($1)),
+         running AS MATERIALIZED (
+             SELECT \"accountId\", date,
+                    SUM(amount) OVER (PARTITION BY \"accountId\" ORDER BY date) AS total
+             FROM (
+                 SELECT t.\"accountId\", t.date, TOTAL({SIGNED_AMOUNT}) AS amount
+                 FROM transactions t JOIN accounts a ON a.id = t.\"accountId\"
+                 WHERE {FROM_OPENING}
+                 GROUP BY t.\"accountId\", t.date
+             )
+         ),
+         trade_running AS MATERIALIZED (
+             SELECT \"accountId\", date,
+                    SUM(amount) OVER (PARTITION BY \"accountId\" ORDER BY date) AS total
+             FROM (
+                 SELECT tr.\"accountId\", tr.date, TOTAL({TRADE_CASH}) AS amount
+                 FROM trades tr
+                 GROUP BY tr.\"accountId\", tr.date
+             )
+         )
+         SELECT d.date, a.id AS \"accountId\", a.\"type\" AS kind, a.\"initialBalance\" + COALESCE(r.total, 0) AS balance,
+                v.value AS valuation, v.date AS \"valuationDate\",
+                a.\"depreciationMethod\", a.\"depreciationRate\", COALESCE(tr.total, 0.0) AS \"tradeCash\"
+         FROM dates d
+         JOIN accounts a ON a.\"openingDate\" IS NULL OR a.\"openingDate\" <= d.date
+         LEFT JOIN running r ON r.\"accountId\" = a.id
+             AND r.date = (SELECT MAX(date) FROM running WHERE \"accountId\" = a.id AND date <= d.date)
+         LEFT JOIN trade_running tr ON tr.\"accountId\" = a.id
+             AND tr.date = (SELECT MAX(date) FROM trade_running WHERE \"accountId\" = a.id AND date <= d.date)
+         LEFT JOIN valuations v ON v.\"accountId\" = a.id
+             AND v.date = (SELECT MAX(date) FROM valuations WHERE \"accountId\" = a.id AND date <= d.date)"
+    ))
+    .bind(serde_json::to_string(dates).map_err(|e| sqlx::Error::Encode(Box::new(e)))?)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let market_values = crate::investments::market_values(conn, dates).await?;
+    // Repeated dates share the totals of the last one
+    let index: HashMap<&str, usize> = dates.iter().enumerate().map(|(i, date)| (date.as_str(), i)).collect();
+    let mut totals = vec![(0.0, 0.0); dates.len()];
+    for row in rows {
+        let i = index[row.date.as_str()];
+        let balance = match (row.valuation, &row.valuation_date) {
+            _ if market_values[i].contains_key(&row.account_id) => {
+                row.balance + row.trade_cash + market_values[i][&row.account_id]
+            }
+            (Some(value), Some(since)) => crate::assets::depreciate(
+                row.depreciation_method.as_deref(),
+                row.depreciation_rate,
+                value,
+                since,
+                &row.date,
+            ),
+            _ => row.balance,
+        };
+        if is_liability(&row.kind) {
+            totals[i].1 -= balance;
+        } else {
+            totals[i].0 += balance;
+        }
+    }
+
+    Ok(dates
+        .iter()
+        .map(|date| {
+            let (assets, liabilities) = totals[index[date.as_str()]];
+            NetWorthPoint {
+                date: date.clone(),
+                assets: round_cents(assets),
+                liabilities: round_cents(liabilities),
+                net_worth: round_cents(assets - liabilities),
+            }
+        })
+        .collect())
+}
+
+/// Transactions of an account between `from` and `to` (inclusive), oldest
+/// first, each with the account balance right after it. Transactions of the
+/// same day are taken in the order they were entered.
+pub async fn running_balance(
+    conn: &mut SqliteConnection,
+    account_id: &str,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<RunningBalance>, sqlx::Error> {
+    let mut query: QueryBuilder<Sqlite> = QueryBuilder::new(format!(
+        "SELECT * FROM (
+             SELECT t.id AS \"transactionId\", t.date, {SIGNED_AMOUNT} AS amount,
+                    a.\"initialBalance\" + SUM({SIGNED_AMOUNT}) OVER (ORDER BY t.date, t.rowid ROWS UNBOUNDED PRECEDING) AS balance,
+                    t.rowid AS position
+             FROM transactions t JOIN accounts a ON a.id = t.\"accountId\"
+             WHERE {FROM_OPENING} AND t.\"accountId\" = "
+    ));
+    query.push_bind(account_id.to_string());
+    if let Some(to) = to {
+        query.push(" AND t.date <= ").push_bind(to);
+    }
+    query.push(") WHERE 1 = 1");
+    // Earlier transactions still count towards the balance, so `from` filters the result
+    if let Some(from) = from {
+        query.push(" AND date >= ").push_bind(from);
+    }
+    query.push(" ORDER BY date, position");
+
+    let rows = query.build_query_as::<RunningBalance>().fetch_all(&mut *conn).await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| RunningBalance {
+            amount: round_cents(r.amount),
+            balance: round_cents(r.balance),
+            ..r
+        })
+        .collect())
+}
+
+// Sum of the transactions of `account` from its opening date to `date`
+async fn movements_until(conn: &mut SqliteConnection, account: &Account, date: &str) -> Result<f64, sqlx::Error> {
+    sqlx::query_scalar(&format!(
+        "SELECT TOTAL({SIGNED_AMOUNT}) FROM transactions t JOIN accounts a ON a.id = t.\"accountId\"
+         WHERE t.\"accountId\" = $1 AND t.date <= $2 AND {FROM_OPENING}"
+    ))
+    .bind(&account.id)
+    .bind(date)
+    .fetch_one(&mut *conn)
+    .await
+}
+
+/// Balance of `account` at the end of `date`.
+pub async fn balance_at(conn: &mut SqliteConnection, account: &Account, date: &str) -> Result<f64, sqlx::Error> {
+    let movements = movements_until(conn, account, date).await?;
+    Ok(round_cents(account.initial_balance + movements))
+}
+
+/// Initial balance that gives `account` the balance `balance` at the end of
+/// `date`, given its transactions between its opening date and then.
+pub async fn opening_balance_for(
+    conn: &mut SqliteConnection,
+    account: &Account,
+    date: &str,
+    balance: f64,
+) -> Result<f64, sqlx::Error> {
+    let movements = movements_until(conn, account, date).await?;
+    Ok(round_cents(balance - movements))
+}
+
+pub async fn fetch_assertions(
+    conn: &mut SqliteConnection,
+    account_id: Option<&str>,
+) -> Result<Vec<BalanceAssertion>, sqlx::Error> {
+    match account_id {
+        Some(account_id) => {
+            sqlx::query_as::<_, BalanceAssertion>("SELECT * FROM balance_assertions WHERE \"accountId\" = $1 ORDER BY date")
+                .bind(account_id)
+                .fetch_all(&mut *conn)
+                .await
+        }
+        None => {
+            sqlx::query_as::<_, BalanceAssertion>("SELECT * FROM balance_assertions ORDER BY \"accountId\", date")
+                .fetch_all(&mut *conn)
+                .await
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct AssertedBalance {
+    #[sqlx(flatten)]
+    assertion: BalanceAssertion,
+    computed: f64,
+}
+
+/// Compares each balance assertion, of one account or all of them, with the
+/// balance computed at the end of its day.
+pub async fn verify(conn: &mut SqliteConnection, account_id: Option<String>) -> Result<Vec<BalanceCheck>, sqlx::Error> {
+    let mut query: QueryBuilder<Sqlite> = QueryBuilder::new(format!(
+        "SELECT b.*,
+                a.\"initialBalance\" + (SELECT TOTAL({SIGNED_AMOUNT}) FROM transactions t
+                                        WHERE t.\"accountId\" = b.\"accountId\" AND t.date <= b.date AND {FROM_OPENING}) AS computed
+         FROM balance_assertions b JOIN accounts a ON a.id = b.\"accountId\""
+    ));
+    if let Some(account_id) = account_id {
+        query.push(" WHERE b.\"accountId\" = ").push_bind(account_id);
+    }
+    query.push(" ORDER BY b.\"accountId\", b.date");
+
+    let rows = query.build_query_as::<AssertedBalance>().fetch_all(&mut *conn).await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let computed = round_cents(row.computed);
+            BalanceCheck {
+                discrepancy: round_cents(row.assertion.balance - computed),
+                computed,
+                assertion: row.assertion,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    async fn seed(conn: &mut SqliteConnection) {
+        for sql in [
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'Courant', 'checking', 100), ('a2', 'Livret', 'savings', 50)",
+            "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, status) VALUES
+             ('t3', '2026-01-20', 'a1', 'expense', 0.3, 'pending'),
+             ('t1', '2026-01-05', 'a1', 'income', 0.1, 'reconciled'),
+             ('t2', '2026-01-05', 'a1', 'expense', 0.2, 'cleared'),
+             ('t4', '2026-02-01', 'a1', 'expense', 10, 'cleared')",
+        ] {
+            sqlx::query(sql).execute(&mut *conn).await.unwrap();
+        }
+    }
+
+    #[test]
+    fn rounds_to_the_cent() {
+        assert_eq!(round_cents(0.1 + 0.2), 0.3);
+        assert_eq!(round_cents(-12.345), -12.35);
+    }
+
+    #[tokio::test]
+    async fn balances_by_status_and_date() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+
+        let statuses = status_balances(&mut conn, false).await.unwrap();
+        let a1 = statuses.iter().find(|b| b.account_id == "a1").unwrap();
+        assert_eq!((a1.reconciled, a1.cleared, a1.pending, a1.total), (100.1, 89.9, -0.3, 89.6));
+
+        let balances = |rows: Vec<AccountBalance>| {
+            let mut rows: Vec<(String, f64)> = rows.into_iter().map(|b| (b.account_id, b.balance)).collect();
+            rows.sort_by(|a, b| a.0.cmp(&b.0));
+            rows
+        };
+        let january = account_balances(&mut conn, Some("2026-01-31".to_string()), false, false).await.unwrap();
+        assert_eq!(balances(january), [("a1".to_string(), 99.6), ("a2".to_string(), 50.0)]);
+        let cleared = account_balances(&mut conn, Some("2026-01-31".to_string()), true, false).await.unwrap();
+        assert_eq!(balances(cleared), [("a1".to_string(), 99.9), ("a2".to_string(), 50.0)]);
+    }
+
+    #[tokio::test]
+    async fn running_balance_counts_rows_before_the_range() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+
+        let rows = running_balance(&mut conn, "a1", Some("2026-01-06".to_string()), None).await.unwrap();
+        let rows: Vec<(&str, f64)> = rows.iter().map(|r| (r.transaction_id.as_str(), r.balance)).collect();
+        assert_eq!(rows, [("t3", 99.6), ("t4", 89.6)]);
+
+        // Same-day rows keep their entry order
+        let rows = running_balance(&mut conn, "a1", None, Some("2026-01-05".to_string())).await.unwrap();
+        let rows: Vec<(&str, f64)> = rows.iter().map(|r| (r.transaction_id.as_str(), r.balance)).collect();
+        assert_eq!(rows, [("t1", 100.1), ("t2", 99.9)]);
+    }
+
+    #[tokio::test]
+    async fn rows_before_the_opening_date_are_left_out() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+        sqlx::query("UPDATE accounts SET \"openingDate\" = '2026-01-10' WHERE id = 'a1'")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+
+        let balances = account_balances(&mut conn, None, false, false).await.unwrap();
+        assert_eq!(balances.iter().find(|b| b.account_id == "a1").unwrap().balance, 89.7);
+        let rows = running_balance(&mut conn, "a1", None, None).await.unwrap();
+        assert_eq!(rows.iter().map(|r| r.transaction_id.as_str()).collect::<Vec<_>>(), ["t3", "t4"]);
+
+        // 200 at the end of January means 200.3 before the pending expense of the 20th
+        let account = crate::audit::fetch_account(&mut conn, "a1").await.unwrap().unwrap();
+        assert_eq!(opening_balance_for(&mut conn, &account, "2026-01-31", 200.0).await.unwrap(), 200.3);
+    }
+
+    #[tokio::test]
+    async fn assertions_report_their_discrepancy() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+        sqlx::query(
+            "INSERT INTO balance_assertions (id, \"accountId\", date, balance) VALUES
+             ('b1', 'a1', '2026-01-05', 99.9), ('b2', 'a1', '2026-02-01', 90), ('b3', 'a2', '2026-01-01', 50)",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+        let checks = verify(&mut conn, Some("a1".to_string())).await.unwrap();
+        let checks: Vec<(&str, f64, f64)> =
+            checks.iter().map(|c| (c.assertion.id.as_str(), c.computed, c.discrepancy)).collect();
+        assert_eq!(checks, [("b1", 99.9, 0.0), ("b2", 89.6, 0.4)]);
+        assert_eq!(verify(&mut conn, None).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn archived_accounts_are_left_out_unless_asked() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+        sqlx::query("UPDATE accounts SET archived = 1, \"closedOn\" = '2026-01-31' WHERE id = 'a2'")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+
+        let ids = |balances: Vec<AccountBalance>| balances.into_iter().map(|b| b.account_id).collect::<Vec<_>>();
+        assert_eq!(ids(account_balances(&mut conn, None, false, false).await.unwrap()), ["a1"]);
+        assert_eq!(account_balances(&mut conn, None, false, true).await.unwrap().len(), 2);
+        assert_eq!(status_balances(&mut conn, false).await.unwrap().len(), 1);
+        assert_eq!(status_balances(&mut conn, true).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn liabilities_count_against_net_worth() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+        sqlx::query(
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\", \"openingDate\") VALUES ('c1', 'Carte', 'credit_card', 0, '2026-01-15');
+             INSERT INTO transactions (id, date, \"accountId\", \"type\", amount) VALUES ('t5', '2026-01-20', 'c1', 'expense', 40)",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+        let worth = net_worth(&mut conn, None).await.unwrap();
+        assert_eq!((worth.assets, worth.liabilities, worth.net_worth), (139.6, 40.0, 99.6));
+        let before_the_card = net_worth(&mut conn, Some("2026-01-10".to_string())).await.unwrap();
+        assert_eq!((before_the_card.assets, before_the_card.liabilities), (149.9, 0.0));
+    }
+
+    #[tokio::test]
+    async fn investment_accounts_count_their_cash_and_holdings() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        sqlx::query(
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'PEA', 'investment', 0);
+             INSERT INTO transactions (id, date, \"accountId\", \"type\", amount) VALUES ('t1', '2026-01-05', 'a1', 'income', 1500);
+             INSERT INTO securities (id, symbol, name) VALUES ('s1', 'CW8', 'MSCI World');
+             INSERT INTO trades (id, \"accountId\", \"securityId\", date, \"type\", quantity, price, fees) VALUES
+             ('b1', 'a1', 's1', '2026-01-10', 'buy', 10, 100, 5), ('s1', 'a1', 's1', '2026-03-10', 'sell', 4, 120, 2);
+             INSERT INTO prices (id, \"securityId\", date, price) VALUES ('s1:2026-02-01', 's1', '2026-02-01', 110)",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+        // 1500 deposited, 1005 spent on the purchase, 478 back from the sale
+        let dates = ["2026-01-07", "2026-02-15", "2026-03-31"].map(str::to_string);
+        let expected = [1500.0, 495.0 + 1100.0, 973.0 + 720.0];
+        let history = net_worth_history(&mut conn, &dates).await.unwrap();
+        for ((date, point), expected) in dates.iter().zip(&history).zip(expected) {
+            let worth = net_worth(&mut conn, Some(date.clone())).await.unwrap();
+            assert_eq!((worth.net_worth, point.net_worth), (expected, expected), "{date}");
+        }
+    }
+}
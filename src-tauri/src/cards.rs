@@ -0,0 +1,140 @@
+use crate::balances::{balance_at, round_cents};
+use crate::dates::{day_of_month, format_date, next_day, parse_date, Date};
+use crate::models::{Account, CardStatement};
+use sqlx::SqliteConnection;
+
+/// Share of the statement balance due at least each month, and the amount
+/// below which the whole balance is due.
+pub const MINIMUM_PAYMENT_RATE: f64 = 0.03;
+pub const MINIMUM_PAYMENT_FLOOR: f64 = 20.0;
+
+/// Last statement closing date on or before `as_of`.
+fn last_closing(as_of: Date, closing_day: u32) -> Date {
+    let this_month = day_of_month(as_of, 0, closing_day);
+    if this_month <= as_of {
+        this_month
+    } else {
+        day_of_month(as_of, -1, closing_day)
+    }
+}
+
+/// First due date after a closing date.
+fn due_after(closing: Date, due_day: u32) -> Date {
+    let same_month = day_of_month(closing, 0, due_day);
+    if same_month > closing {
+        same_month
+    } else {
+        day_of_month(closing, 1, due_day)
+    }
+}
+
+/// Next payment due date on or after `date` (YYYY-MM-DD).
+pub fn next_due_date(date: &str, closing_day: u32, due_day: u32) -> Option<String> {
+    let date = parse_date(date)?;
+    let closing = last_closing(date, closing_day);
+    let due = due_after(closing, due_day);
+    if due >= date {
+        Some(format_date(due))
+    } else {
+        Some(format_date(due_after(day_of_month(closing, 1, closing_day), due_day)))
+    }
+}
+
+/// Statement of a credit card last closed on or before `as_of`
+/// (YYYY-MM-DD), with what was paid on it up to `as_of`. The card needs its
+/// closing and due days.
+pub async fn statement(
+    conn: &mut SqliteConnection,
+    card: &Account,
+    closing_day: u32,
+    due_day: u32,
+    as_of: &str,
+) -> Result<CardStatement, String> {
+    let date = parse_date(as_of).ok_or_else(|| format!("Date invalide : {as_of}"))?;
+    let closing = last_closing(date, closing_day);
+    let previous = day_of_month(closing, -1, closing_day);
+    let closing_date = format_date(closing);
+
+    let owed = -balance_at(conn, card, &closing_date)
+        .await
+        .map_err(|e| format!("Erreur BDD (calcul du relevé): {e}"))?;
+    let paid: f64 = sqlx::query_scalar(
+        "SELECT TOTAL(amount) FROM transactions WHERE \"accountId\" = $1 AND \"type\" = 'income' AND date > $2 AND date <= $3",
+    )
+    .bind(&card.id)
+    .bind(&closing_date)
+    .bind(as_of)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| format!("Erreur BDD (calcul du relevé): {e}"))?;
+
+    let statement_balance = round_cents(owed);
+    let remaining = round_cents((owed - paid).max(0.0));
+    let minimum_payment = if statement_balance <= 0.0 {
+        0.0
+    } else {
+        let minimum = (statement_balance * MINIMUM_PAYMENT_RATE).max(MINIMUM_PAYMENT_FLOOR.min(statement_balance));
+        round_cents((minimum - paid).clamp(0.0, remaining))
+    };
+
+    Ok(CardStatement {
+        account_id: card.id.clone(),
+        period_start: format_date(next_day(previous)),
+        closing_date,
+        due_date: format_date(due_after(closing, due_day)),
+        statement_balance,
+        paid: round_cents(paid),
+        remaining,
+        minimum_payment,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    #[test]
+    fn due_date_follows_the_last_closing() {
+        assert_eq!(next_due_date("2026-01-05", 25, 10).as_deref(), Some("2026-01-10"));
+        assert_eq!(next_due_date("2026-01-10", 25, 10).as_deref(), Some("2026-01-10"));
+        assert_eq!(next_due_date("2026-01-12", 25, 10).as_deref(), Some("2026-02-10"));
+        assert_eq!(next_due_date("2026-02-10", 5, 28).as_deref(), Some("2026-02-28"));
+    }
+
+    #[test]
+    fn days_clamp_to_month_end() {
+        assert_eq!(next_due_date("2026-02-20", 31, 15).as_deref(), Some("2026-03-15"));
+        assert_eq!(next_due_date("2026-02-10", 5, 31).as_deref(), Some("2026-02-28"));
+        assert_eq!(next_due_date("2024-02-10", 5, 31).as_deref(), Some("2024-02-29"));
+    }
+
+    #[test]
+    fn due_date_crosses_years() {
+        assert_eq!(next_due_date("2026-12-28", 25, 10).as_deref(), Some("2027-01-10"));
+        assert_eq!(next_due_date("2026-13-01", 25, 10), None);
+    }
+
+    #[tokio::test]
+    async fn statement_deducts_what_was_paid_since_closing() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        sqlx::query(
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('c1', 'Carte', 'credit_card', 0);
+             INSERT INTO transactions (id, date, \"accountId\", \"type\", amount) VALUES
+             ('t1', '2026-01-10', 'c1', 'expense', 300), ('t2', '2026-01-30', 'c1', 'expense', 50),
+             ('t3', '2026-02-01', 'c1', 'income', 100)",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+        let card = crate::audit::fetch_account(&mut conn, "c1").await.unwrap().unwrap();
+
+        let statement = statement(&mut conn, &card, 25, 10, "2026-02-05").await.unwrap();
+        assert_eq!((statement.period_start.as_str(), statement.closing_date.as_str()), ("2025-12-26", "2026-01-25"));
+        assert_eq!(statement.due_date, "2026-02-10");
+        assert_eq!((statement.statement_balance, statement.paid, statement.remaining), (300.0, 100.0, 200.0));
+        assert_eq!(statement.minimum_payment, 0.0);
+        assert!(super::statement(&mut conn, &card, 25, 10, "2026-02-30").await.is_err());
+    }
+}
@@ -1,9 +1,32 @@
+use crate::accounts;
+use crate::assets;
+use crate::attachments;
+use crate::audit;
+use crate::balances;
+use crate::cards;
+use crate::dates;
 use crate::db::DbPool;
+use crate::goals;
+use crate::history;
+use crate::integrity;
+use crate::investments;
+use crate::loans;
+use crate::reports;
+use crate::payees;
+use crate::reconciliation;
+use crate::rules;
+use crate::suggestions;
+use crate::tags;
 use crate::models::{
-    Account, AppData, Category, ScheduledTransaction, Settings, Transaction, WindowPosition,
-    WindowSize,
+    Account, AccountBalance, AccountStatusBalances, AmortizationRow, AppData, AssetValue, Attachment, AuditEntry, BalanceAssertion, BalanceCheck, BulkUpdateResult, CardStatement, Category, ClosingTransfer, CategorySuggestion, CategoryTotal, Goal, GoalProgress, HistoryEntry, Holding, IntegrityIssue, IntegrityRepair,
+    InvestmentSummary, LoanPayment, NetWorth, NetWorthPoint, Payee, PayeeTotal, Price, Reconciliation, ReconciliationStatus, Rule, RunningBalance, ScheduledTransaction, Security, Settings, Tag, Trade, Transaction, TransactionBulkUpdate, TransactionFilter,
+    TransactionTag, Valuation,
+    KIND_ASSET, KIND_CHECKING, KIND_CREDIT_CARD, KIND_INVESTMENT, KIND_LOAN, STATUS_CLEARED, STATUS_PENDING, STATUS_RECONCILED, TRANSFER_CATEGORY_ID, UNCATEGORIZED_CATEGORY_ID,
 };
-use tauri::{command, State};
+use sqlx::{QueryBuilder, Sqlite};
+use std::collections::HashMap;
+use tauri::{command, AppHandle, State};
+use tauri_plugin_opener::OpenerExt;
 
 // Helper to map SQLx errors to user-friendly strings
 fn map_db_error(e: sqlx::Error, context: &str) -> String {
@@ -21,10 +44,12 @@ fn map_db_error(e: sqlx::Error, context: &str) -> String {
 }
 
 // --- Accounts ---
+/// Lists the accounts; archived ones only with `include_archived`.
 #[command]
-pub async fn get_accounts(pool: State<'_, DbPool>) -> Result<Vec<Account>, String> {
-    log::debug!("Invoked get_accounts");
-    sqlx::query_as::<_, Account>("SELECT * FROM accounts")
+pub async fn get_accounts(pool: State<'_, DbPool>, include_archived: Option<bool>) -> Result<Vec<Account>, String> {
+    log::debug!("Invoked get_accounts: include_archived={include_archived:?}");
+    sqlx::query_as::<_, Account>("SELECT * FROM accounts WHERE $1 OR NOT archived")
+        .bind(include_archived.unwrap_or(false))
         .fetch_all(&*pool)
         .await
         .map_err(|e| map_db_error(e, "récupération des comptes"))
@@ -33,44 +58,370 @@ pub async fn get_accounts(pool: State<'_, DbPool>) -> Result<Vec<Account>, Strin
 #[command]
 pub async fn add_account(pool: State<'_, DbPool>, account: Account) -> Result<(), String> {
     log::debug!("Invoked add_account: {account:?}");
-    sqlx::query(
-        "INSERT OR IGNORE INTO accounts (id, name, \"type\", \"initialBalance\", color, icon) VALUES ($1, $2, $3, $4, $5, $6)"
+    accounts::validate(&account)?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("add_account");
+
+    let result = sqlx::query(
+        "INSERT OR IGNORE INTO accounts (id, name, \"type\", \"initialBalance\", color, icon, \"openingDate\", archived, \"closedOn\", \"creditLimit\", \"statementClosingDay\", \"paymentDueDay\", \"loanPrincipal\", \"loanRate\", \"loanTermMonths\", \"loanStartDate\", \"depreciationMethod\", \"depreciationRate\") VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)"
     )
-    .bind(account.id)
-    .bind(account.name)
-    .bind(account.account_type)
+    .bind(&account.id)
+    .bind(&account.name)
+    .bind(&account.account_type)
     .bind(account.initial_balance)
-    .bind(account.color)
-    .bind(account.icon)
-    .execute(&*pool)
+    .bind(&account.color)
+    .bind(&account.icon)
+    .bind(&account.opening_date)
+    .bind(account.archived)
+    .bind(&account.closed_on)
+    .bind(account.credit_limit)
+    .bind(account.statement_closing_day)
+    .bind(account.payment_due_day)
+    .bind(account.loan_principal)
+    .bind(account.loan_rate)
+    .bind(account.loan_term_months)
+    .bind(&account.loan_start_date)
+    .bind(&account.depreciation_method)
+    .bind(account.depreciation_rate)
+    .execute(&mut *tx)
     .await
     .map_err(|e| map_db_error(e, "ajout du compte"))?;
+
+    if result.rows_affected() > 0 {
+        audit::record(&mut tx, &mut op, audit::ENTITY_ACCOUNT, &account.id, audit::ACTION_CREATE, None, Some(&account))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de l'ajout du compte"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
     Ok(())
 }
 
+/// Updates an account. Closing and reopening go through close_account and
+/// reopen_account, so the archived state is kept as stored.
 #[command]
-pub async fn update_account(pool: State<'_, DbPool>, account: Account) -> Result<(), String> {
+pub async fn update_account(pool: State<'_, DbPool>, mut account: Account) -> Result<(), String> {
     log::debug!("Invoked update_account: {account:?}");
+    accounts::validate(&account)?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("update_account");
+
+    let before = audit::fetch_account(&mut tx, &account.id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du compte"))?;
+    if let Some(before) = &before {
+        account.archived = before.archived;
+        account.closed_on = before.closed_on.clone();
+    }
+
     sqlx::query(
-        "UPDATE accounts SET name = $1, \"type\" = $2, \"initialBalance\" = $3, color = $4, icon = $5 WHERE id = $6"
+        "UPDATE accounts SET name = $1, \"type\" = $2, \"initialBalance\" = $3, color = $4, icon = $5, \"openingDate\" = $6, \"creditLimit\" = $7, \"statementClosingDay\" = $8, \"paymentDueDay\" = $9, \"loanPrincipal\" = $10, \"loanRate\" = $11, \"loanTermMonths\" = $12, \"loanStartDate\" = $13, \"depreciationMethod\" = $14, \"depreciationRate\" = $15 WHERE id = $16"
     )
-    .bind(account.name)
-    .bind(account.account_type)
+    .bind(&account.name)
+    .bind(&account.account_type)
     .bind(account.initial_balance)
-    .bind(account.color)
-    .bind(account.icon)
-    .bind(account.id)
-    .execute(&*pool)
+    .bind(&account.color)
+    .bind(&account.icon)
+    .bind(&account.opening_date)
+    .bind(account.credit_limit)
+    .bind(account.statement_closing_day)
+    .bind(account.payment_due_day)
+    .bind(account.loan_principal)
+    .bind(account.loan_rate)
+    .bind(account.loan_term_months)
+    .bind(&account.loan_start_date)
+    .bind(&account.depreciation_method)
+    .bind(account.depreciation_rate)
+    .bind(&account.id)
+    .execute(&mut *tx)
     .await
     .map_err(|e| map_db_error(e, "mise à jour du compte"))?;
+
+    if before.is_some() {
+        audit::record(&mut tx, &mut op, audit::ENTITY_ACCOUNT, &account.id, audit::ACTION_UPDATE, before.as_ref(), Some(&account))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la mise à jour du compte"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
     Ok(())
 }
 
+/// Sets the initial balance of an account so that its balance at the end of
+/// `date` (YYYY-MM-DD) is `balance`, e.g. as read on a statement. Returns
+/// the updated account.
+#[command]
+pub async fn rebase_opening_balance(
+    pool: State<'_, DbPool>,
+    account_id: String,
+    date: String,
+    balance: f64,
+) -> Result<Account, String> {
+    log::debug!("Invoked rebase_opening_balance: {account_id} {date} = {balance}");
+    if dates::parse_date(&date).is_none() {
+        return Err(format!("Date invalide : {date}"));
+    }
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("rebase_opening_balance");
+
+    let Some(before) = audit::fetch_account(&mut tx, &account_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du compte"))?
+    else {
+        return Err("Compte introuvable.".to_string());
+    };
+    if before.opening_date.as_deref().is_some_and(|opening| date.as_str() < opening) {
+        return Err("La date précède l'ouverture du compte.".to_string());
+    }
+
+    let initial_balance = balances::opening_balance_for(&mut tx, &before, &date, balance)
+        .await
+        .map_err(|e| map_db_error(e, "calcul du solde d'ouverture"))?;
+    let after = Account {
+        initial_balance,
+        ..before.clone()
+    };
+    history::upsert_account(&mut tx, &after)
+        .await
+        .map_err(|e| map_db_error(e, "mise à jour du compte"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_ACCOUNT, &after.id, audit::ACTION_UPDATE, Some(&before), Some(&after))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de la mise à jour du compte"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(after)
+}
+
+/// Closes an account on `closed_on` (YYYY-MM-DD) and archives it. With
+/// `transfer`, the balance left on that day is moved to another account so
+/// the closed account ends at zero. Returns the archived account.
+#[command]
+pub async fn close_account(
+    pool: State<'_, DbPool>,
+    id: String,
+    closed_on: String,
+    transfer: Option<ClosingTransfer>,
+) -> Result<Account, String> {
+    log::debug!("Invoked close_account: {id} on {closed_on}, transfer={transfer:?}");
+    if dates::parse_date(&closed_on).is_none() {
+        return Err(format!("Date invalide : {closed_on}"));
+    }
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("close_account");
+
+    let Some(before) = audit::fetch_account(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du compte"))?
+    else {
+        return Err("Compte introuvable.".to_string());
+    };
+    if before.archived {
+        return Err("Ce compte est déjà clôturé.".to_string());
+    }
+    if before.opening_date.as_deref().is_some_and(|opening| closed_on.as_str() < opening) {
+        return Err("La date précède l'ouverture du compte.".to_string());
+    }
+
+    let later: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transactions WHERE \"accountId\" = $1 AND date > $2")
+        .bind(&id)
+        .bind(&closed_on)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des transactions du compte"))?;
+    if later > 0 {
+        return Err("Des transactions sont postérieures à la date de clôture.".to_string());
+    }
+    // They would keep adding transactions to the closed account
+    let scheduled: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM scheduled_transactions WHERE \"accountId\" = $1 OR \"toAccountId\" = $1"
+    )
+    .bind(&id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| map_db_error(e, "lecture des échéances du compte"))?;
+    if scheduled > 0 {
+        return Err("Des échéances utilisent encore ce compte.".to_string());
+    }
+
+    if let Some(transfer) = &transfer {
+        if transfer.to_account_id == id {
+            return Err("Le solde doit être viré vers un autre compte.".to_string());
+        }
+        let target = audit::fetch_account(&mut tx, &transfer.to_account_id)
+            .await
+            .map_err(|e| map_db_error(e, "lecture du compte de destination"))?;
+        let target_open = target.is_some_and(|target| !target.archived);
+        if !target_open {
+            return Err("Le compte de destination est introuvable ou clôturé.".to_string());
+        }
+
+        let balance = balances::balance_at(&mut tx, &before, &closed_on)
+            .await
+            .map_err(|e| map_db_error(e, "calcul du solde"))?;
+        if balance != 0.0 {
+            // A negative balance (e.g. a credit card) is paid off from the other account
+            let (leg_type, linked_type) = if balance > 0.0 { ("expense", "income") } else { ("income", "expense") };
+            let description = Some(format!("Clôture du compte {}", before.name));
+            let leg = Transaction {
+                id: transfer.transaction_id.clone(),
+                date: closed_on.clone(),
+                account_id: id.clone(),
+                transaction_type: leg_type.to_string(),
+                amount: balance.abs(),
+                category: TRANSFER_CATEGORY_ID.to_string(),
+                description,
+                status: STATUS_PENDING.to_string(),
+                cleared_at: None,
+                reconciled_at: None,
+                is_transfer: true,
+                linked_transaction_id: Some(transfer.linked_transaction_id.clone()),
+                payee_id: None,
+                reconciliation_id: None,
+            };
+            let linked = Transaction {
+                id: transfer.linked_transaction_id.clone(),
+                account_id: transfer.to_account_id.clone(),
+                transaction_type: linked_type.to_string(),
+                linked_transaction_id: Some(transfer.transaction_id.clone()),
+                ..leg.clone()
+            };
+            for transaction in [&leg, &linked] {
+                insert_transaction(&mut tx, transaction)
+                    .await
+                    .map_err(|e| map_db_error(e, "virement du solde"))?;
+                audit::record(&mut tx, &mut op, audit::ENTITY_TRANSACTION, &transaction.id, audit::ACTION_CREATE, None, Some(transaction))
+                    .await
+                    .map_err(|e| map_db_error(e, "journalisation du virement du solde"))?;
+            }
+        }
+    }
+
+    let after = Account {
+        archived: true,
+        closed_on: Some(closed_on),
+        ..before.clone()
+    };
+    history::upsert_account(&mut tx, &after)
+        .await
+        .map_err(|e| map_db_error(e, "clôture du compte"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_ACCOUNT, &id, audit::ACTION_UPDATE, Some(&before), Some(&after))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de la clôture du compte"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(after)
+}
+
+/// Reopens a closed account. A transfer made when closing it stays in place.
+#[command]
+pub async fn reopen_account(pool: State<'_, DbPool>, id: String) -> Result<Account, String> {
+    log::debug!("Invoked reopen_account: {id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("reopen_account");
+
+    let Some(before) = audit::fetch_account(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du compte"))?
+    else {
+        return Err("Compte introuvable.".to_string());
+    };
+    if !before.archived {
+        return Err("Ce compte n'est pas clôturé.".to_string());
+    }
+
+    let after = Account {
+        archived: false,
+        closed_on: None,
+        ..before.clone()
+    };
+    history::upsert_account(&mut tx, &after)
+        .await
+        .map_err(|e| map_db_error(e, "réouverture du compte"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_ACCOUNT, &id, audit::ACTION_UPDATE, Some(&before), Some(&after))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de la réouverture du compte"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(after)
+}
+
 #[command]
 pub async fn delete_account(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
     log::debug!("Invoked delete_account: {id}");
     // Transactional delete
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("delete_account");
+
+    // Snapshot everything removed along with the account
+    let transactions = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE \"accountId\" = $1")
+        .bind(&id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des transactions liées"))?;
+
+    let scheduled = sqlx::query_as::<_, ScheduledTransaction>("SELECT * FROM scheduled_transactions WHERE \"accountId\" = $1")
+        .bind(&id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des échéances liées"))?;
+
+    let account = audit::fetch_account(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du compte"))?;
+
+    // Transfer legs in other accounts and scheduled transfers towards this
+    // account are unlinked here rather than by ON DELETE SET NULL so the
+    // change is journaled and can be undone.
+    let linked = sqlx::query_as::<_, Transaction>(
+        "SELECT * FROM transactions WHERE \"accountId\" != $1 AND \"linkedTransactionId\" IN (SELECT id FROM transactions WHERE \"accountId\" = $1)"
+    )
+    .bind(&id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| map_db_error(e, "lecture des virements liés"))?;
+
+    for before in &linked {
+        let after = Transaction {
+            linked_transaction_id: None,
+            ..before.clone()
+        };
+        history::upsert_transaction(&mut tx, &after)
+            .await
+            .map_err(|e| map_db_error(e, "détachement des virements liés"))?;
+        audit::record(&mut tx, &mut op, audit::ENTITY_TRANSACTION, &after.id, audit::ACTION_UPDATE, Some(before), Some(&after))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation du détachement des virements liés"))?;
+    }
+
+    let incoming = sqlx::query_as::<_, ScheduledTransaction>(
+        "SELECT * FROM scheduled_transactions WHERE \"accountId\" != $1 AND \"toAccountId\" = $1"
+    )
+    .bind(&id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| map_db_error(e, "lecture des échéances liées"))?;
+
+    for before in &incoming {
+        let after = ScheduledTransaction {
+            to_account_id: None,
+            ..before.clone()
+        };
+        history::upsert_scheduled(&mut tx, &after)
+            .await
+            .map_err(|e| map_db_error(e, "détachement des échéances liées"))?;
+        audit::record(&mut tx, &mut op, audit::ENTITY_SCHEDULED, &after.id, audit::ACTION_UPDATE, Some(before), Some(&after))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation du détachement des échéances liées"))?;
+    }
+
+    for t in &transactions {
+        tags::unlink_transaction(&mut tx, &mut op, &t.id)
+            .await
+            .map_err(|e| map_db_error(e, "retrait des tags liés"))?;
+        attachments::delete_for_transaction(&mut tx, &mut op, &t.id)
+            .await
+            .map_err(|e| map_db_error(e, "suppression des pièces jointes liées"))?;
+    }
 
     sqlx::query("DELETE FROM transactions WHERE \"accountId\" = $1")
         .bind(&id)
@@ -84,207 +435,3058 @@ pub async fn delete_account(pool: State<'_, DbPool>, id: String) -> Result<(), S
         .await
         .map_err(|e| map_db_error(e, "suppression des échéances liées"))?;
 
+    let rules = sqlx::query_as::<_, Rule>("SELECT * FROM rules WHERE \"accountId\" = $1")
+        .bind(&id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des règles liées"))?;
+
+    sqlx::query("DELETE FROM rules WHERE \"accountId\" = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression des règles liées"))?;
+
+    // After the transactions, which may point to them
+    let reconciliations = reconciliation::fetch_reconciliations(&mut tx, Some(&id))
+        .await
+        .map_err(|e| map_db_error(e, "lecture des rapprochements liés"))?;
+
+    sqlx::query("DELETE FROM reconciliations WHERE \"accountId\" = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression des rapprochements liés"))?;
+
+    let assertions = balances::fetch_assertions(&mut tx, Some(&id))
+        .await
+        .map_err(|e| map_db_error(e, "lecture des soldes constatés liés"))?;
+
+    sqlx::query("DELETE FROM balance_assertions WHERE \"accountId\" = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression des soldes constatés liés"))?;
+
+    let valuations = assets::fetch_valuations(&mut tx, Some(&id))
+        .await
+        .map_err(|e| map_db_error(e, "lecture des estimations liées"))?;
+
+    sqlx::query("DELETE FROM valuations WHERE \"accountId\" = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression des estimations liées"))?;
+
+    let trades = investments::fetch_trades(&mut tx, Some(&id))
+        .await
+        .map_err(|e| map_db_error(e, "lecture des opérations sur titres liées"))?;
+
+    sqlx::query("DELETE FROM trades WHERE \"accountId\" = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression des opérations sur titres liées"))?;
+
+    let goals = sqlx::query_as::<_, Goal>("SELECT * FROM goals WHERE \"accountId\" = $1")
+        .bind(&id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des objectifs liés"))?;
+
+    sqlx::query("DELETE FROM goals WHERE \"accountId\" = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression des objectifs liés"))?;
+
     sqlx::query("DELETE FROM accounts WHERE id = $1")
         .bind(&id)
         .execute(&mut *tx)
         .await
         .map_err(|e| map_db_error(e, "suppression du compte"))?;
 
+    for t in &transactions {
+        audit::record(&mut tx, &mut op, audit::ENTITY_TRANSACTION, &t.id, audit::ACTION_DELETE, Some(t), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression des transactions liées"))?;
+    }
+    for s in &scheduled {
+        audit::record(&mut tx, &mut op, audit::ENTITY_SCHEDULED, &s.id, audit::ACTION_DELETE, Some(s), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression des échéances liées"))?;
+    }
+    for r in &rules {
+        audit::record(&mut tx, &mut op, audit::ENTITY_RULE, &r.id, audit::ACTION_DELETE, Some(r), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression des règles liées"))?;
+    }
+    for r in &reconciliations {
+        audit::record(&mut tx, &mut op, audit::ENTITY_RECONCILIATION, &r.id, audit::ACTION_DELETE, Some(r), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression des rapprochements liés"))?;
+    }
+    for a in &assertions {
+        audit::record(&mut tx, &mut op, audit::ENTITY_BALANCE_ASSERTION, &a.id, audit::ACTION_DELETE, Some(a), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression des soldes constatés liés"))?;
+    }
+    for v in &valuations {
+        audit::record(&mut tx, &mut op, audit::ENTITY_VALUATION, &v.id, audit::ACTION_DELETE, Some(v), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression des estimations liées"))?;
+    }
+    for t in &trades {
+        audit::record(&mut tx, &mut op, audit::ENTITY_TRADE, &t.id, audit::ACTION_DELETE, Some(t), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression des opérations sur titres liées"))?;
+    }
+    for g in &goals {
+        audit::record(&mut tx, &mut op, audit::ENTITY_GOAL, &g.id, audit::ACTION_DELETE, Some(g), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression des objectifs liés"))?;
+    }
+    if let Some(account) = &account {
+        audit::record(&mut tx, &mut op, audit::ENTITY_ACCOUNT, &id, audit::ACTION_DELETE, Some(account), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression du compte"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Balance of every account, as of a date (YYYY-MM-DD, inclusive) when
+/// given. With `cleared_only`, pending transactions are left out. Archived
+/// accounts are only listed with `include_archived`.
+#[command]
+pub async fn get_account_balances(
+    pool: State<'_, DbPool>,
+    as_of: Option<String>,
+    cleared_only: Option<bool>,
+    include_archived: Option<bool>,
+) -> Result<Vec<AccountBalance>, String> {
+    log::debug!("Invoked get_account_balances: as_of={as_of:?}, cleared_only={cleared_only:?}, include_archived={include_archived:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    balances::account_balances(&mut conn, as_of, cleared_only.unwrap_or(false), include_archived.unwrap_or(false))
+        .await
+        .map_err(|e| map_db_error(e, "calcul des soldes"))
+}
+
+/// Transactions of an account in a date range, oldest first, each with the
+/// balance right after it. Transactions before `from` count towards it.
+#[command]
+pub async fn get_running_balance(
+    pool: State<'_, DbPool>,
+    account_id: String,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<RunningBalance>, String> {
+    log::debug!("Invoked get_running_balance: {account_id} from={from:?} to={to:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    if audit::fetch_account(&mut conn, &account_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du compte"))?
+        .is_none()
+    {
+        return Err("Compte introuvable.".to_string());
+    }
+    balances::running_balance(&mut conn, &account_id, from, to)
+        .await
+        .map_err(|e| map_db_error(e, "calcul du solde courant"))
+}
+
+/// Balances of every account by transaction status: reconciled, cleared
+/// (including reconciled), still pending, and total. Archived accounts are
+/// only listed with `include_archived`.
+#[command]
+pub async fn get_status_balances(
+    pool: State<'_, DbPool>,
+    include_archived: Option<bool>,
+) -> Result<Vec<AccountStatusBalances>, String> {
+    log::debug!("Invoked get_status_balances: include_archived={include_archived:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    balances::status_balances(&mut conn, include_archived.unwrap_or(false))
+        .await
+        .map_err(|e| map_db_error(e, "calcul des soldes"))
+}
+
+/// Net worth at the end of `as_of` (YYYY-MM-DD) when given, otherwise with
+/// every transaction: assets minus what is owed on credit cards, loans and
+/// other liabilities.
+#[command]
+pub async fn get_net_worth(pool: State<'_, DbPool>, as_of: Option<String>) -> Result<NetWorth, String> {
+    log::debug!("Invoked get_net_worth: as_of={as_of:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    balances::net_worth(&mut conn, as_of)
+        .await
+        .map_err(|e| map_db_error(e, "calcul du patrimoine"))
+}
+
+/// Net worth at the end of each day, week, month (the default), quarter or
+/// year between `from` and `to` (YYYY-MM-DD), and at `to`, for charting.
+#[command]
+pub async fn get_net_worth_history(
+    pool: State<'_, DbPool>,
+    from: String,
+    to: String,
+    interval: Option<String>,
+) -> Result<Vec<NetWorthPoint>, String> {
+    log::debug!("Invoked get_net_worth_history: {from} to {to}, interval={interval:?}");
+    let dates = dates::period_ends(&from, &to, interval.as_deref().unwrap_or("month"))?;
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    balances::net_worth_history(&mut conn, &dates)
+        .await
+        .map_err(|e| map_db_error(e, "calcul de l'historique du patrimoine"))
+}
+
+// --- Transactions ---
+/// Appends the conditions of `filter` to a query on `transactions` that
+/// already has a WHERE.
+fn push_transaction_filter(query: &mut QueryBuilder<Sqlite>, filter: &TransactionFilter) {
+    if let Some(account_id) = &filter.account_id {
+        query.push(" AND \"accountId\" = ").push_bind(account_id.clone());
+    }
+    if let Some(from) = &filter.from {
+        query.push(" AND date >= ").push_bind(from.clone());
+    }
+    if let Some(to) = &filter.to {
+        query.push(" AND date <= ").push_bind(to.clone());
+    }
+    tags::push_filter(query, &filter.tag_ids, filter.match_all_tags);
+}
+
+/// Transactions targeted by a bulk command: those of `ids`, those matching
+/// `filter`, or the intersection when both are given.
+async fn select_transactions(
+    conn: &mut sqlx::SqliteConnection,
+    ids: Option<Vec<String>>,
+    filter: Option<TransactionFilter>,
+) -> Result<Vec<Transaction>, String> {
+    if ids.is_none() && filter.is_none() {
+        return Err("Aucune transaction sélectionnée.".to_string());
+    }
+    let mut query: QueryBuilder<Sqlite> = QueryBuilder::new("SELECT * FROM transactions WHERE 1 = 1");
+    if let Some(ids) = ids {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        query.push(" AND id IN (");
+        let mut separated = query.separated(", ");
+        for id in ids {
+            separated.push_bind(id);
+        }
+        query.push(")");
+    }
+    if let Some(filter) = &filter {
+        push_transaction_filter(&mut query, filter);
+    }
+    query
+        .build_query_as::<Transaction>()
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| map_db_error(e, "sélection des transactions"))
+}
+
+/// Lists transactions, newest first. The optional filter narrows them by
+/// account, date range (YYYY-MM-DD, both inclusive) and tags.
+#[command]
+pub async fn get_transactions(
+    pool: State<'_, DbPool>,
+    filter: Option<TransactionFilter>,
+) -> Result<Vec<Transaction>, String> {
+    log::debug!("Invoked get_transactions: {filter:?}");
+    let mut query: QueryBuilder<Sqlite> = QueryBuilder::new("SELECT * FROM transactions WHERE 1 = 1");
+    push_transaction_filter(&mut query, &filter.unwrap_or_default());
+    query.push(" ORDER BY date DESC");
+
+    query
+        .build_query_as::<Transaction>()
+        .fetch_all(&*pool)
+        .await
+        .map_err(|e| map_db_error(e, "récupération des transactions"))
+}
+
+async fn insert_transaction(
+    conn: &mut sqlx::SqliteConnection,
+    transaction: &Transaction,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, category, description, status, \"clearedAt\", \"reconciledAt\", \"isTransfer\", \"linkedTransactionId\", \"payeeId\") VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)"
+    )
+    .bind(&transaction.id)
+    .bind(&transaction.date)
+    .bind(&transaction.account_id)
+    .bind(&transaction.transaction_type)
+    .bind(transaction.amount)
+    .bind(&transaction.category)
+    .bind(&transaction.description)
+    .bind(&transaction.status)
+    .bind(&transaction.cleared_at)
+    .bind(&transaction.reconciled_at)
+    .bind(transaction.is_transfer)
+    .bind(&transaction.linked_transaction_id)
+    .bind(&transaction.payee_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Adds a transaction. A missing payee is matched from the description and
+/// uncategorized transactions go through the rules; the stored transaction
+/// is returned. New transactions are never reconciled.
+#[command]
+pub async fn add_transaction(
+    pool: State<'_, DbPool>,
+    mut transaction: Transaction,
+) -> Result<Transaction, String> {
+    log::debug!("Invoked add_transaction: {transaction:?}");
+    reconciliation::check_status(&transaction, None)?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("add_transaction");
+
+    let matcher = payees::PayeeMatcher::load(&mut tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des bénéficiaires"))?;
+    matcher.assign(&mut transaction);
+
+    let mut tag_ids = Vec::new();
+    if rules::is_candidate(&transaction) {
+        let rule_set = rules::RuleSet::load(&mut tx)
+            .await
+            .map_err(|e| map_db_error(e, "lecture des règles"))?;
+        tag_ids = rule_set.apply(&mut transaction).tag_ids;
+    }
+    let today = reconciliation::today(&mut tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de la date"))?;
+    reconciliation::stamp_status(&mut transaction, None, &today);
+
+    insert_transaction(&mut tx, &transaction)
+        .await
+        .map_err(|e| map_db_error(e, "ajout de transaction"))?;
+
+    audit::record(&mut tx, &mut op, audit::ENTITY_TRANSACTION, &transaction.id, audit::ACTION_CREATE, None, Some(&transaction))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de l'ajout de transaction"))?;
+
+    for tag_id in &tag_ids {
+        tags::link(&mut tx, &mut op, &transaction.id, tag_id)
+            .await
+            .map_err(|e| map_db_error(e, "ajout des tags de la règle"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(transaction)
+}
+
+/// Inserts both legs of a transfer as a single operation.
+#[command]
+pub async fn add_transfer(
+    pool: State<'_, DbPool>,
+    mut from_transaction: Transaction,
+    mut to_transaction: Transaction,
+) -> Result<(), String> {
+    log::debug!("Invoked add_transfer: {from_transaction:?} -> {to_transaction:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("add_transfer");
+
+    let today = reconciliation::today(&mut tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de la date"))?;
+    for transaction in [&mut from_transaction, &mut to_transaction] {
+        reconciliation::check_status(transaction, None)?;
+        reconciliation::stamp_status(transaction, None, &today);
+    }
+
+    for transaction in [&from_transaction, &to_transaction] {
+        insert_transaction(&mut tx, transaction)
+            .await
+            .map_err(|e| map_db_error(e, "ajout de virement"))?;
+
+        audit::record(&mut tx, &mut op, audit::ENTITY_TRANSACTION, &transaction.id, audit::ACTION_CREATE, None, Some(transaction))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de l'ajout de virement"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Updates a transaction and returns it as stored, with its status dates.
+/// Reconciled transactions need `force`.
+#[command]
+pub async fn update_transaction(
+    pool: State<'_, DbPool>,
+    mut transaction: Transaction,
+    force: Option<bool>,
+) -> Result<Transaction, String> {
+    log::debug!("Invoked update_transaction: {transaction:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("update_transaction");
+
+    let before = audit::fetch_transaction(&mut tx, &transaction.id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de transaction"))?;
+    reconciliation::check_unlocked(before.iter(), force.unwrap_or(false))?;
+    reconciliation::check_status(&transaction, before.as_ref())?;
+    let today = reconciliation::today(&mut tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de la date"))?;
+    reconciliation::stamp_status(&mut transaction, before.as_ref(), &today);
+
+    sqlx::query(
+        "UPDATE transactions SET date = $1, \"accountId\" = $2, \"type\" = $3, amount = $4, category = $5, description = $6, status = $7, \"clearedAt\" = $8, \"reconciledAt\" = $9, \"isTransfer\" = $10, \"linkedTransactionId\" = $11, \"payeeId\" = $12, \"reconciliationId\" = $13 WHERE id = $14"
+    )
+    .bind(&transaction.date)
+    .bind(&transaction.account_id)
+    .bind(&transaction.transaction_type)
+    .bind(transaction.amount)
+    .bind(&transaction.category)
+    .bind(&transaction.description)
+    .bind(&transaction.status)
+    .bind(&transaction.cleared_at)
+    .bind(&transaction.reconciled_at)
+    .bind(transaction.is_transfer)
+    .bind(&transaction.linked_transaction_id)
+    .bind(&transaction.payee_id)
+    .bind(&transaction.reconciliation_id)
+    .bind(&transaction.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| map_db_error(e, "mise à jour de transaction"))?;
+
+    if before.is_some() {
+        audit::record(&mut tx, &mut op, audit::ENTITY_TRANSACTION, &transaction.id, audit::ACTION_UPDATE, before.as_ref(), Some(&transaction))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la mise à jour de transaction"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(transaction)
+}
+
+/// Deletes a transaction with its tags and attachments inside the caller's
+/// transaction. Returns false when it did not exist.
+async fn remove_transaction(
+    conn: &mut sqlx::SqliteConnection,
+    op: &mut audit::Operation,
+    id: &str,
+    force: bool,
+) -> Result<bool, String> {
+    let before = audit::fetch_transaction(conn, id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de transaction"))?;
+    reconciliation::check_unlocked(before.iter(), force)?;
+
+    // Unlink the other leg of a transfer explicitly so undo can restore the pair
+    let linked = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE \"linkedTransactionId\" = $1")
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du virement lié"))?;
+
+    for linked_before in &linked {
+        let linked_after = Transaction {
+            linked_transaction_id: None,
+            ..linked_before.clone()
+        };
+        history::upsert_transaction(conn, &linked_after)
+            .await
+            .map_err(|e| map_db_error(e, "détachement du virement lié"))?;
+        audit::record(conn, op, audit::ENTITY_TRANSACTION, &linked_after.id, audit::ACTION_UPDATE, Some(linked_before), Some(&linked_after))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation du détachement du virement lié"))?;
+    }
+
+    tags::unlink_transaction(conn, op, id)
+        .await
+        .map_err(|e| map_db_error(e, "retrait des tags de la transaction"))?;
+    attachments::delete_for_transaction(conn, op, id)
+        .await
+        .map_err(|e| map_db_error(e, "suppression des pièces jointes de la transaction"))?;
+
+    sqlx::query("DELETE FROM transactions WHERE id = $1")
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| map_db_error(e, "suppression de transaction"))?;
+
+    let Some(before) = &before else {
+        return Ok(false);
+    };
+    audit::record(conn, op, audit::ENTITY_TRANSACTION, id, audit::ACTION_DELETE, Some(before), None)
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de la suppression de transaction"))?;
+    Ok(true)
+}
+
+#[command]
+pub async fn delete_transaction(pool: State<'_, DbPool>, id: String, force: Option<bool>) -> Result<(), String> {
+    log::debug!("Invoked delete_transaction: {id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("delete_transaction");
+
+    remove_transaction(&mut tx, &mut op, &id, force.unwrap_or(false)).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Applies the same changes to many transactions as one undoable operation.
+/// Transfer legs keep their category and cannot change account; moved
+/// transactions must fall within an open account's life. Reconciled
+/// transactions need `force`, and go back to cleared when they leave their
+/// account and its reconciliation.
+#[command]
+pub async fn bulk_update_transactions(
+    pool: State<'_, DbPool>,
+    ids: Option<Vec<String>>,
+    filter: Option<TransactionFilter>,
+    update: TransactionBulkUpdate,
+    force: Option<bool>,
+) -> Result<BulkUpdateResult, String> {
+    log::debug!("Invoked bulk_update_transactions: ids={ids:?}, filter={filter:?}, update={update:?}");
+    if update.description_find.as_deref() == Some("") {
+        return Err("Le texte à remplacer ne peut pas être vide.".to_string());
+    }
+    if let Some(status) = &update.status {
+        if status != STATUS_PENDING && status != STATUS_CLEARED {
+            return Err("Seul un rapprochement peut marquer une transaction comme rapprochée.".to_string());
+        }
+    }
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("bulk_update_transactions");
+
+    if let Some(category) = &update.category {
+        if audit::fetch_category(&mut tx, category)
+            .await
+            .map_err(|e| map_db_error(e, "lecture de catégorie"))?
+            .is_none()
+        {
+            return Err("Catégorie introuvable.".to_string());
+        }
+    }
+    let mut target = None;
+    if let Some(account_id) = &update.account_id {
+        let Some(account) = audit::fetch_account(&mut tx, account_id)
+            .await
+            .map_err(|e| map_db_error(e, "lecture du compte"))?
+        else {
+            return Err("Compte introuvable.".to_string());
+        };
+        if account.archived {
+            return Err("Ce compte est clôturé.".to_string());
+        }
+        target = Some(account);
+    }
+    for tag_id in &update.add_tag_ids {
+        if audit::fetch_tag(&mut tx, tag_id)
+            .await
+            .map_err(|e| map_db_error(e, "lecture de tag"))?
+            .is_none()
+        {
+            return Err(format!("Tag introuvable : {tag_id}"));
+        }
+    }
+
+    let transactions = select_transactions(&mut tx, ids, filter).await?;
+    reconciliation::check_unlocked(&transactions, force.unwrap_or(false))?;
+    if let Some(target) = &target {
+        for moved in transactions.iter().filter(|t| t.account_id != target.id) {
+            if moved.is_transfer {
+                return Err("Un virement ne peut pas changer de compte.".to_string());
+            }
+            if target.opening_date.as_deref().is_some_and(|opening| moved.date.as_str() < opening) {
+                return Err("La date précède l'ouverture du compte.".to_string());
+            }
+        }
+    }
+    let today = reconciliation::today(&mut tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de la date"))?;
+    let mut result = BulkUpdateResult {
+        matched: transactions.len() as u64,
+        ..Default::default()
+    };
+
+    for before in &transactions {
+        let mut after = before.clone();
+        if let Some(category) = &update.category {
+            if !after.is_transfer {
+                after.category = category.clone();
+            }
+        }
+        let moved = update.account_id.as_ref().filter(|id| **id != before.account_id);
+        if let Some(account_id) = moved {
+            after.account_id = account_id.clone();
+            if after.status == STATUS_RECONCILED {
+                after.status = STATUS_CLEARED.to_string();
+            }
+        }
+        if let Some(status) = &update.status {
+            after.status = status.clone();
+        }
+        if moved.is_some() || update.status.is_some() {
+            reconciliation::stamp_status(&mut after, Some(before), &today);
+        }
+        if let (Some(find), Some(description)) = (&update.description_find, &after.description) {
+            after.description = Some(description.replace(find.as_str(), update.description_replace.as_deref().unwrap_or("")));
+        }
+
+        let mut changed = false;
+        if after != *before {
+            history::upsert_transaction(&mut tx, &after)
+                .await
+                .map_err(|e| map_db_error(e, "mise à jour des transactions"))?;
+            audit::record(&mut tx, &mut op, audit::ENTITY_TRANSACTION, &after.id, audit::ACTION_UPDATE, Some(before), Some(&after))
+                .await
+                .map_err(|e| map_db_error(e, "journalisation de la mise à jour des transactions"))?;
+            changed = true;
+        }
+        for tag_id in &update.add_tag_ids {
+            if tags::link(&mut tx, &mut op, &after.id, tag_id)
+                .await
+                .map_err(|e| map_db_error(e, "ajout de tag"))?
+            {
+                result.tags_added += 1;
+                changed = true;
+            }
+        }
+        for tag_id in &update.remove_tag_ids {
+            if tags::unlink(&mut tx, &mut op, &after.id, tag_id)
+                .await
+                .map_err(|e| map_db_error(e, "retrait de tag"))?
+            {
+                result.tags_removed += 1;
+                changed = true;
+            }
+        }
+        if changed {
+            result.updated += 1;
+        }
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+/// Deletes many transactions as one undoable operation. Returns how many
+/// were deleted. Reconciled transactions need `force`.
+#[command]
+pub async fn bulk_delete_transactions(
+    pool: State<'_, DbPool>,
+    ids: Option<Vec<String>>,
+    filter: Option<TransactionFilter>,
+    force: Option<bool>,
+) -> Result<u64, String> {
+    log::debug!("Invoked bulk_delete_transactions: ids={ids:?}, filter={filter:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("bulk_delete_transactions");
+
+    let transactions = select_transactions(&mut tx, ids, filter).await?;
+    reconciliation::check_unlocked(&transactions, force.unwrap_or(false))?;
+
+    let mut deleted = 0;
+    for transaction in &transactions {
+        if remove_transaction(&mut tx, &mut op, &transaction.id, true).await? {
+            deleted += 1;
+        }
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(deleted)
+}
+
+// --- Reconciliations ---
+#[command]
+pub async fn get_reconciliations(
+    pool: State<'_, DbPool>,
+    account_id: Option<String>,
+) -> Result<Vec<Reconciliation>, String> {
+    log::debug!("Invoked get_reconciliations: {account_id:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    reconciliation::fetch_reconciliations(&mut conn, account_id.as_deref())
+        .await
+        .map_err(|e| map_db_error(e, "récupération des rapprochements"))
+}
+
+async fn fetch_open_reconciliation(conn: &mut sqlx::SqliteConnection, id: &str) -> Result<Reconciliation, String> {
+    let reconciliation = audit::fetch_reconciliation(conn, id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du rapprochement"))?
+        .ok_or("Rapprochement introuvable.")?;
+    if reconciliation.status != reconciliation::STATUS_OPEN {
+        return Err("Ce rapprochement est déjà terminé.".to_string());
+    }
+    Ok(reconciliation)
+}
+
+/// Opens a reconciliation against a bank statement. An account has at most
+/// one open reconciliation, and statements follow each other in time.
+#[command]
+pub async fn start_reconciliation(
+    pool: State<'_, DbPool>,
+    id: String,
+    account_id: String,
+    statement_date: String,
+    statement_balance: f64,
+) -> Result<ReconciliationStatus, String> {
+    log::debug!("Invoked start_reconciliation: {id} for {account_id} at {statement_date} ({statement_balance})");
+    if dates::parse_date(&statement_date).is_none() {
+        return Err(format!("Date invalide : {statement_date}"));
+    }
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("start_reconciliation");
+
+    if audit::fetch_account(&mut tx, &account_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du compte"))?
+        .is_none()
+    {
+        return Err("Compte introuvable.".to_string());
+    }
+    if reconciliation::fetch_open(&mut tx, &account_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des rapprochements"))?
+        .is_some()
+    {
+        return Err("Un rapprochement est déjà en cours pour ce compte.".to_string());
+    }
+    let last_date: Option<String> = sqlx::query_scalar("SELECT MAX(\"statementDate\") FROM reconciliations WHERE \"accountId\" = $1")
+        .bind(&account_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des rapprochements"))?;
+    if last_date.is_some_and(|last| statement_date < last) {
+        return Err("La date du relevé précède celle du dernier rapprochement.".to_string());
+    }
+
+    sqlx::query(
+        "INSERT INTO reconciliations (id, \"accountId\", \"statementDate\", \"statementBalance\", status) VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(&id)
+    .bind(&account_id)
+    .bind(&statement_date)
+    .bind(statement_balance)
+    .bind(reconciliation::STATUS_OPEN)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| map_db_error(e, "ouverture du rapprochement"))?;
+
+    let created = audit::fetch_reconciliation(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du rapprochement"))?
+        .ok_or("Rapprochement introuvable.")?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_RECONCILIATION, &id, audit::ACTION_CREATE, None, Some(&created))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de l'ouverture du rapprochement"))?;
+
+    let status = reconciliation::status(&mut tx, created)
+        .await
+        .map_err(|e| map_db_error(e, "calcul du rapprochement"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(status)
+}
+
+#[command]
+pub async fn get_reconciliation_status(pool: State<'_, DbPool>, id: String) -> Result<ReconciliationStatus, String> {
+    log::debug!("Invoked get_reconciliation_status: {id}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let reconciliation = audit::fetch_reconciliation(&mut conn, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du rapprochement"))?
+        .ok_or("Rapprochement introuvable.")?;
+    reconciliation::status(&mut conn, reconciliation)
+        .await
+        .map_err(|e| map_db_error(e, "calcul du rapprochement"))
+}
+
+/// Marks transactions of the reconciled account as cleared (or not) and
+/// returns the updated difference with the statement. Reconciled
+/// transactions are left alone.
+#[command]
+pub async fn set_transactions_cleared(
+    pool: State<'_, DbPool>,
+    reconciliation_id: String,
+    transaction_ids: Vec<String>,
+    cleared: bool,
+) -> Result<ReconciliationStatus, String> {
+    log::debug!("Invoked set_transactions_cleared: {reconciliation_id} {transaction_ids:?} = {cleared}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("set_transactions_cleared");
+
+    let current = fetch_open_reconciliation(&mut tx, &reconciliation_id).await?;
+    let today = reconciliation::today(&mut tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de la date"))?;
+
+    for transaction_id in &transaction_ids {
+        let Some(before) = audit::fetch_transaction(&mut tx, transaction_id)
+            .await
+            .map_err(|e| map_db_error(e, "lecture de transaction"))?
+        else {
+            return Err(format!("Transaction introuvable : {transaction_id}"));
+        };
+        if before.account_id != current.account_id {
+            return Err("La transaction n'appartient pas au compte rapproché.".to_string());
+        }
+        let status = if cleared { STATUS_CLEARED } else { STATUS_PENDING };
+        if before.status == STATUS_RECONCILED || before.status == status {
+            continue;
+        }
+        let mut after = Transaction {
+            status: status.to_string(),
+            ..before.clone()
+        };
+        reconciliation::stamp_status(&mut after, Some(&before), &today);
+        history::upsert_transaction(&mut tx, &after)
+            .await
+            .map_err(|e| map_db_error(e, "pointage des transactions"))?;
+        audit::record(&mut tx, &mut op, audit::ENTITY_TRANSACTION, &after.id, audit::ACTION_UPDATE, Some(&before), Some(&after))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation du pointage"))?;
+    }
+
+    let status = reconciliation::status(&mut tx, current)
+        .await
+        .map_err(|e| map_db_error(e, "calcul du rapprochement"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(status)
+}
+
+/// Locks the cleared transactions up to the statement date. Fails while the
+/// cleared balance differs from the statement.
+#[command]
+pub async fn finish_reconciliation(pool: State<'_, DbPool>, id: String) -> Result<Reconciliation, String> {
+    log::debug!("Invoked finish_reconciliation: {id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("finish_reconciliation");
+
+    let current = fetch_open_reconciliation(&mut tx, &id).await?;
+    let status = reconciliation::status(&mut tx, current.clone())
+        .await
+        .map_err(|e| map_db_error(e, "calcul du rapprochement"))?;
+    if status.difference != 0.0 {
+        return Err(format!(
+            "Le solde pointé diffère du relevé de {:.2} : le rapprochement ne peut pas être terminé.",
+            status.difference
+        ));
+    }
+
+    let finished = reconciliation::finish(&mut tx, &mut op, &current)
+        .await
+        .map_err(|e| map_db_error(e, "clôture du rapprochement"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(finished)
+}
+
+/// Abandons an open reconciliation. Cleared marks are kept.
+#[command]
+pub async fn cancel_reconciliation(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
+    log::debug!("Invoked cancel_reconciliation: {id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("cancel_reconciliation");
+
+    let current = fetch_open_reconciliation(&mut tx, &id).await?;
+
+    sqlx::query("DELETE FROM reconciliations WHERE id = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "annulation du rapprochement"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_RECONCILIATION, &id, audit::ACTION_DELETE, Some(&current), None)
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de l'annulation du rapprochement"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// --- Balance assertions ---
+#[command]
+pub async fn get_balance_assertions(
+    pool: State<'_, DbPool>,
+    account_id: Option<String>,
+) -> Result<Vec<BalanceAssertion>, String> {
+    log::debug!("Invoked get_balance_assertions: {account_id:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    balances::fetch_assertions(&mut conn, account_id.as_deref())
+        .await
+        .map_err(|e| map_db_error(e, "récupération des soldes constatés"))
+}
+
+/// Records the balance the bank reported for an account at the end of
+/// `date` (YYYY-MM-DD) and returns how it compares with the transactions.
+#[command]
+pub async fn add_balance_assertion(
+    pool: State<'_, DbPool>,
+    id: String,
+    account_id: String,
+    date: String,
+    balance: f64,
+) -> Result<BalanceCheck, String> {
+    log::debug!("Invoked add_balance_assertion: {id} for {account_id} at {date} ({balance})");
+    if dates::parse_date(&date).is_none() {
+        return Err(format!("Date invalide : {date}"));
+    }
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("add_balance_assertion");
+
+    let Some(account) = audit::fetch_account(&mut tx, &account_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du compte"))?
+    else {
+        return Err("Compte introuvable.".to_string());
+    };
+    if account.opening_date.as_deref().is_some_and(|opening| date.as_str() < opening) {
+        return Err("La date précède l'ouverture du compte.".to_string());
+    }
+
+    sqlx::query("INSERT INTO balance_assertions (id, \"accountId\", date, balance) VALUES ($1, $2, $3, $4)")
+        .bind(&id)
+        .bind(&account_id)
+        .bind(&date)
+        .bind(balance)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "ajout du solde constaté"))?;
+
+    let created = audit::fetch_balance_assertion(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du solde constaté"))?
+        .ok_or("Solde constaté introuvable.")?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_BALANCE_ASSERTION, &id, audit::ACTION_CREATE, None, Some(&created))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de l'ajout du solde constaté"))?;
+
+    let check = balances::verify(&mut tx, Some(account_id))
+        .await
+        .map_err(|e| map_db_error(e, "vérification des soldes"))?
+        .into_iter()
+        .find(|check| check.assertion.id == id)
+        .ok_or("Solde constaté introuvable.")?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(check)
+}
+
+#[command]
+pub async fn delete_balance_assertion(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
+    log::debug!("Invoked delete_balance_assertion: {id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("delete_balance_assertion");
+
+    let before = audit::fetch_balance_assertion(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du solde constaté"))?;
+
+    sqlx::query("DELETE FROM balance_assertions WHERE id = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression du solde constaté"))?;
+
+    if before.is_some() {
+        audit::record(&mut tx, &mut op, audit::ENTITY_BALANCE_ASSERTION, &id, audit::ACTION_DELETE, before.as_ref(), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression du solde constaté"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Compares every balance assertion, of one account or all of them, with
+/// the balance computed from the transactions at the end of its day.
+#[command]
+pub async fn verify_balances(pool: State<'_, DbPool>, account_id: Option<String>) -> Result<Vec<BalanceCheck>, String> {
+    log::debug!("Invoked verify_balances: {account_id:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    balances::verify(&mut conn, account_id)
+        .await
+        .map_err(|e| map_db_error(e, "vérification des soldes"))
+}
+
+// --- Credit cards ---
+/// Reads a credit card with its statement days.
+async fn fetch_card(conn: &mut sqlx::SqliteConnection, account_id: &str) -> Result<(Account, u32, u32), String> {
+    let Some(card) = audit::fetch_account(conn, account_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du compte"))?
+    else {
+        return Err("Compte introuvable.".to_string());
+    };
+    if card.account_type != KIND_CREDIT_CARD {
+        return Err("Ce compte n'est pas une carte de crédit.".to_string());
+    }
+    let (Some(closing_day), Some(due_day)) = (card.statement_closing_day, card.payment_due_day) else {
+        return Err("Définissez les jours de clôture du relevé et d'échéance de la carte.".to_string());
+    };
+    Ok((card, closing_day, due_day))
+}
+
+/// Last statement of a credit card closed on or before `as_of` (YYYY-MM-DD,
+/// today by default): its balance, due date, what is left to pay and the
+/// minimum payment.
+#[command]
+pub async fn get_card_statement(
+    pool: State<'_, DbPool>,
+    account_id: String,
+    as_of: Option<String>,
+) -> Result<CardStatement, String> {
+    log::debug!("Invoked get_card_statement: {account_id} as_of={as_of:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let (card, closing_day, due_day) = fetch_card(&mut conn, &account_id).await?;
+    let as_of = match as_of {
+        Some(as_of) => as_of,
+        None => reconciliation::today(&mut conn)
+            .await
+            .map_err(|e| map_db_error(e, "lecture de la date"))?,
+    };
+    cards::statement(&mut conn, &card, closing_day, due_day, &as_of).await
+}
+
+/// Schedules the payment of the current statement of a credit card from a
+/// checking account, once on its next due date. The amount is what is left
+/// to pay on the statement unless given: each statement owes a different
+/// amount, so each one gets its own payment. Returns the scheduled transfer.
+#[command]
+pub async fn schedule_card_payment(
+    pool: State<'_, DbPool>,
+    id: String,
+    account_id: String,
+    from_account_id: String,
+    amount: Option<f64>,
+) -> Result<ScheduledTransaction, String> {
+    log::debug!("Invoked schedule_card_payment: {account_id} from {from_account_id}, amount={amount:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("schedule_card_payment");
+
+    let (card, closing_day, due_day) = fetch_card(&mut tx, &account_id).await?;
+    let from = audit::fetch_account(&mut tx, &from_account_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du compte"))?;
+    if !from.is_some_and(|from| from.account_type == KIND_CHECKING && !from.archived) {
+        return Err("Le paiement doit partir d'un compte courant ouvert.".to_string());
+    }
+
+    let today = reconciliation::today(&mut tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de la date"))?;
+    let amount = match amount {
+        Some(amount) => amount,
+        None => cards::statement(&mut tx, &card, closing_day, due_day, &today).await?.remaining,
+    };
+    if amount <= 0.0 {
+        return Err("Aucun montant à payer sur cette carte.".to_string());
+    }
+    let next_date = cards::next_due_date(&today, closing_day, due_day)
+        .ok_or_else(|| format!("Date invalide : {today}"))?;
+
+    let scheduled = ScheduledTransaction {
+        id,
+        description: format!("Paiement {}", card.name),
+        amount,
+        transaction_type: "transfer".to_string(),
+        frequency: "once".to_string(),
+        account_id: from_account_id,
+        next_date,
+        category: TRANSFER_CATEGORY_ID.to_string(),
+        to_account_id: Some(card.id.clone()),
+        include_in_forecast: Some(true),
+        end_date: None,
+        payee_id: None,
+    };
+    history::upsert_scheduled(&mut tx, &scheduled)
+        .await
+        .map_err(|e| map_db_error(e, "ajout d'échéance"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_SCHEDULED, &scheduled.id, audit::ACTION_CREATE, None, Some(&scheduled))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de l'ajout d'échéance"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(scheduled)
+}
+
+// --- Loans ---
+/// Reads a loan account with its terms.
+async fn fetch_loan(conn: &mut sqlx::SqliteConnection, account_id: &str) -> Result<(Account, loans::LoanTerms), String> {
+    let Some(loan) = audit::fetch_account(conn, account_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du compte"))?
+    else {
+        return Err("Compte introuvable.".to_string());
+    };
+    if loan.account_type != KIND_LOAN {
+        return Err("Ce compte n'est pas un prêt.".to_string());
+    }
+    let Some(terms) = loans::LoanTerms::of(&loan) else {
+        return Err("Définissez le capital, le taux, la durée et la première échéance du prêt.".to_string());
+    };
+    Ok((loan, terms))
+}
+
+/// Amortization schedule of a loan from its terms: interest and principal
+/// of each monthly payment.
+#[command]
+pub async fn get_amortization_schedule(pool: State<'_, DbPool>, account_id: String) -> Result<Vec<AmortizationRow>, String> {
+    log::debug!("Invoked get_amortization_schedule: {account_id}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let (_, terms) = fetch_loan(&mut conn, &account_id).await?;
+    Ok(loans::schedule(&terms))
+}
+
+/// Payments left on a loan after `as_of` (YYYY-MM-DD, today by default),
+/// from the balance of the account at that date.
+#[command]
+pub async fn get_loan_projection(
+    pool: State<'_, DbPool>,
+    account_id: String,
+    as_of: Option<String>,
+) -> Result<Vec<AmortizationRow>, String> {
+    log::debug!("Invoked get_loan_projection: {account_id} as_of={as_of:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let (loan, terms) = fetch_loan(&mut conn, &account_id).await?;
+    let as_of = match as_of {
+        Some(as_of) => as_of,
+        None => reconciliation::today(&mut conn)
+            .await
+            .map_err(|e| map_db_error(e, "lecture de la date"))?,
+    };
+    loans::projection(&mut conn, &loan, &terms, &as_of).await
+}
+
+/// Schedules the monthly payment of a loan from another open account, from
+/// the next payment date until the last one. Each occurrence is recorded
+/// with record_loan_payment. Returns the scheduled transfer.
+#[command]
+pub async fn schedule_loan_payment(
+    pool: State<'_, DbPool>,
+    id: String,
+    account_id: String,
+    from_account_id: String,
+) -> Result<ScheduledTransaction, String> {
+    log::debug!("Invoked schedule_loan_payment: {account_id} from {from_account_id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("schedule_loan_payment");
+
+    let (loan, terms) = fetch_loan(&mut tx, &account_id).await?;
+    if loan.archived {
+        return Err("Ce prêt est clôturé.".to_string());
+    }
+    let from = audit::fetch_account(&mut tx, &from_account_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du compte"))?;
+    if from_account_id == account_id || from.map_or(true, |from| from.archived) {
+        return Err("Le remboursement doit partir d'un autre compte ouvert.".to_string());
+    }
+
+    let today = reconciliation::today(&mut tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de la date"))?;
+    let Some(next_date) = (1..=terms.term_months)
+        .map(|number| terms.payment_date(number))
+        .find(|date| *date >= today)
+    else {
+        return Err("Toutes les échéances de ce prêt sont passées.".to_string());
+    };
+
+    let scheduled = ScheduledTransaction {
+        id,
+        description: format!("Échéance {}", loan.name),
+        amount: terms.payment(),
+        transaction_type: "transfer".to_string(),
+        frequency: "monthly".to_string(),
+        account_id: from_account_id,
+        next_date,
+        category: TRANSFER_CATEGORY_ID.to_string(),
+        to_account_id: Some(loan.id.clone()),
+        include_in_forecast: Some(true),
+        end_date: Some(terms.last_payment_date()),
+        payee_id: None,
+    };
+    history::upsert_scheduled(&mut tx, &scheduled)
+        .await
+        .map_err(|e| map_db_error(e, "ajout d'échéance"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_SCHEDULED, &scheduled.id, audit::ACTION_CREATE, None, Some(&scheduled))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de l'ajout d'échéance"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(scheduled)
+}
+
+/// Records a loan payment: the interest due on what is owed at the payment
+/// date becomes an expense of the paying account and the rest a transfer to
+/// the loan. Returns the transactions created.
+#[command]
+pub async fn record_loan_payment(
+    pool: State<'_, DbPool>,
+    account_id: String,
+    payment: LoanPayment,
+) -> Result<Vec<Transaction>, String> {
+    log::debug!("Invoked record_loan_payment: {account_id} {payment:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("record_loan_payment");
+
+    let (loan, terms) = fetch_loan(&mut tx, &account_id).await?;
+    let from = audit::fetch_account(&mut tx, &payment.from_account_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du compte"))?;
+    if payment.from_account_id == account_id || loan.archived || from.map_or(true, |from| from.archived) {
+        return Err("Le remboursement doit partir d'un autre compte ouvert.".to_string());
+    }
+    if payment.amount <= 0.0 {
+        return Err("Le montant du remboursement doit être positif.".to_string());
+    }
+    if dates::parse_date(&payment.date).is_none() {
+        return Err(format!("Date invalide : {}", payment.date));
+    }
+
+    let owed = -balances::balance_at(&mut tx, &loan, &payment.date)
+        .await
+        .map_err(|e| map_db_error(e, "calcul du solde du prêt"))?;
+    let interest = balances::round_cents((owed.max(0.0) * terms.rate).min(payment.amount));
+    let principal = balances::round_cents(payment.amount - interest);
+
+    let mut created = Vec::new();
+    if interest > 0.0 {
+        created.push(Transaction {
+            id: payment.interest_transaction_id.clone(),
+            date: payment.date.clone(),
+            account_id: payment.from_account_id.clone(),
+            transaction_type: "expense".to_string(),
+            amount: interest,
+            category: UNCATEGORIZED_CATEGORY_ID.to_string(),
+            description: Some(format!("Intérêts {}", loan.name)),
+            status: STATUS_PENDING.to_string(),
+            cleared_at: None,
+            reconciled_at: None,
+            is_transfer: false,
+            linked_transaction_id: None,
+            payee_id: None,
+            reconciliation_id: None,
+        });
+    }
+    if principal > 0.0 {
+        let leg = Transaction {
+            id: payment.from_transaction_id.clone(),
+            date: payment.date.clone(),
+            account_id: payment.from_account_id.clone(),
+            transaction_type: "expense".to_string(),
+            amount: principal,
+            category: TRANSFER_CATEGORY_ID.to_string(),
+            description: Some(format!("Échéance {}", loan.name)),
+            status: STATUS_PENDING.to_string(),
+            cleared_at: None,
+            reconciled_at: None,
+            is_transfer: true,
+            linked_transaction_id: Some(payment.to_transaction_id.clone()),
+            payee_id: None,
+            reconciliation_id: None,
+        };
+        let linked = Transaction {
+            id: payment.to_transaction_id.clone(),
+            account_id: loan.id.clone(),
+            transaction_type: "income".to_string(),
+            linked_transaction_id: Some(payment.from_transaction_id.clone()),
+            ..leg.clone()
+        };
+        created.extend([leg, linked]);
+    }
+
+    for transaction in &created {
+        insert_transaction(&mut tx, transaction)
+            .await
+            .map_err(|e| map_db_error(e, "remboursement du prêt"))?;
+        audit::record(&mut tx, &mut op, audit::ENTITY_TRANSACTION, &transaction.id, audit::ACTION_CREATE, None, Some(transaction))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation du remboursement du prêt"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(created)
+}
+
+// --- Assets ---
+#[command]
+pub async fn get_valuations(pool: State<'_, DbPool>, account_id: Option<String>) -> Result<Vec<Valuation>, String> {
+    log::debug!("Invoked get_valuations: {account_id:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    assets::fetch_valuations(&mut conn, account_id.as_deref())
+        .await
+        .map_err(|e| map_db_error(e, "récupération des estimations"))
+}
+
+/// Records the value of an asset account at `date` (YYYY-MM-DD), replacing
+/// the valuation already entered for that day. Returns it as stored.
+#[command]
+pub async fn add_valuation(
+    pool: State<'_, DbPool>,
+    id: String,
+    account_id: String,
+    date: String,
+    value: f64,
+) -> Result<Valuation, String> {
+    log::debug!("Invoked add_valuation: {id} for {account_id} at {date} ({value})");
+    if dates::parse_date(&date).is_none() {
+        return Err(format!("Date invalide : {date}"));
+    }
+    if value < 0.0 {
+        return Err("La valeur d'un bien ne peut pas être négative.".to_string());
+    }
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("add_valuation");
+
+    let account = audit::fetch_account(&mut tx, &account_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du compte"))?;
+    if account.map_or(true, |a| a.account_type != KIND_ASSET) {
+        return Err("Seul un bien peut être estimé.".to_string());
+    }
+
+    let before = sqlx::query_as::<_, Valuation>("SELECT * FROM valuations WHERE \"accountId\" = $1 AND date = $2")
+        .bind(&account_id)
+        .bind(&date)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de l'estimation"))?;
+    let after = Valuation {
+        id: before.as_ref().map_or(id, |v| v.id.clone()),
+        account_id,
+        date,
+        value,
+    };
+    history::upsert_valuation(&mut tx, &after)
+        .await
+        .map_err(|e| map_db_error(e, "ajout d'estimation"))?;
+    let action = if before.is_some() { audit::ACTION_UPDATE } else { audit::ACTION_CREATE };
+    audit::record(&mut tx, &mut op, audit::ENTITY_VALUATION, &after.id, action, before.as_ref(), Some(&after))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de l'ajout d'estimation"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(after)
+}
+
+#[command]
+pub async fn delete_valuation(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
+    log::debug!("Invoked delete_valuation: {id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("delete_valuation");
+
+    let before = audit::fetch_valuation(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de l'estimation"))?;
+    sqlx::query("DELETE FROM valuations WHERE id = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression de l'estimation"))?;
+
+    if before.is_some() {
+        audit::record(&mut tx, &mut op, audit::ENTITY_VALUATION, &id, audit::ACTION_DELETE, before.as_ref(), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression de l'estimation"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Value of every valued asset at the end of `as_of` (YYYY-MM-DD, today by
+/// default): its last valuation, depreciated since then.
+#[command]
+pub async fn get_asset_values(pool: State<'_, DbPool>, as_of: Option<String>) -> Result<Vec<AssetValue>, String> {
+    log::debug!("Invoked get_asset_values: as_of={as_of:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let as_of = match as_of {
+        Some(as_of) => as_of,
+        None => reconciliation::today(&mut conn)
+            .await
+            .map_err(|e| map_db_error(e, "lecture de la date"))?,
+    };
+    assets::values(&mut conn, &as_of)
+        .await
+        .map_err(|e| map_db_error(e, "calcul de la valeur des biens"))
+}
+
+// --- Investments ---
+#[command]
+pub async fn get_securities(pool: State<'_, DbPool>) -> Result<Vec<Security>, String> {
+    log::debug!("Invoked get_securities");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    investments::fetch_securities(&mut conn)
+        .await
+        .map_err(|e| map_db_error(e, "récupération des titres"))
+}
+
+#[command]
+pub async fn add_security(pool: State<'_, DbPool>, security: Security) -> Result<Security, String> {
+    log::debug!("Invoked add_security: {security:?}");
+    investments::validate_security(&security)?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("add_security");
+
+    sqlx::query("INSERT INTO securities (id, symbol, name) VALUES ($1, $2, $3)")
+        .bind(&security.id)
+        .bind(&security.symbol)
+        .bind(&security.name)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "ajout de titre"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_SECURITY, &security.id, audit::ACTION_CREATE, None, Some(&security))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de l'ajout de titre"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(security)
+}
+
+#[command]
+pub async fn update_security(pool: State<'_, DbPool>, security: Security) -> Result<(), String> {
+    log::debug!("Invoked update_security: {security:?}");
+    investments::validate_security(&security)?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("update_security");
+
+    let before = audit::fetch_security(&mut tx, &security.id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du titre"))?;
+    sqlx::query("UPDATE securities SET symbol = $1, name = $2 WHERE id = $3")
+        .bind(&security.symbol)
+        .bind(&security.name)
+        .bind(&security.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "mise à jour du titre"))?;
+
+    if before.is_some() {
+        audit::record(&mut tx, &mut op, audit::ENTITY_SECURITY, &security.id, audit::ACTION_UPDATE, before.as_ref(), Some(&security))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la mise à jour du titre"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Deletes a security with its prices. Fails while trades use it.
+#[command]
+pub async fn delete_security(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
+    log::debug!("Invoked delete_security: {id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("delete_security");
+
+    let before = audit::fetch_security(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du titre"))?;
+    let prices = investments::fetch_prices(&mut tx, Some(&id))
+        .await
+        .map_err(|e| map_db_error(e, "lecture des cours liés"))?;
+
+    sqlx::query("DELETE FROM prices WHERE \"securityId\" = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression des cours liés"))?;
+    sqlx::query("DELETE FROM securities WHERE id = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression du titre"))?;
+
+    for p in &prices {
+        audit::record(&mut tx, &mut op, audit::ENTITY_PRICE, &p.id, audit::ACTION_DELETE, Some(p), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression des cours liés"))?;
+    }
+    if let Some(security) = &before {
+        audit::record(&mut tx, &mut op, audit::ENTITY_SECURITY, &id, audit::ACTION_DELETE, Some(security), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression du titre"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Trades of one account or all of them, oldest first.
+#[command]
+pub async fn get_trades(pool: State<'_, DbPool>, account_id: Option<String>) -> Result<Vec<Trade>, String> {
+    log::debug!("Invoked get_trades: {account_id:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    investments::fetch_trades(&mut conn, account_id.as_deref())
+        .await
+        .map_err(|e| map_db_error(e, "récupération des opérations sur titres"))
+}
+
+/// Records a buy or a sale in an open investment account. A sale cannot
+/// exceed the quantity held at its date, nor make a later one do so.
+#[command]
+pub async fn add_trade(pool: State<'_, DbPool>, trade: Trade) -> Result<Trade, String> {
+    log::debug!("Invoked add_trade: {trade:?}");
+    investments::validate_trade(&trade)?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("add_trade");
+
+    let account = audit::fetch_account(&mut tx, &trade.account_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du compte"))?;
+    if !account.is_some_and(|a| a.account_type == KIND_INVESTMENT && !a.archived) {
+        return Err("Les titres se détiennent dans un compte d'investissement ouvert.".to_string());
+    }
+
+    history::upsert_trade(&mut tx, &trade)
+        .await
+        .map_err(|e| map_db_error(e, "ajout d'opération sur titres"))?;
+    investments::check_quantities(&mut tx, &trade.account_id).await?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_TRADE, &trade.id, audit::ACTION_CREATE, None, Some(&trade))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de l'ajout d'opération sur titres"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(trade)
+}
+
+/// Deletes a trade, unless a later sale would then exceed the quantity held.
+#[command]
+pub async fn delete_trade(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
+    log::debug!("Invoked delete_trade: {id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("delete_trade");
+
+    let Some(before) = audit::fetch_trade(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de l'opération sur titres"))?
+    else {
+        return Ok(());
+    };
+    sqlx::query("DELETE FROM trades WHERE id = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression de l'opération sur titres"))?;
+    investments::check_quantities(&mut tx, &before.account_id).await?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_TRADE, &id, audit::ACTION_DELETE, Some(&before), None)
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de la suppression de l'opération sur titres"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[command]
+pub async fn get_prices(pool: State<'_, DbPool>, security_id: Option<String>) -> Result<Vec<Price>, String> {
+    log::debug!("Invoked get_prices: {security_id:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    investments::fetch_prices(&mut conn, security_id.as_deref())
+        .await
+        .map_err(|e| map_db_error(e, "récupération des cours"))
+}
+
+/// Sets the price of a security on a day, replacing the one already
+/// entered for that day. Returns the price as stored.
+async fn store_price(
+    conn: &mut sqlx::SqliteConnection,
+    op: &mut audit::Operation,
+    security_id: &str,
+    date: &str,
+    price: f64,
+) -> Result<Price, String> {
+    let id = investments::price_id(security_id, date);
+    let before = audit::fetch_price(conn, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du cours"))?;
+    let after = Price {
+        id,
+        security_id: security_id.to_string(),
+        date: date.to_string(),
+        price,
+    };
+    history::upsert_price(conn, &after)
+        .await
+        .map_err(|e| map_db_error(e, "enregistrement du cours"))?;
+    let action = if before.is_some() { audit::ACTION_UPDATE } else { audit::ACTION_CREATE };
+    audit::record(conn, op, audit::ENTITY_PRICE, &after.id, action, before.as_ref(), Some(&after))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation du cours"))?;
+    Ok(after)
+}
+
+/// Enters the price of a security at the end of `date` (YYYY-MM-DD).
+#[command]
+pub async fn set_price(
+    pool: State<'_, DbPool>,
+    security_id: String,
+    date: String,
+    price: f64,
+) -> Result<Price, String> {
+    log::debug!("Invoked set_price: {security_id} at {date} ({price})");
+    if dates::parse_date(&date).is_none() {
+        return Err(format!("Date invalide : {date}"));
+    }
+    if !price.is_finite() || price < 0.0 {
+        return Err("Le cours ne peut pas être négatif.".to_string());
+    }
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("set_price");
+    let stored = store_price(&mut tx, &mut op, &security_id, &date, price).await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(stored)
+}
+
+#[command]
+pub async fn delete_price(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
+    log::debug!("Invoked delete_price: {id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("delete_price");
+
+    let before = audit::fetch_price(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture du cours"))?;
+    sqlx::query("DELETE FROM prices WHERE id = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression du cours"))?;
+
+    if before.is_some() {
+        audit::record(&mut tx, &mut op, audit::ENTITY_PRICE, &id, audit::ACTION_DELETE, before.as_ref(), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression du cours"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Imports prices from CSV content (`symbol,date,price` lines) as one
+/// operation. Every symbol must be a known security. Returns the prices
+/// stored.
+#[command]
+pub async fn import_prices(pool: State<'_, DbPool>, content: String) -> Result<Vec<Price>, String> {
+    log::debug!("Invoked import_prices: {} bytes", content.len());
+    let rows = investments::parse_prices_csv(&content)?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("import_prices");
+
+    let securities: HashMap<String, String> = investments::fetch_securities(&mut tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des titres"))?
+        .into_iter()
+        .map(|s| (s.symbol, s.id))
+        .collect();
+    let mut stored = Vec::with_capacity(rows.len());
+    for (symbol, date, price) in rows {
+        let Some(security_id) = securities.get(&symbol) else {
+            return Err(format!("Titre inconnu : {symbol}"));
+        };
+        stored.push(store_price(&mut tx, &mut op, security_id, &date, price).await?);
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(stored)
+}
+
+/// Holdings at the end of `as_of` (YYYY-MM-DD, every trade when not given),
+/// of one account or all of them: quantity, cost basis, market value and
+/// gains per security.
+#[command]
+pub async fn get_holdings(
+    pool: State<'_, DbPool>,
+    account_id: Option<String>,
+    as_of: Option<String>,
+) -> Result<Vec<Holding>, String> {
+    log::debug!("Invoked get_holdings: {account_id:?} as_of={as_of:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    investments::holdings(&mut conn, account_id.as_deref(), as_of.as_deref())
+        .await
+        .map_err(|e| map_db_error(e, "calcul des positions"))
+}
+
+/// Market value, cost basis and realized and unrealized gains of each
+/// investment account at the end of `as_of` when given.
+#[command]
+pub async fn get_investment_summaries(
+    pool: State<'_, DbPool>,
+    as_of: Option<String>,
+) -> Result<Vec<InvestmentSummary>, String> {
+    log::debug!("Invoked get_investment_summaries: as_of={as_of:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let holdings = investments::holdings(&mut conn, None, as_of.as_deref())
+        .await
+        .map_err(|e| map_db_error(e, "calcul des positions"))?;
+    Ok(investments::summaries(&holdings))
+}
+
+// --- Goals ---
+#[command]
+pub async fn get_goals(pool: State<'_, DbPool>) -> Result<Vec<Goal>, String> {
+    log::debug!("Invoked get_goals");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    goals::fetch_goals(&mut conn)
+        .await
+        .map_err(|e| map_db_error(e, "récupération des objectifs"))
+}
+
+#[command]
+pub async fn add_goal(pool: State<'_, DbPool>, goal: Goal) -> Result<Goal, String> {
+    log::debug!("Invoked add_goal: {goal:?}");
+    goals::validate(&goal)?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("add_goal");
+
+    sqlx::query("INSERT INTO goals (id, name, \"targetAmount\", \"targetDate\", \"accountId\", \"tagId\") VALUES ($1, $2, $3, $4, $5, $6)")
+        .bind(&goal.id)
+        .bind(&goal.name)
+        .bind(goal.target_amount)
+        .bind(&goal.target_date)
+        .bind(&goal.account_id)
+        .bind(&goal.tag_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "ajout d'objectif"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_GOAL, &goal.id, audit::ACTION_CREATE, None, Some(&goal))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de l'ajout d'objectif"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(goal)
+}
+
+#[command]
+pub async fn update_goal(pool: State<'_, DbPool>, goal: Goal) -> Result<(), String> {
+    log::debug!("Invoked update_goal: {goal:?}");
+    goals::validate(&goal)?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("update_goal");
+
+    let before = audit::fetch_goal(&mut tx, &goal.id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de l'objectif"))?;
+    sqlx::query("UPDATE goals SET name = $1, \"targetAmount\" = $2, \"targetDate\" = $3, \"accountId\" = $4, \"tagId\" = $5 WHERE id = $6")
+        .bind(&goal.name)
+        .bind(goal.target_amount)
+        .bind(&goal.target_date)
+        .bind(&goal.account_id)
+        .bind(&goal.tag_id)
+        .bind(&goal.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "mise à jour de l'objectif"))?;
+
+    if before.is_some() {
+        audit::record(&mut tx, &mut op, audit::ENTITY_GOAL, &goal.id, audit::ACTION_UPDATE, before.as_ref(), Some(&goal))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la mise à jour de l'objectif"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[command]
+pub async fn delete_goal(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
+    log::debug!("Invoked delete_goal: {id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("delete_goal");
+
+    let before = audit::fetch_goal(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de l'objectif"))?;
+    sqlx::query("DELETE FROM goals WHERE id = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression de l'objectif"))?;
+
+    if before.is_some() {
+        audit::record(&mut tx, &mut op, audit::ENTITY_GOAL, &id, audit::ACTION_DELETE, before.as_ref(), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression de l'objectif"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Amount saved towards a goal at the end of `as_of` (YYYY-MM-DD, today by
+/// default), the monthly contribution it needs to be reached by its target
+/// date and the date it is reached at the pace of recent contributions.
+#[command]
+pub async fn get_goal_progress(pool: State<'_, DbPool>, id: String, as_of: Option<String>) -> Result<GoalProgress, String> {
+    log::debug!("Invoked get_goal_progress: {id} as_of={as_of:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let goal = audit::fetch_goal(&mut conn, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de l'objectif"))?
+        .ok_or_else(|| "Objectif introuvable.".to_string())?;
+    let as_of = match as_of {
+        Some(as_of) => as_of,
+        None => reconciliation::today(&mut conn)
+            .await
+            .map_err(|e| map_db_error(e, "lecture de la date"))?,
+    };
+    goals::progress(&mut conn, &goal, &as_of).await
+}
+
+// --- Categories ---
+#[command]
+pub async fn get_categories(pool: State<'_, DbPool>) -> Result<Vec<Category>, String> {
+    log::debug!("Invoked get_categories");
+    sqlx::query_as::<_, Category>("SELECT * FROM categories")
+        .fetch_all(&*pool)
+        .await
+        .map_err(|e| map_db_error(e, "récupération des catégories"))
+}
+
+/// True when `ancestor_id` is `id` itself or one of its ancestors.
+async fn is_category_ancestor(
+    conn: &mut sqlx::SqliteConnection,
+    id: &str,
+    ancestor_id: &str,
+) -> Result<bool, sqlx::Error> {
+    let found: i64 = sqlx::query_scalar(
+        "WITH RECURSIVE ancestors(id) AS (
+            SELECT $1
+            UNION
+            SELECT c.\"parentId\" FROM categories c JOIN ancestors a ON c.id = a.id WHERE c.\"parentId\" IS NOT NULL
+         )
+         SELECT count(*) FROM ancestors WHERE id = $2",
+    )
+    .bind(id)
+    .bind(ancestor_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(found > 0)
+}
+
+/// Rejects a parent that does not exist or would create a cycle.
+async fn check_category_parent(
+    conn: &mut sqlx::SqliteConnection,
+    id: &str,
+    parent_id: Option<&str>,
+) -> Result<(), String> {
+    let Some(parent_id) = parent_id else {
+        return Ok(());
+    };
+    if audit::fetch_category(conn, parent_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de catégorie"))?
+        .is_none()
+    {
+        return Err("La catégorie parente est introuvable.".to_string());
+    }
+    if is_category_ancestor(conn, parent_id, id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de l'arborescence des catégories"))?
+    {
+        return Err("Une catégorie ne peut pas être placée sous elle-même ou sous une de ses sous-catégories.".to_string());
+    }
+    Ok(())
+}
+
+/// Changes the parent of a category; its whole subtree moves with it.
+async fn set_category_parent(
+    conn: &mut sqlx::SqliteConnection,
+    op: &mut audit::Operation,
+    id: &str,
+    parent_id: Option<&str>,
+) -> Result<(), String> {
+    check_category_parent(conn, id, parent_id).await?;
+
+    let Some(before) = audit::fetch_category(conn, id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de catégorie"))?
+    else {
+        return Err("Catégorie introuvable.".to_string());
+    };
+    let after = Category {
+        parent_id: parent_id.map(str::to_string),
+        ..before.clone()
+    };
+
+    history::upsert_category(conn, &after)
+        .await
+        .map_err(|e| map_db_error(e, "déplacement de catégorie"))?;
+    audit::record(conn, op, audit::ENTITY_CATEGORY, id, audit::ACTION_UPDATE, Some(&before), Some(&after))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation du déplacement de catégorie"))?;
+    Ok(())
+}
+
+#[command]
+pub async fn add_category(pool: State<'_, DbPool>, category: Category) -> Result<(), String> {
+    log::debug!("Invoked add_category: {category:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("add_category");
+
+    check_category_parent(&mut tx, &category.id, category.parent_id.as_deref()).await?;
+
+    let result = sqlx::query("INSERT OR IGNORE INTO categories (id, name, icon, color, \"parentId\") VALUES ($1, $2, $3, $4, $5)")
+        .bind(&category.id)
+        .bind(&category.name)
+        .bind(&category.icon)
+        .bind(&category.color)
+        .bind(&category.parent_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "ajout de catégorie"))?;
+
+    if result.rows_affected() > 0 {
+        audit::record(&mut tx, &mut op, audit::ENTITY_CATEGORY, &category.id, audit::ACTION_CREATE, None, Some(&category))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de l'ajout de catégorie"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[command]
+pub async fn update_category(pool: State<'_, DbPool>, category: Category) -> Result<(), String> {
+    log::debug!("Invoked update_category: {category:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("update_category");
+
+    let before = audit::fetch_category(&mut tx, &category.id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de catégorie"))?;
+
+    check_category_parent(&mut tx, &category.id, category.parent_id.as_deref()).await?;
+
+    sqlx::query("UPDATE categories SET name = $1, icon = $2, color = $3, \"parentId\" = $4 WHERE id = $5")
+        .bind(&category.name)
+        .bind(&category.icon)
+        .bind(&category.color)
+        .bind(&category.parent_id)
+        .bind(&category.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "mise à jour de catégorie"))?;
+
+    if before.is_some() {
+        audit::record(&mut tx, &mut op, audit::ENTITY_CATEGORY, &category.id, audit::ACTION_UPDATE, before.as_ref(), Some(&category))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la mise à jour de catégorie"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Moves every transaction and scheduled transaction of `id` to `target_id`
+/// and its sub-categories under `children_parent_id`, then deletes `id`.
+/// Runs inside the caller's transaction.
+async fn remove_category(
+    conn: &mut sqlx::SqliteConnection,
+    op: &mut audit::Operation,
+    id: &str,
+    target_id: &str,
+    children_parent_id: Option<&str>,
+) -> Result<(), String> {
+    if id == UNCATEGORIZED_CATEGORY_ID || id == TRANSFER_CATEGORY_ID {
+        return Err("Cette catégorie système ne peut pas être supprimée.".to_string());
+    }
+    if id == target_id {
+        return Err("La catégorie de remplacement doit être différente.".to_string());
+    }
+    if audit::fetch_category(conn, target_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de catégorie"))?
+        .is_none()
+    {
+        return Err("La catégorie de remplacement est introuvable.".to_string());
+    }
+
+    let transactions = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE category = $1")
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des transactions de la catégorie"))?;
+
+    for before in &transactions {
+        let after = Transaction {
+            category: target_id.to_string(),
+            ..before.clone()
+        };
+        history::upsert_transaction(conn, &after)
+            .await
+            .map_err(|e| map_db_error(e, "réaffectation des transactions"))?;
+        audit::record(conn, op, audit::ENTITY_TRANSACTION, &after.id, audit::ACTION_UPDATE, Some(before), Some(&after))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la réaffectation des transactions"))?;
+    }
+
+    let scheduled = sqlx::query_as::<_, ScheduledTransaction>("SELECT * FROM scheduled_transactions WHERE category = $1")
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des échéances de la catégorie"))?;
+
+    for before in &scheduled {
+        let after = ScheduledTransaction {
+            category: target_id.to_string(),
+            ..before.clone()
+        };
+        history::upsert_scheduled(conn, &after)
+            .await
+            .map_err(|e| map_db_error(e, "réaffectation des échéances"))?;
+        audit::record(conn, op, audit::ENTITY_SCHEDULED, &after.id, audit::ACTION_UPDATE, Some(before), Some(&after))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la réaffectation des échéances"))?;
+    }
+
+    let rules = sqlx::query_as::<_, Rule>("SELECT * FROM rules WHERE \"setCategory\" = $1")
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des règles de la catégorie"))?;
+
+    for before in &rules {
+        // Assigning "uncategorized" is a no-op, so the rule loses its category action
+        let after = Rule {
+            set_category: Some(target_id.to_string()).filter(|t| t != UNCATEGORIZED_CATEGORY_ID),
+            ..before.clone()
+        };
+        history::upsert_rule(conn, &after)
+            .await
+            .map_err(|e| map_db_error(e, "réaffectation des règles"))?;
+        audit::record(conn, op, audit::ENTITY_RULE, &after.id, audit::ACTION_UPDATE, Some(before), Some(&after))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la réaffectation des règles"))?;
+    }
+
+    let before = audit::fetch_category(conn, id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de catégorie"))?;
+    let parent_id = before.as_ref().and_then(|c| c.parent_id.clone());
+
+    // The new parent of the children may itself sit below the removed category
+    if let Some(children_parent_id) = children_parent_id {
+        if is_category_ancestor(conn, children_parent_id, id)
+            .await
+            .map_err(|e| map_db_error(e, "lecture de l'arborescence des catégories"))?
+        {
+            set_category_parent(conn, op, children_parent_id, parent_id.as_deref()).await?;
+        }
+    }
+
+    let children: Vec<String> = sqlx::query_scalar("SELECT id FROM categories WHERE \"parentId\" = $1")
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des sous-catégories"))?;
+
+    for child in &children {
+        set_category_parent(conn, op, child, children_parent_id).await?;
+    }
+
+    sqlx::query("DELETE FROM categories WHERE id = $1")
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| map_db_error(e, "suppression de catégorie"))?;
+
+    if let Some(before) = &before {
+        audit::record(conn, op, audit::ENTITY_CATEGORY, id, audit::ACTION_DELETE, Some(before), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression de catégorie"))?;
+    }
+
+    Ok(())
+}
+
+/// Deletes a category after moving its transactions and scheduled
+/// transactions to `target_id` (use "uncategorized" to leave them uncategorized).
+/// Sub-categories move up to the parent of the deleted category.
+#[command]
+pub async fn delete_category(
+    pool: State<'_, DbPool>,
+    id: String,
+    target_id: String,
+) -> Result<(), String> {
+    log::debug!("Invoked delete_category: {id} -> {target_id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("delete_category");
+
+    let parent_id = audit::fetch_category(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de catégorie"))?
+        .and_then(|c| c.parent_id);
+    remove_category(&mut tx, &mut op, &id, &target_id, parent_id.as_deref()).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Moves all references of `source_ids` into `target_id` and deletes the
+/// sources. Their sub-categories are attached to `target_id`.
+#[command]
+pub async fn merge_categories(
+    pool: State<'_, DbPool>,
+    source_ids: Vec<String>,
+    target_id: String,
+) -> Result<(), String> {
+    log::debug!("Invoked merge_categories: {source_ids:?} -> {target_id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("merge_categories");
+
+    for id in source_ids.iter().filter(|id| **id != target_id) {
+        remove_category(&mut tx, &mut op, id, &target_id, Some(&target_id)).await?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Moves a category and its sub-categories under `parent_id`, or to the top level.
+#[command]
+pub async fn move_category(
+    pool: State<'_, DbPool>,
+    id: String,
+    parent_id: Option<String>,
+) -> Result<(), String> {
+    log::debug!("Invoked move_category: {id} -> {parent_id:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("move_category");
+
+    set_category_parent(&mut tx, &mut op, &id, parent_id.as_deref()).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Income and expense per category, with sub-category amounts rolled up into
+/// their parents. Transfers are excluded. Dates are YYYY-MM-DD, both inclusive;
+/// `tag_ids` keeps only transactions carrying any of the tags.
+#[command]
+pub async fn get_category_totals(
+    pool: State<'_, DbPool>,
+    from: Option<String>,
+    to: Option<String>,
+    account_id: Option<String>,
+    tag_ids: Option<Vec<String>>,
+) -> Result<Vec<CategoryTotal>, String> {
+    log::debug!("Invoked get_category_totals: from={from:?}, to={to:?}, account_id={account_id:?}, tag_ids={tag_ids:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    reports::category_totals(&mut conn, from, to, account_id, &tag_ids.unwrap_or_default())
+        .await
+        .map_err(|e| map_db_error(e, "calcul des totaux par catégorie"))
+}
+
+/// Ranks likely categories for a transaction from the categorized history,
+/// best first (5 by default).
+#[command]
+pub async fn suggest_category(
+    pool: State<'_, DbPool>,
+    description: String,
+    amount: f64,
+    limit: Option<usize>,
+) -> Result<Vec<CategorySuggestion>, String> {
+    log::debug!("Invoked suggest_category: {description} ({amount})");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let model = suggestions::CategoryModel::load(&mut conn)
+        .await
+        .map_err(|e| map_db_error(e, "suggestion de catégorie"))?;
+    Ok(model.suggest(&description, amount, limit.unwrap_or(5)))
+}
+
+/// suggest_category for many (description, amount) pairs at once, e.g. the
+/// rows of an import, with the history read once. Results are in the same
+/// order.
+#[command]
+pub async fn suggest_categories(
+    pool: State<'_, DbPool>,
+    transactions: Vec<(String, f64)>,
+    limit: Option<usize>,
+) -> Result<Vec<Vec<CategorySuggestion>>, String> {
+    log::debug!("Invoked suggest_categories: {} transactions", transactions.len());
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let model = suggestions::CategoryModel::load(&mut conn)
+        .await
+        .map_err(|e| map_db_error(e, "suggestion de catégories"))?;
+    let limit = limit.unwrap_or(5);
+    Ok(transactions
+        .iter()
+        .map(|(description, amount)| model.suggest(description, *amount, limit))
+        .collect())
+}
+
+// --- Scheduled Transactions ---
+#[command]
+pub async fn get_scheduled(pool: State<'_, DbPool>) -> Result<Vec<ScheduledTransaction>, String> {
+    log::debug!("Invoked get_scheduled");
+    sqlx::query_as::<_, ScheduledTransaction>("SELECT * FROM scheduled_transactions")
+        .fetch_all(&*pool)
+        .await
+        .map_err(|e| map_db_error(e, "récupération des échéances"))
+}
+
+#[command]
+pub async fn add_scheduled(
+    pool: State<'_, DbPool>,
+    scheduled: ScheduledTransaction,
+) -> Result<(), String> {
+    log::debug!("Invoked add_scheduled: {scheduled:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("add_scheduled");
+
+    sqlx::query(
+        "INSERT INTO scheduled_transactions (id, description, amount, \"type\", frequency, \"accountId\", \"nextDate\", category, \"toAccountId\", \"includeInForecast\", \"endDate\", \"payeeId\") VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"
+    )
+    .bind(&scheduled.id)
+    .bind(&scheduled.description)
+    .bind(scheduled.amount)
+    .bind(&scheduled.transaction_type)
+    .bind(&scheduled.frequency)
+    .bind(&scheduled.account_id)
+    .bind(&scheduled.next_date)
+    .bind(&scheduled.category)
+    .bind(&scheduled.to_account_id)
+    .bind(scheduled.include_in_forecast)
+    .bind(&scheduled.end_date)
+    .bind(&scheduled.payee_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| map_db_error(e, "ajout d'échéance"))?;
+
+    audit::record(&mut tx, &mut op, audit::ENTITY_SCHEDULED, &scheduled.id, audit::ACTION_CREATE, None, Some(&scheduled))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de l'ajout d'échéance"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[command]
+pub async fn update_scheduled(
+    pool: State<'_, DbPool>,
+    scheduled: ScheduledTransaction,
+) -> Result<(), String> {
+    log::debug!("Invoked update_scheduled: {scheduled:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("update_scheduled");
+
+    let before = audit::fetch_scheduled(&mut tx, &scheduled.id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture d'échéance"))?;
+
+    sqlx::query(
+        "UPDATE scheduled_transactions SET description = $1, amount = $2, \"type\" = $3, frequency = $4, \"accountId\" = $5, \"nextDate\" = $6, category = $7, \"toAccountId\" = $8, \"includeInForecast\" = $9, \"endDate\" = $10, \"payeeId\" = $11 WHERE id = $12"
+    )
+    .bind(&scheduled.description)
+    .bind(scheduled.amount)
+    .bind(&scheduled.transaction_type)
+    .bind(&scheduled.frequency)
+    .bind(&scheduled.account_id)
+    .bind(&scheduled.next_date)
+    .bind(&scheduled.category)
+    .bind(&scheduled.to_account_id)
+    .bind(scheduled.include_in_forecast)
+    .bind(&scheduled.end_date)
+    .bind(&scheduled.payee_id)
+    .bind(&scheduled.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| map_db_error(e, "mise à jour d'échéance"))?;
+
+    if before.is_some() {
+        audit::record(&mut tx, &mut op, audit::ENTITY_SCHEDULED, &scheduled.id, audit::ACTION_UPDATE, before.as_ref(), Some(&scheduled))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la mise à jour d'échéance"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[command]
+pub async fn delete_scheduled(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
+    log::debug!("Invoked delete_scheduled: {id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("delete_scheduled");
+
+    let before = audit::fetch_scheduled(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture d'échéance"))?;
+
+    sqlx::query("DELETE FROM scheduled_transactions WHERE id = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression d'échéance"))?;
+
+    if let Some(before) = &before {
+        audit::record(&mut tx, &mut op, audit::ENTITY_SCHEDULED, &id, audit::ACTION_DELETE, Some(before), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression d'échéance"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// --- Payees ---
+#[command]
+pub async fn get_payees(pool: State<'_, DbPool>) -> Result<Vec<Payee>, String> {
+    log::debug!("Invoked get_payees");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    payees::fetch_payees(&mut conn)
+        .await
+        .map_err(|e| map_db_error(e, "récupération des bénéficiaires"))
+}
+
+#[command]
+pub async fn add_payee(pool: State<'_, DbPool>, payee: Payee) -> Result<(), String> {
+    log::debug!("Invoked add_payee: {payee:?}");
+    payees::validate(&payee)?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("add_payee");
+
+    if audit::fetch_payee(&mut tx, &payee.id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de bénéficiaire"))?
+        .is_some()
+    {
+        return Err("Ce bénéficiaire existe déjà.".to_string());
+    }
+
+    history::upsert_payee(&mut tx, &payee)
+        .await
+        .map_err(|e| map_db_error(e, "ajout de bénéficiaire"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_PAYEE, &payee.id, audit::ACTION_CREATE, None, Some(&payee))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de l'ajout de bénéficiaire"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[command]
+pub async fn update_payee(pool: State<'_, DbPool>, payee: Payee) -> Result<(), String> {
+    log::debug!("Invoked update_payee: {payee:?}");
+    payees::validate(&payee)?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("update_payee");
+
+    let Some(before) = audit::fetch_payee(&mut tx, &payee.id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de bénéficiaire"))?
+    else {
+        return Err("Bénéficiaire introuvable.".to_string());
+    };
+
+    history::upsert_payee(&mut tx, &payee)
+        .await
+        .map_err(|e| map_db_error(e, "mise à jour de bénéficiaire"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_PAYEE, &payee.id, audit::ACTION_UPDATE, Some(&before), Some(&payee))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de la mise à jour de bénéficiaire"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Points every transaction, scheduled transaction and rule of `id` to
+/// `target_id` (or to no payee), then deletes `id`. Runs inside the caller's
+/// transaction.
+async fn remove_payee(
+    conn: &mut sqlx::SqliteConnection,
+    op: &mut audit::Operation,
+    id: &str,
+    target_id: Option<&str>,
+) -> Result<(), String> {
+    let transactions = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE \"payeeId\" = $1")
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des transactions du bénéficiaire"))?;
+
+    for before in &transactions {
+        let after = Transaction {
+            payee_id: target_id.map(str::to_string),
+            ..before.clone()
+        };
+        history::upsert_transaction(conn, &after)
+            .await
+            .map_err(|e| map_db_error(e, "réaffectation des transactions"))?;
+        audit::record(conn, op, audit::ENTITY_TRANSACTION, &after.id, audit::ACTION_UPDATE, Some(before), Some(&after))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la réaffectation des transactions"))?;
+    }
+
+    let scheduled = sqlx::query_as::<_, ScheduledTransaction>("SELECT * FROM scheduled_transactions WHERE \"payeeId\" = $1")
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des échéances du bénéficiaire"))?;
+
+    for before in &scheduled {
+        let after = ScheduledTransaction {
+            payee_id: target_id.map(str::to_string),
+            ..before.clone()
+        };
+        history::upsert_scheduled(conn, &after)
+            .await
+            .map_err(|e| map_db_error(e, "réaffectation des échéances"))?;
+        audit::record(conn, op, audit::ENTITY_SCHEDULED, &after.id, audit::ACTION_UPDATE, Some(before), Some(&after))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la réaffectation des échéances"))?;
+    }
+
+    let rules = sqlx::query_as::<_, Rule>("SELECT * FROM rules WHERE \"setPayee\" = $1")
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des règles du bénéficiaire"))?;
+
+    for before in &rules {
+        let after = Rule {
+            set_payee: target_id.map(str::to_string),
+            ..before.clone()
+        };
+        history::upsert_rule(conn, &after)
+            .await
+            .map_err(|e| map_db_error(e, "réaffectation des règles"))?;
+        audit::record(conn, op, audit::ENTITY_RULE, &after.id, audit::ACTION_UPDATE, Some(before), Some(&after))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la réaffectation des règles"))?;
+    }
+
+    let before = audit::fetch_payee(conn, id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de bénéficiaire"))?;
+
+    sqlx::query("DELETE FROM payees WHERE id = $1")
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| map_db_error(e, "suppression de bénéficiaire"))?;
+
+    if let Some(before) = &before {
+        audit::record(conn, op, audit::ENTITY_PAYEE, id, audit::ACTION_DELETE, Some(before), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression de bénéficiaire"))?;
+    }
+    Ok(())
+}
+
+/// Deletes a payee; its transactions, scheduled transactions and rules are
+/// left without payee.
+#[command]
+pub async fn delete_payee(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
+    log::debug!("Invoked delete_payee: {id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("delete_payee");
+
+    remove_payee(&mut tx, &mut op, &id, None).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Moves everything of `source_ids` to `target_id` and deletes the sources.
+/// Their names and aliases become aliases of the target so future
+/// descriptions keep matching.
+#[command]
+pub async fn merge_payees(
+    pool: State<'_, DbPool>,
+    source_ids: Vec<String>,
+    target_id: String,
+) -> Result<(), String> {
+    log::debug!("Invoked merge_payees: {source_ids:?} -> {target_id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("merge_payees");
+
+    let Some(target) = audit::fetch_payee(&mut tx, &target_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de bénéficiaire"))?
+    else {
+        return Err("Le bénéficiaire cible est introuvable.".to_string());
+    };
+
+    let mut merged = target.clone();
+    for id in source_ids.iter().filter(|id| **id != target_id) {
+        let Some(source) = audit::fetch_payee(&mut tx, id)
+            .await
+            .map_err(|e| map_db_error(e, "lecture de bénéficiaire"))?
+        else {
+            continue;
+        };
+        for alias in std::iter::once(source.name).chain(source.aliases) {
+            if alias != merged.name && !merged.aliases.contains(&alias) {
+                merged.aliases.push(alias);
+            }
+        }
+        remove_payee(&mut tx, &mut op, id, Some(&target_id)).await?;
+    }
+
+    history::upsert_payee(&mut tx, &merged)
+        .await
+        .map_err(|e| map_db_error(e, "fusion des bénéficiaires"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_PAYEE, &target_id, audit::ACTION_UPDATE, Some(&target), Some(&merged))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de la fusion des bénéficiaires"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Matches existing transactions against payee names and aliases, optionally
+/// for one account. Only transactions without payee are touched unless `all`
+/// is set, and reconciled ones only with `force`. Returns the number of
+/// transactions changed.
+#[command]
+pub async fn assign_payees(
+    pool: State<'_, DbPool>,
+    account_id: Option<String>,
+    all: Option<bool>,
+    force: Option<bool>,
+) -> Result<u64, String> {
+    log::debug!("Invoked assign_payees: account_id={account_id:?}, all={all:?}, force={force:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("assign_payees");
+
+    let changed = payees::assign_existing(&mut tx, &mut op, account_id, all.unwrap_or(false), force.unwrap_or(false))
+        .await
+        .map_err(|e| map_db_error(e, "attribution des bénéficiaires"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(changed)
+}
+
+/// Income and expense per payee, largest spending first. Dates are
+/// YYYY-MM-DD, both inclusive; `tag_ids` keeps only transactions carrying
+/// any of the tags.
+#[command]
+pub async fn get_payee_totals(
+    pool: State<'_, DbPool>,
+    from: Option<String>,
+    to: Option<String>,
+    account_id: Option<String>,
+    tag_ids: Option<Vec<String>>,
+) -> Result<Vec<PayeeTotal>, String> {
+    log::debug!("Invoked get_payee_totals: from={from:?}, to={to:?}, account_id={account_id:?}, tag_ids={tag_ids:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    payees::payee_totals(&mut conn, from, to, account_id, &tag_ids.unwrap_or_default())
+        .await
+        .map_err(|e| map_db_error(e, "calcul des totaux par bénéficiaire"))
+}
+
+// --- Tags ---
+#[command]
+pub async fn get_tags(pool: State<'_, DbPool>) -> Result<Vec<Tag>, String> {
+    log::debug!("Invoked get_tags");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    tags::fetch_tags(&mut conn)
+        .await
+        .map_err(|e| map_db_error(e, "récupération des tags"))
+}
+
+/// Tag names are unique regardless of case.
+async fn check_tag_name(conn: &mut sqlx::SqliteConnection, tag: &Tag) -> Result<(), String> {
+    let taken: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tags WHERE name = $1 COLLATE NOCASE AND id != $2")
+        .bind(tag.name.trim())
+        .bind(&tag.id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des tags"))?;
+    if taken > 0 {
+        return Err("Un tag portant ce nom existe déjà.".to_string());
+    }
+    Ok(())
+}
+
+#[command]
+pub async fn add_tag(pool: State<'_, DbPool>, tag: Tag) -> Result<(), String> {
+    log::debug!("Invoked add_tag: {tag:?}");
+    tags::validate(&tag)?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("add_tag");
+
+    if audit::fetch_tag(&mut tx, &tag.id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de tag"))?
+        .is_some()
+    {
+        return Err("Ce tag existe déjà.".to_string());
+    }
+    check_tag_name(&mut tx, &tag).await?;
+
+    history::upsert_tag(&mut tx, &tag)
+        .await
+        .map_err(|e| map_db_error(e, "ajout de tag"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_TAG, &tag.id, audit::ACTION_CREATE, None, Some(&tag))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de l'ajout de tag"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[command]
+pub async fn update_tag(pool: State<'_, DbPool>, tag: Tag) -> Result<(), String> {
+    log::debug!("Invoked update_tag: {tag:?}");
+    tags::validate(&tag)?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("update_tag");
+
+    let Some(before) = audit::fetch_tag(&mut tx, &tag.id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de tag"))?
+    else {
+        return Err("Tag introuvable.".to_string());
+    };
+    check_tag_name(&mut tx, &tag).await?;
+
+    history::upsert_tag(&mut tx, &tag)
+        .await
+        .map_err(|e| map_db_error(e, "mise à jour de tag"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_TAG, &tag.id, audit::ACTION_UPDATE, Some(&before), Some(&tag))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de la mise à jour de tag"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Deletes a tag, removing it from its transactions and from the rules that
+/// set it.
+#[command]
+pub async fn delete_tag(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
+    log::debug!("Invoked delete_tag: {id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("delete_tag");
+
+    let transaction_ids: Vec<String> = sqlx::query_scalar("SELECT \"transactionId\" FROM transaction_tags WHERE \"tagId\" = $1")
+        .bind(&id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des transactions du tag"))?;
+
+    for transaction_id in &transaction_ids {
+        tags::unlink(&mut tx, &mut op, transaction_id, &id)
+            .await
+            .map_err(|e| map_db_error(e, "retrait du tag des transactions"))?;
+    }
+
+    let rules = sqlx::query_as::<_, Rule>("SELECT * FROM rules WHERE EXISTS (SELECT 1 FROM json_each(\"setTags\") WHERE value = $1)")
+        .bind(&id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des règles du tag"))?;
+
+    for before in &rules {
+        let after = Rule {
+            set_tags: before.set_tags.iter().filter(|tag_id| **tag_id != id).cloned().collect(),
+            ..before.clone()
+        };
+        history::upsert_rule(&mut tx, &after)
+            .await
+            .map_err(|e| map_db_error(e, "retrait du tag des règles"))?;
+        audit::record(&mut tx, &mut op, audit::ENTITY_RULE, &after.id, audit::ACTION_UPDATE, Some(before), Some(&after))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation du retrait du tag des règles"))?;
+    }
+
+    let goals = sqlx::query_as::<_, Goal>("SELECT * FROM goals WHERE \"tagId\" = $1")
+        .bind(&id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des objectifs du tag"))?;
+
+    sqlx::query("DELETE FROM goals WHERE \"tagId\" = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression des objectifs du tag"))?;
+    for g in &goals {
+        audit::record(&mut tx, &mut op, audit::ENTITY_GOAL, &g.id, audit::ACTION_DELETE, Some(g), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression des objectifs du tag"))?;
+    }
+
+    let before = audit::fetch_tag(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de tag"))?;
+
+    sqlx::query("DELETE FROM tags WHERE id = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression de tag"))?;
+
+    if let Some(before) = &before {
+        audit::record(&mut tx, &mut op, audit::ENTITY_TAG, &id, audit::ACTION_DELETE, Some(before), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression de tag"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Every transaction/tag pair; the frontend groups them per transaction.
+#[command]
+pub async fn get_transaction_tags(pool: State<'_, DbPool>) -> Result<Vec<TransactionTag>, String> {
+    log::debug!("Invoked get_transaction_tags");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    tags::fetch_links(&mut conn)
+        .await
+        .map_err(|e| map_db_error(e, "récupération des tags des transactions"))
+}
+
+/// Adds every tag to every transaction as one undoable operation. Returns
+/// how many links were created; pairs already linked are skipped.
+#[command]
+pub async fn tag_transactions(
+    pool: State<'_, DbPool>,
+    transaction_ids: Vec<String>,
+    tag_ids: Vec<String>,
+) -> Result<u64, String> {
+    log::debug!("Invoked tag_transactions: {transaction_ids:?} + {tag_ids:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("tag_transactions");
+
+    for tag_id in &tag_ids {
+        if audit::fetch_tag(&mut tx, tag_id)
+            .await
+            .map_err(|e| map_db_error(e, "lecture de tag"))?
+            .is_none()
+        {
+            return Err(format!("Tag introuvable : {tag_id}"));
+        }
+    }
+
+    let mut linked = 0;
+    for transaction_id in &transaction_ids {
+        if audit::fetch_transaction(&mut tx, transaction_id)
+            .await
+            .map_err(|e| map_db_error(e, "lecture de transaction"))?
+            .is_none()
+        {
+            return Err(format!("Transaction introuvable : {transaction_id}"));
+        }
+        for tag_id in &tag_ids {
+            if tags::link(&mut tx, &mut op, transaction_id, tag_id)
+                .await
+                .map_err(|e| map_db_error(e, "ajout de tag"))?
+            {
+                linked += 1;
+            }
+        }
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(linked)
+}
+
+/// Removes every tag from every transaction as one undoable operation.
+/// Returns how many links were removed.
+#[command]
+pub async fn untag_transactions(
+    pool: State<'_, DbPool>,
+    transaction_ids: Vec<String>,
+    tag_ids: Vec<String>,
+) -> Result<u64, String> {
+    log::debug!("Invoked untag_transactions: {transaction_ids:?} - {tag_ids:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("untag_transactions");
+
+    let mut unlinked = 0;
+    for transaction_id in &transaction_ids {
+        for tag_id in &tag_ids {
+            if tags::unlink(&mut tx, &mut op, transaction_id, tag_id)
+                .await
+                .map_err(|e| map_db_error(e, "retrait de tag"))?
+            {
+                unlinked += 1;
+            }
+        }
+    }
+
     tx.commit().await.map_err(|e| e.to_string())?;
-    Ok(())
+    Ok(unlinked)
 }
 
-// --- Transactions ---
+// --- Attachments ---
 #[command]
-pub async fn get_transactions(pool: State<'_, DbPool>) -> Result<Vec<Transaction>, String> {
-    log::debug!("Invoked get_transactions");
-    sqlx::query_as::<_, Transaction>("SELECT * FROM transactions ORDER BY date DESC")
-        .fetch_all(&*pool)
+pub async fn get_attachments(
+    pool: State<'_, DbPool>,
+    transaction_id: Option<String>,
+) -> Result<Vec<Attachment>, String> {
+    log::debug!("Invoked get_attachments: {transaction_id:?}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    attachments::fetch_attachments(&mut conn, transaction_id.as_deref())
         .await
-        .map_err(|e| map_db_error(e, "récupération des transactions"))
+        .map_err(|e| map_db_error(e, "récupération des pièces jointes"))
 }
 
+/// Copies a PDF, JPEG or PNG file into the attachments folder and joins it
+/// to a transaction. Identical files are stored once.
 #[command]
-pub async fn add_transaction(
+pub async fn add_attachment(
+    app: AppHandle,
     pool: State<'_, DbPool>,
-    transaction: Transaction,
-) -> Result<(), String> {
-    log::debug!("Invoked add_transaction: {transaction:?}");
+    id: String,
+    transaction_id: String,
+    path: String,
+) -> Result<Attachment, String> {
+    log::debug!("Invoked add_attachment: {id} -> {transaction_id} ({path})");
+    let content = std::fs::read(&path).map_err(|e| format!("Lecture du fichier impossible : {e}"))?;
+    let mime_type = attachments::detect_mime_type(&content)
+        .ok_or("Format non pris en charge : seuls les fichiers PDF, JPEG et PNG peuvent être joints.")?;
+    let original_name = std::path::Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let dir = attachments::dir(&app)?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("add_attachment");
+
+    if audit::fetch_transaction(&mut tx, &transaction_id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de transaction"))?
+        .is_none()
+    {
+        return Err("Transaction introuvable.".to_string());
+    }
+    if audit::fetch_attachment(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de pièce jointe"))?
+        .is_some()
+    {
+        return Err("Cette pièce jointe existe déjà.".to_string());
+    }
+
+    // A file copied for a failed insert is left to gc_attachments, which spares
+    // it for attachments::GC_MIN_AGE so the insert below has time to commit
+    let hash = attachments::store(&dir, &content, mime_type)
+        .map_err(|e| format!("Copie du fichier impossible : {e}"))?;
+
     sqlx::query(
-        "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, category, description, checked, \"isTransfer\", \"linkedTransactionId\") VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)"
+        "INSERT INTO attachments (id, \"transactionId\", hash, \"mimeType\", \"originalName\", size) VALUES ($1, $2, $3, $4, $5, $6)"
     )
-    .bind(transaction.id)
-    .bind(transaction.date)
-    .bind(transaction.account_id)
-    .bind(transaction.transaction_type)
-    .bind(transaction.amount)
-    .bind(transaction.category)
-    .bind(transaction.description)
-    .bind(transaction.checked)
-    .bind(transaction.is_transfer)
-    .bind(transaction.linked_transaction_id)
-    .execute(&*pool)
+    .bind(&id)
+    .bind(&transaction_id)
+    .bind(&hash)
+    .bind(mime_type)
+    .bind(&original_name)
+    .bind(content.len() as i64)
+    .execute(&mut *tx)
     .await
-    .map_err(|e| map_db_error(e, "ajout de transaction"))?;
-    Ok(())
+    .map_err(|e| map_db_error(e, "ajout de pièce jointe"))?;
+
+    let attachment = audit::fetch_attachment(&mut tx, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de pièce jointe"))?
+        .ok_or("Pièce jointe introuvable.")?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_ATTACHMENT, &id, audit::ACTION_CREATE, None, Some(&attachment))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de l'ajout de pièce jointe"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(attachment)
 }
 
+/// Opens an attachment with the default application of the system.
 #[command]
-pub async fn update_transaction(
-    pool: State<'_, DbPool>,
-    transaction: Transaction,
-) -> Result<(), String> {
-    log::debug!("Invoked update_transaction: {transaction:?}");
-    sqlx::query(
-        "UPDATE transactions SET date = $1, \"accountId\" = $2, \"type\" = $3, amount = $4, category = $5, description = $6, checked = $7, \"isTransfer\" = $8, \"linkedTransactionId\" = $9 WHERE id = $10"
-    )
-    .bind(transaction.date)
-    .bind(transaction.account_id)
-    .bind(transaction.transaction_type)
-    .bind(transaction.amount)
-    .bind(transaction.category)
-    .bind(transaction.description)
-    .bind(transaction.checked)
-    .bind(transaction.is_transfer)
-    .bind(transaction.linked_transaction_id)
-    .bind(transaction.id)
-    .execute(&*pool)
-    .await
-    .map_err(|e| map_db_error(e, "mise à jour de transaction"))?;
-    Ok(())
+pub async fn open_attachment(app: AppHandle, pool: State<'_, DbPool>, id: String) -> Result<(), String> {
+    log::debug!("Invoked open_attachment: {id}");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let attachment = audit::fetch_attachment(&mut conn, &id)
+        .await
+        .map_err(|e| map_db_error(e, "lecture de pièce jointe"))?
+        .ok_or("Pièce jointe introuvable.")?;
+
+    let path = attachments::file_path(&attachments::dir(&app)?, &attachment);
+    if !path.exists() {
+        return Err("Le fichier de la pièce jointe est introuvable.".to_string());
+    }
+    app.opener()
+        .open_path(path.to_string_lossy(), None::<&str>)
+        .map_err(|e| format!("Ouverture de la pièce jointe impossible : {e}"))
 }
 
+/// Removes an attachment from its transaction. The file itself is only
+/// deleted by gc_attachments, so the removal can be undone.
 #[command]
-pub async fn delete_transaction(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
-    log::debug!("Invoked delete_transaction: {id}");
-    sqlx::query("DELETE FROM transactions WHERE id = $1")
-        .bind(id)
-        .execute(&*pool)
+pub async fn delete_attachment(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
+    log::debug!("Invoked delete_attachment: {id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("delete_attachment");
+
+    attachments::delete(&mut tx, &mut op, &id)
         .await
-        .map_err(|e| map_db_error(e, "suppression de transaction"))?;
+        .map_err(|e| map_db_error(e, "suppression de pièce jointe"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
     Ok(())
 }
 
-// --- Categories ---
+/// Deletes the stored files that no attachment, nor any undoable change,
+/// refers to, leaving out those written in the last minutes. Returns how many
+/// files were removed.
 #[command]
-pub async fn get_categories(pool: State<'_, DbPool>) -> Result<Vec<Category>, String> {
-    log::debug!("Invoked get_categories");
-    sqlx::query_as::<_, Category>("SELECT * FROM categories")
-        .fetch_all(&*pool)
-        .await
-        .map_err(|e| map_db_error(e, "récupération des catégories"))
+pub async fn gc_attachments(app: AppHandle, pool: State<'_, DbPool>) -> Result<u64, String> {
+    log::debug!("Invoked gc_attachments");
+    let dir = attachments::dir(&app)?;
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    attachments::collect_garbage(&mut conn, &dir).await
 }
 
+// --- Rules ---
 #[command]
-pub async fn add_category(pool: State<'_, DbPool>, category: Category) -> Result<(), String> {
-    log::debug!("Invoked add_category: {category:?}");
-    sqlx::query("INSERT OR IGNORE INTO categories (id, name, icon, color) VALUES ($1, $2, $3, $4)")
-        .bind(category.id)
-        .bind(category.name)
-        .bind(category.icon)
-        .bind(category.color)
-        .execute(&*pool)
+pub async fn get_rules(pool: State<'_, DbPool>) -> Result<Vec<Rule>, String> {
+    log::debug!("Invoked get_rules");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    rules::fetch_rules(&mut conn)
         .await
-        .map_err(|e| map_db_error(e, "ajout de catégorie"))?;
-    Ok(())
+        .map_err(|e| map_db_error(e, "récupération des règles"))
 }
 
+/// Adds a rule after the existing ones; the stored rule is returned.
 #[command]
-pub async fn update_category(pool: State<'_, DbPool>, category: Category) -> Result<(), String> {
-    log::debug!("Invoked update_category: {category:?}");
-    sqlx::query("UPDATE categories SET name = $1, icon = $2, color = $3 WHERE id = $4")
-        .bind(category.name)
-        .bind(category.icon)
-        .bind(category.color)
-        .bind(category.id)
-        .execute(&*pool)
+pub async fn add_rule(pool: State<'_, DbPool>, mut rule: Rule) -> Result<Rule, String> {
+    log::debug!("Invoked add_rule: {rule:?}");
+    rules::validate(&rule)?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("add_rule");
+
+    if audit::fetch_rule(&mut tx, &rule.id)
         .await
-        .map_err(|e| map_db_error(e, "mise à jour de catégorie"))?;
-    Ok(())
+        .map_err(|e| map_db_error(e, "lecture de règle"))?
+        .is_some()
+    {
+        return Err("Cette règle existe déjà.".to_string());
+    }
+
+    rule.position = sqlx::query_scalar("SELECT COALESCE(MAX(position) + 1, 0) FROM rules")
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des règles"))?;
+
+    history::upsert_rule(&mut tx, &rule)
+        .await
+        .map_err(|e| map_db_error(e, "ajout de règle"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_RULE, &rule.id, audit::ACTION_CREATE, None, Some(&rule))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de l'ajout de règle"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(rule)
 }
 
 #[command]
-pub async fn delete_category(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
-    log::debug!("Invoked delete_category: {id}");
-    sqlx::query("DELETE FROM categories WHERE id = $1")
-        .bind(id)
-        .execute(&*pool)
+pub async fn update_rule(pool: State<'_, DbPool>, rule: Rule) -> Result<(), String> {
+    log::debug!("Invoked update_rule: {rule:?}");
+    rules::validate(&rule)?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("update_rule");
+
+    let Some(before) = audit::fetch_rule(&mut tx, &rule.id)
         .await
-        .map_err(|e| map_db_error(e, "suppression de catégorie"))?;
+        .map_err(|e| map_db_error(e, "lecture de règle"))?
+    else {
+        return Err("Règle introuvable.".to_string());
+    };
+
+    history::upsert_rule(&mut tx, &rule)
+        .await
+        .map_err(|e| map_db_error(e, "mise à jour de règle"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_RULE, &rule.id, audit::ACTION_UPDATE, Some(&before), Some(&rule))
+        .await
+        .map_err(|e| map_db_error(e, "journalisation de la mise à jour de règle"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
     Ok(())
 }
 
-// --- Scheduled Transactions ---
 #[command]
-pub async fn get_scheduled(pool: State<'_, DbPool>) -> Result<Vec<ScheduledTransaction>, String> {
-    log::debug!("Invoked get_scheduled");
-    sqlx::query_as::<_, ScheduledTransaction>("SELECT * FROM scheduled_transactions")
-        .fetch_all(&*pool)
+pub async fn delete_rule(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
+    log::debug!("Invoked delete_rule: {id}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("delete_rule");
+
+    let before = audit::fetch_rule(&mut tx, &id)
         .await
-        .map_err(|e| map_db_error(e, "récupération des échéances"))
-}
+        .map_err(|e| map_db_error(e, "lecture de règle"))?;
 
-#[command]
-pub async fn add_scheduled(
-    pool: State<'_, DbPool>,
-    scheduled: ScheduledTransaction,
-) -> Result<(), String> {
-    log::debug!("Invoked add_scheduled: {scheduled:?}");
-    sqlx::query(
-        "INSERT INTO scheduled_transactions (id, description, amount, \"type\", frequency, \"accountId\", \"nextDate\", category, \"toAccountId\", \"includeInForecast\", \"endDate\") VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)"
-    )
-    .bind(scheduled.id)
-    .bind(scheduled.description)
-    .bind(scheduled.amount)
-    .bind(scheduled.transaction_type)
-    .bind(scheduled.frequency)
-    .bind(scheduled.account_id)
-    .bind(scheduled.next_date)
-    .bind(scheduled.category)
-    .bind(scheduled.to_account_id)
-    .bind(scheduled.include_in_forecast)
-    .bind(scheduled.end_date)
-    .execute(&*pool)
-    .await
-    .map_err(|e| map_db_error(e, "ajout d'échéance"))?;
+    sqlx::query("DELETE FROM rules WHERE id = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| map_db_error(e, "suppression de règle"))?;
+
+    if let Some(before) = &before {
+        audit::record(&mut tx, &mut op, audit::ENTITY_RULE, &id, audit::ACTION_DELETE, Some(before), None)
+            .await
+            .map_err(|e| map_db_error(e, "journalisation de la suppression de règle"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
     Ok(())
 }
 
+/// Sets the evaluation order: `ids[0]` runs first. Rules left out keep
+/// running after the listed ones.
 #[command]
-pub async fn update_scheduled(
-    pool: State<'_, DbPool>,
-    scheduled: ScheduledTransaction,
-) -> Result<(), String> {
-    log::debug!("Invoked update_scheduled: {scheduled:?}");
-    sqlx::query(
-        "UPDATE scheduled_transactions SET description = $1, amount = $2, \"type\" = $3, frequency = $4, \"accountId\" = $5, \"nextDate\" = $6, category = $7, \"toAccountId\" = $8, \"includeInForecast\" = $9, \"endDate\" = $10 WHERE id = $11"
-    )
-    .bind(scheduled.description)
-    .bind(scheduled.amount)
-    .bind(scheduled.transaction_type)
-    .bind(scheduled.frequency)
-    .bind(scheduled.account_id)
-    .bind(scheduled.next_date)
-    .bind(scheduled.category)
-    .bind(scheduled.to_account_id)
-    .bind(scheduled.include_in_forecast)
-    .bind(scheduled.end_date)
-    .bind(scheduled.id)
-    .execute(&*pool)
-    .await
-    .map_err(|e| map_db_error(e, "mise à jour d'échéance"))?;
+pub async fn reorder_rules(pool: State<'_, DbPool>, ids: Vec<String>) -> Result<(), String> {
+    log::debug!("Invoked reorder_rules: {ids:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("reorder_rules");
+
+    let current = rules::fetch_rules(&mut tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des règles"))?;
+    let (mut ordered, rest): (Vec<Rule>, Vec<Rule>) = current.into_iter().partition(|r| ids.contains(&r.id));
+    ordered.sort_by_key(|r| ids.iter().position(|id| *id == r.id));
+
+    for (position, before) in ordered.iter().chain(rest.iter()).enumerate() {
+        let after = Rule {
+            position: position as i64,
+            ..before.clone()
+        };
+        history::upsert_rule(&mut tx, &after)
+            .await
+            .map_err(|e| map_db_error(e, "réordonnancement des règles"))?;
+        audit::record(&mut tx, &mut op, audit::ENTITY_RULE, &after.id, audit::ACTION_UPDATE, Some(before), Some(&after))
+            .await
+            .map_err(|e| map_db_error(e, "journalisation du réordonnancement des règles"))?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
     Ok(())
 }
 
+/// Runs the rules over existing transactions, optionally for one account.
+/// Only uncategorized transactions are touched unless `all` is set, and
+/// reconciled ones only with `force`. Returns the number of transactions
+/// changed.
 #[command]
-pub async fn delete_scheduled(pool: State<'_, DbPool>, id: String) -> Result<(), String> {
-    log::debug!("Invoked delete_scheduled: {id}");
-    sqlx::query("DELETE FROM scheduled_transactions WHERE id = $1")
-        .bind(id)
-        .execute(&*pool)
+pub async fn apply_rules(
+    pool: State<'_, DbPool>,
+    account_id: Option<String>,
+    all: Option<bool>,
+    force: Option<bool>,
+) -> Result<u64, String> {
+    log::debug!("Invoked apply_rules: account_id={account_id:?}, all={all:?}, force={force:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::new("apply_rules");
+
+    let changed = rules::apply_to_existing(&mut tx, &mut op, account_id, all.unwrap_or(false), force.unwrap_or(false))
         .await
-        .map_err(|e| map_db_error(e, "suppression d'échéance"))?;
-    Ok(())
+        .map_err(|e| map_db_error(e, "application des règles"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(changed)
 }
 
 // --- Import Data ---
@@ -292,95 +3494,21 @@ pub async fn delete_scheduled(pool: State<'_, DbPool>, id: String) -> Result<(),
 pub async fn import_data(pool: State<'_, DbPool>, data: AppData) -> Result<(), String> {
     log::info!("Invoked import_data with {} accounts", data.accounts.len());
     let mut tx = pool.begin().await.map_err(|e| map_db_error(e, "début de transaction d'import"))?;
+    let mut op = audit::Operation::new("import_data");
 
-    // STEP 1: DELETE ALL EXISTING DATA
-    log::info!("Clearing existing database data...");
-    
-    sqlx::query("DELETE FROM transactions")
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| map_db_error(e, "nettoyage des transactions"))?;
-    
-    sqlx::query("DELETE FROM scheduled_transactions")
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| map_db_error(e, "nettoyage des échéances"))?;
-    
-    sqlx::query("DELETE FROM accounts")
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| map_db_error(e, "nettoyage des comptes"))?;
-    
-    sqlx::query("DELETE FROM categories")
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| map_db_error(e, "nettoyage des catégories"))?;
-
-    // STEP 2: INSERT NEW DATA
-    for acc in data.accounts {
-        sqlx::query(
-            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\", color, icon) VALUES ($1, $2, $3, $4, $5, $6)"
-        )
-        .bind(acc.id)
-        .bind(acc.name)
-        .bind(acc.account_type)
-        .bind(acc.initial_balance)
-        .bind(acc.color)
-        .bind(acc.icon)
-        .execute(&mut *tx)
+    // Snapshot the data being replaced and the incoming data for the audit log
+    let before = audit::fetch_app_data(&mut tx)
         .await
-        .map_err(|e| map_db_error(e, "import de compte"))?;
-    }
-
-    for cat in data.categories {
-        sqlx::query("INSERT INTO categories (id, name, icon, color) VALUES ($1, $2, $3, $4)")
-            .bind(cat.id)
-            .bind(cat.name)
-            .bind(cat.icon)
-            .bind(cat.color)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| map_db_error(e, "import de catégorie"))?;
-    }
-
-    for t in data.transactions {
-        sqlx::query(
-            "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, category, description, checked, \"isTransfer\", \"linkedTransactionId\") VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)"
-        )
-        .bind(t.id)
-        .bind(t.date)
-        .bind(t.account_id)
-        .bind(t.transaction_type)
-        .bind(t.amount)
-        .bind(t.category)
-        .bind(t.description)
-        .bind(t.checked)
-        .bind(t.is_transfer)
-        .bind(t.linked_transaction_id)
-        .execute(&mut *tx)
+        .map_err(|e| map_db_error(e, "sauvegarde des données avant import"))?;
+    audit::record(&mut tx, &mut op, audit::ENTITY_IMPORT, "", audit::ACTION_IMPORT, Some(&before), Some(&data))
         .await
-        .map_err(|e| map_db_error(e, "import de transaction"))?;
-    }
-
-    for s in data.scheduled {
-        sqlx::query(
-            "INSERT INTO scheduled_transactions (id, description, amount, \"type\", frequency, \"accountId\", \"nextDate\", category, \"toAccountId\", \"includeInForecast\", \"endDate\") VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)"
-        )
-        .bind(s.id)
-        .bind(s.description)
-        .bind(s.amount)
-        .bind(s.transaction_type)
-        .bind(s.frequency)
-        .bind(s.account_id)
-        .bind(s.next_date)
-        .bind(s.category)
-        .bind(s.to_account_id)
-        .bind(s.include_in_forecast)
-        .bind(s.end_date)
-        .execute(&mut *tx)
+        .map_err(|e| map_db_error(e, "journalisation de l'import"))?;
+
+    // Delete all existing data, then insert the imported data
+    log::info!("Replacing existing database data...");
+    history::replace_app_data(&mut tx, &data)
         .await
-        .map_err(|e| map_db_error(e, "import d'échéance"))?;
-    }
+        .map_err(|e| map_db_error(e, "import des données"))?;
 
     tx.commit().await.map_err(|e| map_db_error(e, "validation finale de l'import"))?;
     log::info!("Import data completed successfully");
@@ -391,95 +3519,35 @@ pub async fn import_data(pool: State<'_, DbPool>, data: AppData) -> Result<(), S
 #[command]
 pub async fn get_settings(pool: State<'_, DbPool>) -> Result<Option<Settings>, String> {
     log::debug!("Invoked get_settings");
-
-    #[derive(sqlx::FromRow)]
-    struct SettingsRow {
-        theme: String,
-        #[sqlx(rename = "primaryColor")]
-        primary_color: String,
-        #[sqlx(rename = "displayStyle")]
-        display_style: String,
-        #[sqlx(rename = "windowPositionX")]
-        window_position_x: Option<i32>,
-        #[sqlx(rename = "windowPositionY")]
-        window_position_y: Option<i32>,
-        #[sqlx(rename = "windowSizeWidth")]
-        window_size_width: Option<i32>,
-        #[sqlx(rename = "windowSizeHeight")]
-        window_size_height: Option<i32>,
-        #[sqlx(rename = "accountGroups")]
-        account_groups: Option<String>,
-        #[sqlx(rename = "customGroups")]
-        custom_groups: Option<String>,
-        #[sqlx(rename = "customGroupsOrder")]
-        custom_groups_order: Option<String>,
-        #[sqlx(rename = "accountsOrder")]
-        accounts_order: Option<String>,
-        #[sqlx(rename = "lastSeenVersion")]
-        last_seen_version: Option<String>,
-        #[sqlx(rename = "componentSpacing")]
-        component_spacing: i32,
-        #[sqlx(rename = "componentPadding")]
-        component_padding: i32,
-    }
-
-    match sqlx::query_as::<_, SettingsRow>("SELECT * FROM settings WHERE id = 1")
-        .fetch_optional(&*pool)
-        .await
-        .map_err(|e| map_db_error(e, "récupération des paramètres"))?
-    {
-        Some(row) => {
-            let window_position =
-                if let (Some(x), Some(y)) = (row.window_position_x, row.window_position_y) {
-                    Some(WindowPosition { x, y })
-                } else {
-                    None
-                };
-
-            let window_size = if let (Some(width), Some(height)) =
-                (row.window_size_width, row.window_size_height)
-            {
-                Some(WindowSize { width, height })
-            } else {
-                None
-            };
-
-            Ok(Some(Settings {
-                theme: row.theme,
-                primary_color: row.primary_color,
-                display_style: Some(row.display_style),
-                window_position,
-                window_size,
-                account_groups: row.account_groups,
-                custom_groups: row.custom_groups,
-                custom_groups_order: row.custom_groups_order,
-                accounts_order: row.accounts_order,
-                last_seen_version: row.last_seen_version,
-                component_spacing: row.component_spacing,
-                component_padding: row.component_padding,
-            }))
-        }
-        None => Ok(None),
-    }
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    audit::fetch_settings(&mut conn)
+        .await
+        .map_err(|e| map_db_error(e, "récupération des paramètres"))
 }
 
 #[command]
 pub async fn save_settings(pool: State<'_, DbPool>, settings: Settings) -> Result<(), String> {
     log::debug!("Invoked save_settings: {settings:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut op = audit::Operation::untracked("save_settings");
 
-    let (pos_x, pos_y) = if let Some(pos) = settings.window_position {
+    let before = audit::fetch_settings(&mut tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des paramètres"))?;
+
+    let (pos_x, pos_y) = if let Some(pos) = &settings.window_position {
         (Some(pos.x), Some(pos.y))
     } else {
         (None, None)
     };
 
-    let (size_w, size_h) = if let Some(size) = settings.window_size {
+    let (size_w, size_h) = if let Some(size) = &settings.window_size {
         (Some(size.width), Some(size.height))
     } else {
         (None, None)
     };
 
-    let display_style = settings.display_style.unwrap_or_else(|| "modern".to_string());
+    let display_style = settings.display_style.clone().unwrap_or_else(|| "modern".to_string());
 
     sqlx::query(
         "INSERT INTO settings (id, theme, \"primaryColor\", \"displayStyle\", \"windowPositionX\", \"windowPositionY\", \"windowSizeWidth\", \"windowSizeHeight\", \"accountGroups\", \"customGroups\", \"customGroupsOrder\", \"accountsOrder\", \"lastSeenVersion\", \"componentSpacing\", \"componentPadding\")
@@ -500,23 +3568,243 @@ pub async fn save_settings(pool: State<'_, DbPool>, settings: Settings) -> Resul
             \"componentSpacing\" = $13,
             \"componentPadding\" = $14"
     )
-    .bind(settings.theme)
-    .bind(settings.primary_color)
+    .bind(&settings.theme)
+    .bind(&settings.primary_color)
     .bind(display_style)
     .bind(pos_x)
     .bind(pos_y)
     .bind(size_w)
     .bind(size_h)
-    .bind(settings.account_groups)
-    .bind(settings.custom_groups)
-    .bind(settings.custom_groups_order)
-    .bind(settings.accounts_order)
-    .bind(settings.last_seen_version)
+    .bind(&settings.account_groups)
+    .bind(&settings.custom_groups)
+    .bind(&settings.custom_groups_order)
+    .bind(&settings.accounts_order)
+    .bind(&settings.last_seen_version)
     .bind(settings.component_spacing)
     .bind(settings.component_padding)
-    .execute(&*pool)
+    .execute(&mut *tx)
     .await
     .map_err(|e| map_db_error(e, "sauvegarde des paramètres"))?;
 
+    // Read back so the snapshot holds the stored values (e.g. the displayStyle default)
+    let after = audit::fetch_settings(&mut tx)
+        .await
+        .map_err(|e| map_db_error(e, "lecture des paramètres"))?;
+    let action = if before.is_some() { audit::ACTION_UPDATE } else { audit::ACTION_CREATE };
+    audit::record(&mut tx, &mut op, audit::ENTITY_SETTINGS, "1", action, before.as_ref(), after.as_ref())
+        .await
+        .map_err(|e| map_db_error(e, "journalisation des paramètres"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
     Ok(())
-}
\ No newline at end of file
+}
+
+// --- Audit Log ---
+#[command]
+pub async fn get_audit_log(
+    pool: State<'_, DbPool>,
+    entity: Option<String>,
+    entity_id: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<AuditEntry>, String> {
+    log::debug!("Invoked get_audit_log: entity={entity:?}, entity_id={entity_id:?}, from={from:?}, to={to:?}");
+
+    let mut query: QueryBuilder<Sqlite> = QueryBuilder::new("SELECT * FROM audit_log WHERE 1 = 1");
+    if let Some(entity) = entity {
+        query.push(" AND entity = ").push_bind(entity);
+    }
+    if let Some(entity_id) = entity_id {
+        query.push(" AND \"entityId\" = ").push_bind(entity_id);
+    }
+    // Dates are YYYY-MM-DD, both bounds inclusive
+    if let Some(from) = from {
+        query.push(" AND \"timestamp\" >= ").push_bind(from);
+    }
+    if let Some(to) = to {
+        query.push(" AND \"timestamp\" < date(").push_bind(to).push(", '+1 day')");
+    }
+    query.push(" ORDER BY id DESC");
+    if let Some(limit) = limit {
+        query.push(" LIMIT ").push_bind(limit);
+    }
+
+    query
+        .build_query_as::<AuditEntry>()
+        .fetch_all(&*pool)
+        .await
+        .map_err(|e| map_db_error(e, "récupération du journal d'audit"))
+}
+
+// --- Undo / Redo ---
+#[command]
+pub async fn undo(pool: State<'_, DbPool>, steps: Option<u32>) -> Result<Vec<HistoryEntry>, String> {
+    log::debug!("Invoked undo: {steps:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let undone = history::undo(&mut tx, steps.unwrap_or(1))
+        .await
+        .map_err(|e| map_db_error(e, "annulation"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(undone)
+}
+
+#[command]
+pub async fn redo(pool: State<'_, DbPool>, steps: Option<u32>) -> Result<Vec<HistoryEntry>, String> {
+    log::debug!("Invoked redo: {steps:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let redone = history::redo(&mut tx, steps.unwrap_or(1))
+        .await
+        .map_err(|e| map_db_error(e, "rétablissement"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(redone)
+}
+
+/// Operations on the undo and redo stacks, most recent first.
+#[command]
+pub async fn get_history(pool: State<'_, DbPool>, limit: Option<i64>) -> Result<Vec<HistoryEntry>, String> {
+    log::debug!("Invoked get_history");
+    sqlx::query_as::<_, HistoryEntry>("SELECT * FROM operations ORDER BY id DESC LIMIT $1")
+        .bind(limit.unwrap_or(50))
+        .fetch_all(&*pool)
+        .await
+        .map_err(|e| map_db_error(e, "récupération de l'historique"))
+}
+
+// --- Integrity ---
+#[command]
+pub async fn check_integrity(pool: State<'_, DbPool>) -> Result<Vec<IntegrityIssue>, String> {
+    log::debug!("Invoked check_integrity");
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    integrity::check(&mut conn)
+        .await
+        .map_err(|e| map_db_error(e, "vérification de l'intégrité"))
+}
+
+#[command]
+pub async fn repair_integrity(
+    pool: State<'_, DbPool>,
+    repair: IntegrityRepair,
+) -> Result<Vec<IntegrityIssue>, String> {
+    log::info!("Invoked repair_integrity: {repair:?}");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    integrity::check_target(&mut tx, &repair).await?;
+    let repaired = integrity::repair(&mut tx, &repair)
+        .await
+        .map_err(|e| map_db_error(e, "réparation de l'intégrité"))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(repaired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    async fn seed(conn: &mut sqlx::SqliteConnection) {
+        for sql in [
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'Compte', 'checking', 0)",
+            "INSERT INTO categories (id, name, icon, color) VALUES ('food', 'Courses', 'Tag', '#fff'), ('market', 'Marché', 'Tag', '#fff')",
+            "INSERT INTO categories (id, name, icon, color, \"parentId\") VALUES ('fruit', 'Fruits', 'Tag', '#fff', 'food'), ('apple', 'Pommes', 'Tag', '#fff', 'fruit')",
+            "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, category) VALUES ('t1', '2026-01-10', 'a1', 'expense', 10, 'food')",
+            "INSERT INTO scheduled_transactions (id, description, amount, \"type\", frequency, \"accountId\", \"nextDate\", category) VALUES ('s1', 'Panier', 30, 'expense', 'weekly', 'a1', '2026-01-12', 'food')",
+        ] {
+            sqlx::query(sql).execute(&mut *conn).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn removed_categories_hand_their_rows_over() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+        let mut op = audit::Operation::new("delete_category");
+
+        remove_category(&mut conn, &mut op, "food", "market", None).await.unwrap();
+        assert!(audit::fetch_category(&mut conn, "food").await.unwrap().is_none());
+        assert_eq!(audit::fetch_transaction(&mut conn, "t1").await.unwrap().unwrap().category, "market");
+        assert_eq!(audit::fetch_scheduled(&mut conn, "s1").await.unwrap().unwrap().category, "market");
+
+        // Moves and deletion come back together
+        history::undo(&mut conn, 1).await.unwrap();
+        assert_eq!(audit::fetch_transaction(&mut conn, "t1").await.unwrap().unwrap().category, "food");
+        assert!(audit::fetch_category(&mut conn, "food").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn removal_needs_another_existing_category() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+        let mut op = audit::Operation::new("delete_category");
+
+        assert!(remove_category(&mut conn, &mut op, UNCATEGORIZED_CATEGORY_ID, "food", None).await.is_err());
+        assert!(remove_category(&mut conn, &mut op, TRANSFER_CATEGORY_ID, "food", None).await.is_err());
+        assert!(remove_category(&mut conn, &mut op, "food", "food", None).await.is_err());
+        assert!(remove_category(&mut conn, &mut op, "food", "gone", None).await.is_err());
+        assert!(audit::fetch_category(&mut conn, "food").await.unwrap().is_some());
+    }
+
+    async fn parent(conn: &mut sqlx::SqliteConnection, id: &str) -> Option<String> {
+        audit::fetch_category(conn, id).await.unwrap().unwrap().parent_id
+    }
+
+    #[tokio::test]
+    async fn categories_cannot_move_below_themselves() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+        let mut op = audit::Operation::new("move_category");
+
+        assert!(set_category_parent(&mut conn, &mut op, "food", Some("food")).await.is_err());
+        assert!(set_category_parent(&mut conn, &mut op, "food", Some("apple")).await.is_err());
+        assert!(set_category_parent(&mut conn, &mut op, "food", Some("gone")).await.is_err());
+
+        set_category_parent(&mut conn, &mut op, "fruit", Some("market")).await.unwrap();
+        assert_eq!(parent(&mut conn, "fruit").await.as_deref(), Some("market"));
+        assert_eq!(parent(&mut conn, "apple").await.as_deref(), Some("fruit"));
+    }
+
+    #[tokio::test]
+    async fn removed_categories_hand_their_children_over() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+        let mut op = audit::Operation::new("delete_category");
+
+        remove_category(&mut conn, &mut op, "fruit", "food", Some("market")).await.unwrap();
+        assert_eq!(parent(&mut conn, "apple").await.as_deref(), Some("market"));
+
+        // A new parent taken from the removed subtree is lifted out of it first
+        remove_category(&mut conn, &mut op, "market", "food", Some("apple")).await.unwrap();
+        assert_eq!(parent(&mut conn, "apple").await, None);
+    }
+
+    #[tokio::test]
+    async fn bulk_selection_intersects_ids_and_filter() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+        sqlx::query("INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, category) VALUES ('t2', '2026-02-10', 'a1', 'expense', 20, 'food')")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        let ids = |selected: Vec<Transaction>| selected.into_iter().map(|t| t.id).collect::<Vec<_>>();
+        let february = || TransactionFilter {
+            from: Some("2026-02-01".to_string()),
+            ..Default::default()
+        };
+
+        assert!(select_transactions(&mut conn, None, None).await.is_err());
+        assert!(select_transactions(&mut conn, Some(Vec::new()), Some(february())).await.unwrap().is_empty());
+        assert_eq!(ids(select_transactions(&mut conn, None, Some(february())).await.unwrap()), ["t2"]);
+        let both = Some(vec!["t1".to_string(), "t2".to_string()]);
+        assert_eq!(ids(select_transactions(&mut conn, both, Some(february())).await.unwrap()), ["t2"]);
+    }
+}
@@ -0,0 +1,175 @@
+// Calendar arithmetic on YYYY-MM-DD dates, for the monthly cycles of credit
+// cards and loans
+
+/// (year, month, day)
+pub type Date = (i32, u32, u32);
+
+pub fn parse_date(date: &str) -> Option<Date> {
+    let mut parts = date.splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+    // Four-digit years only, so that dates keep sorting as text
+    ((1..=9999).contains(&year) && (1..=12).contains(&month) && day >= 1 && day <= days_in_month(year, month))
+        .then_some((year, month, day))
+}
+
+pub fn format_date((year, month, day): Date) -> String {
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// `day` of the month `months` after the one of `date`, or its last day
+/// when the month is shorter.
+pub fn day_of_month(date: Date, months: i32, day: u32) -> Date {
+    let index = date.0 * 12 + date.1 as i32 - 1 + months;
+    let (year, month) = (index.div_euclid(12), index.rem_euclid(12) as u32 + 1);
+    (year, month, day.min(days_in_month(year, month)))
+}
+
+pub fn next_day(date: Date) -> Date {
+    if date.2 < days_in_month(date.0, date.1) {
+        (date.0, date.1, date.2 + 1)
+    } else {
+        day_of_month(date, 1, 1)
+    }
+}
+
+/// Whole months from `from` to `to`, 0 when `to` comes first. A month
+/// ending on the 31st is complete on the last day of a shorter month.
+pub fn months_between(from: Date, to: Date) -> u32 {
+    let incomplete = to.2 < from.2.min(days_in_month(to.0, to.1));
+    let months = (to.0 * 12 + to.1 as i32) - (from.0 * 12 + from.1 as i32) - i32::from(incomplete);
+    months.max(0) as u32
+}
+
+/// Most dates period_ends returns, about 27 years of days.
+pub const MAX_PERIODS: usize = 10_000;
+
+/// Last day of each day, week (counted from `from`), month, quarter or year
+/// between `from` and `to`, ending with `to` itself.
+pub fn period_ends(from: &str, to: &str, interval: &str) -> Result<Vec<String>, String> {
+    let first = parse_date(from).ok_or_else(|| format!("Date invalide : {from}"))?;
+    let last = parse_date(to).ok_or_else(|| format!("Date invalide : {to}"))?;
+    if first > last {
+        return Err("La date de début suit la date de fin.".to_string());
+    }
+
+    let mut ends = Vec::new();
+    let mut date = match interval {
+        "day" => first,
+        "week" => (0..6).fold(first, |date, _| next_day(date)),
+        "month" => day_of_month(first, 0, 31),
+        "quarter" => day_of_month(first, (3 - first.1 as i32 % 3) % 3, 31),
+        "year" => (first.0, 12, 31),
+        _ => return Err(format!("Intervalle inconnu : {interval}")),
+    };
+    while date < last {
+        if ends.len() == MAX_PERIODS {
+            return Err("Trop de dates : choisissez un intervalle plus long.".to_string());
+        }
+        ends.push(format_date(date));
+        date = match interval {
+            "day" => next_day(date),
+            "week" => (0..7).fold(date, |date, _| next_day(date)),
+            "month" => day_of_month(date, 1, 31),
+            "quarter" => day_of_month(date, 3, 31),
+            _ => (date.0 + 1, 12, 31),
+        };
+    }
+    ends.push(format_date(last));
+    Ok(ends)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_date_rejects_impossible_days() {
+        assert_eq!(parse_date("2024-02-29"), Some((2024, 2, 29)));
+        assert_eq!(parse_date("2026-02-29"), None);
+        assert_eq!(parse_date("2026-13-01"), None);
+        assert_eq!(parse_date("2026-01"), None);
+        assert_eq!(parse_date("0000-01-01"), None);
+        assert_eq!(parse_date("10000-01-01"), None);
+        assert_eq!(parse_date("-2026-01-01"), None);
+        assert_eq!(parse_date("9999-12-31"), Some((9999, 12, 31)));
+        assert_eq!(format_date((2026, 3, 5)), "2026-03-05");
+    }
+
+    #[test]
+    fn day_of_month_clamps_to_month_end() {
+        assert_eq!(day_of_month((2026, 1, 31), 1, 31), (2026, 2, 28));
+        assert_eq!(day_of_month((2024, 1, 31), 1, 31), (2024, 2, 29));
+        assert_eq!(day_of_month((2026, 3, 15), 0, 31), (2026, 3, 31));
+    }
+
+    #[test]
+    fn day_of_month_crosses_years() {
+        assert_eq!(day_of_month((2026, 11, 5), 3, 5), (2027, 2, 5));
+        assert_eq!(day_of_month((2026, 1, 5), -1, 5), (2025, 12, 5));
+        assert_eq!(day_of_month((2026, 3, 31), -13, 31), (2025, 2, 28));
+        assert_eq!(next_day((2026, 12, 31)), (2027, 1, 1));
+    }
+
+    #[test]
+    fn months_between_counts_whole_months() {
+        assert_eq!(months_between((2026, 1, 15), (2026, 3, 14)), 1);
+        assert_eq!(months_between((2026, 1, 15), (2026, 3, 15)), 2);
+        assert_eq!(months_between((2025, 11, 1), (2026, 2, 1)), 3);
+        assert_eq!(months_between((2026, 3, 1), (2026, 1, 1)), 0);
+    }
+
+    #[test]
+    fn months_between_completes_on_short_month_end() {
+        assert_eq!(months_between((2026, 1, 31), (2026, 2, 28)), 1);
+        assert_eq!(months_between((2026, 1, 31), (2026, 2, 27)), 0);
+        assert_eq!(months_between((2026, 1, 31), (2026, 4, 30)), 3);
+    }
+
+    #[test]
+    fn weeks_count_from_the_first_date() {
+        assert_eq!(period_ends("2026-01-01", "2026-01-20", "week").unwrap(), ["2026-01-07", "2026-01-14", "2026-01-20"]);
+        assert_eq!(period_ends("2026-01-01", "2026-01-14", "week").unwrap(), ["2026-01-07", "2026-01-14"]);
+        assert_eq!(period_ends("2026-12-29", "2027-01-12", "week").unwrap(), ["2027-01-04", "2027-01-11", "2027-01-12"]);
+    }
+
+    #[test]
+    fn months_end_on_their_last_day() {
+        assert_eq!(period_ends("2026-01-31", "2026-03-31", "month").unwrap(), ["2026-01-31", "2026-02-28", "2026-03-31"]);
+        assert_eq!(period_ends("2026-01-15", "2026-01-20", "month").unwrap(), ["2026-01-20"]);
+    }
+
+    #[test]
+    fn quarters_follow_the_calendar() {
+        assert_eq!(
+            period_ends("2026-02-10", "2026-11-15", "quarter").unwrap(),
+            ["2026-03-31", "2026-06-30", "2026-09-30", "2026-11-15"]
+        );
+        assert_eq!(period_ends("2026-03-01", "2026-04-01", "quarter").unwrap(), ["2026-03-31", "2026-04-01"]);
+        assert_eq!(period_ends("2025-12-31", "2026-01-05", "quarter").unwrap(), ["2025-12-31", "2026-01-05"]);
+    }
+
+    #[test]
+    fn years_and_days() {
+        assert_eq!(period_ends("2025-06-01", "2026-06-01", "year").unwrap(), ["2025-12-31", "2026-06-01"]);
+        assert_eq!(period_ends("2024-02-28", "2024-03-01", "day").unwrap(), ["2024-02-28", "2024-02-29", "2024-03-01"]);
+    }
+
+    #[test]
+    fn period_ends_rejects_bad_ranges() {
+        assert!(period_ends("2026-02-01", "2026-01-01", "month").is_err());
+        assert!(period_ends("2026-01-01", "2026-02-30", "month").is_err());
+        assert!(period_ends("2026-01-01", "2026-02-01", "fortnight").is_err());
+        assert!(period_ends("1900-01-01", "2026-01-01", "day").is_err());
+    }
+}
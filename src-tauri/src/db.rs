@@ -1,5 +1,9 @@
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+use crate::accounts::LEGACY_KINDS;
+use crate::models::{ACCOUNT_KINDS, KIND_CHECKING, TRANSFER_CATEGORY_ID, UNCATEGORIZED_CATEGORY_ID};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Connection, Pool, Sqlite, SqliteConnection};
 use std::fs;
+use std::str::FromStr;
 use tauri::Manager;
 
 pub type DbPool = Pool<Sqlite>;
@@ -21,9 +25,14 @@ pub async fn init_db(app_handle: &tauri::AppHandle) -> Result<DbPool, String> {
         fs::File::create(&db_path).map_err(|e| e.to_string())?;
     }
 
+    // Every pooled connection enforces foreign keys
+    let options = SqliteConnectOptions::from_str(&db_url)
+        .map_err(|e| e.to_string())?
+        .foreign_keys(true);
+
     let pool = SqlitePoolOptions::new()
         .max_connections(5)
-        .connect(&db_url)
+        .connect_with(options)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -32,8 +41,42 @@ pub async fn init_db(app_handle: &tauri::AppHandle) -> Result<DbPool, String> {
     Ok(pool)
 }
 
+/// Empty in-memory database with the full schema, for tests.
+#[cfg(test)]
+pub async fn memory_pool() -> DbPool {
+    let pool = memory_pool_without_schema().await;
+    create_tables(&pool).await.expect("schema");
+    pool
+}
+
+#[cfg(test)]
+async fn memory_pool_without_schema() -> DbPool {
+    let options = SqliteConnectOptions::from_str("sqlite::memory:")
+        .expect("in-memory database")
+        .foreign_keys(true);
+    // A single connection that never closes: each one opens its own database
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect_with(options)
+        .await
+        .expect("in-memory database")
+}
+
 async fn create_tables(pool: &DbPool) -> Result<(), sqlx::Error> {
-    let mut tx = pool.begin().await?;
+    let mut conn = pool.acquire().await?;
+
+    // Table rebuilds below must not fire ON DELETE actions, and the pragma
+    // cannot be changed inside a transaction.
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&mut *conn).await?;
+    let result = migrate(&mut conn).await;
+    sqlx::query("PRAGMA foreign_keys = ON").execute(&mut *conn).await?;
+    result
+}
+
+async fn migrate(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    let mut tx = conn.begin().await?;
 
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS accounts (
@@ -42,7 +85,19 @@ async fn create_tables(pool: &DbPool) -> Result<(), sqlx::Error> {
             \"type\" TEXT NOT NULL,
             \"initialBalance\" REAL NOT NULL,
             color TEXT,
-            icon TEXT
+            icon TEXT,
+            \"openingDate\" TEXT,
+            archived BOOLEAN NOT NULL DEFAULT 0,
+            \"closedOn\" TEXT,
+            \"creditLimit\" REAL,
+            \"statementClosingDay\" INTEGER,
+            \"paymentDueDay\" INTEGER,
+            \"loanPrincipal\" REAL,
+            \"loanRate\" REAL,
+            \"loanTermMonths\" INTEGER,
+            \"loanStartDate\" TEXT,
+            \"depreciationMethod\" TEXT,
+            \"depreciationRate\" REAL
         )",
     )
     .execute(&mut *tx)
@@ -55,12 +110,94 @@ async fn create_tables(pool: &DbPool) -> Result<(), sqlx::Error> {
             \"accountId\" TEXT NOT NULL,
             \"type\" TEXT NOT NULL,
             amount REAL NOT NULL,
-            category TEXT NOT NULL,
+            category TEXT NOT NULL DEFAULT 'uncategorized',
             description TEXT,
-            checked BOOLEAN DEFAULT 0,
+            status TEXT NOT NULL DEFAULT 'pending',
+            \"clearedAt\" TEXT,
+            \"reconciledAt\" TEXT,
             \"isTransfer\" BOOLEAN DEFAULT 0,
             \"linkedTransactionId\" TEXT,
-            FOREIGN KEY(\"accountId\") REFERENCES accounts(id)
+            \"payeeId\" TEXT REFERENCES payees(id) ON DELETE SET NULL,
+            \"reconciliationId\" TEXT REFERENCES reconciliations(id) ON DELETE SET NULL,
+            FOREIGN KEY(\"accountId\") REFERENCES accounts(id) ON DELETE CASCADE,
+            FOREIGN KEY(category) REFERENCES categories(id) ON UPDATE CASCADE ON DELETE SET DEFAULT,
+            FOREIGN KEY(\"linkedTransactionId\") REFERENCES transactions(id) ON DELETE SET NULL DEFERRABLE INITIALLY DEFERRED
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS reconciliations (
+            id TEXT PRIMARY KEY,
+            \"accountId\" TEXT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+            \"statementDate\" TEXT NOT NULL,
+            \"statementBalance\" REAL NOT NULL,
+            status TEXT NOT NULL DEFAULT 'open',
+            \"createdAt\" TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+            \"finishedAt\" TEXT
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS balance_assertions (
+            id TEXT PRIMARY KEY,
+            \"accountId\" TEXT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+            date TEXT NOT NULL,
+            balance REAL NOT NULL,
+            \"createdAt\" TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS valuations (
+            id TEXT PRIMARY KEY,
+            \"accountId\" TEXT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+            date TEXT NOT NULL,
+            value REAL NOT NULL,
+            UNIQUE(\"accountId\", date)
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS securities (
+            id TEXT PRIMARY KEY,
+            symbol TEXT NOT NULL UNIQUE,
+            name TEXT NOT NULL
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // A security cannot be deleted while trades use it
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS trades (
+            id TEXT PRIMARY KEY,
+            \"accountId\" TEXT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+            \"securityId\" TEXT NOT NULL REFERENCES securities(id),
+            date TEXT NOT NULL,
+            \"type\" TEXT NOT NULL,
+            quantity REAL NOT NULL,
+            price REAL NOT NULL,
+            fees REAL NOT NULL DEFAULT 0
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS prices (
+            id TEXT PRIMARY KEY,
+            \"securityId\" TEXT NOT NULL REFERENCES securities(id) ON DELETE CASCADE,
+            date TEXT NOT NULL,
+            price REAL NOT NULL,
+            UNIQUE(\"securityId\", date)
         )",
     )
     .execute(&mut *tx)
@@ -71,7 +208,8 @@ async fn create_tables(pool: &DbPool) -> Result<(), sqlx::Error> {
             id TEXT PRIMARY KEY,
             name TEXT NOT NULL,
             icon TEXT NOT NULL,
-            color TEXT NOT NULL
+            color TEXT NOT NULL,
+            \"parentId\" TEXT REFERENCES categories(id) ON DELETE SET NULL
         )",
     )
     .execute(&mut *tx)
@@ -86,8 +224,72 @@ async fn create_tables(pool: &DbPool) -> Result<(), sqlx::Error> {
             frequency TEXT NOT NULL,
             \"accountId\" TEXT NOT NULL,
             \"nextDate\" TEXT NOT NULL,
-            category TEXT NOT NULL,
-            FOREIGN KEY(\"accountId\") REFERENCES accounts(id)
+            category TEXT NOT NULL DEFAULT 'uncategorized',
+            \"toAccountId\" TEXT,
+            \"includeInForecast\" BOOLEAN DEFAULT 1,
+            \"endDate\" TEXT,
+            \"payeeId\" TEXT REFERENCES payees(id) ON DELETE SET NULL,
+            FOREIGN KEY(\"accountId\") REFERENCES accounts(id) ON DELETE CASCADE,
+            FOREIGN KEY(\"toAccountId\") REFERENCES accounts(id) ON DELETE SET NULL,
+            FOREIGN KEY(category) REFERENCES categories(id) ON UPDATE CASCADE ON DELETE SET DEFAULT
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS payees (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            aliases TEXT NOT NULL DEFAULT '[]'
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS tags (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            color TEXT
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Followed on an account or on a tag, one of the two
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS goals (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            \"targetAmount\" REAL NOT NULL,
+            \"targetDate\" TEXT,
+            \"accountId\" TEXT REFERENCES accounts(id) ON DELETE CASCADE,
+            \"tagId\" TEXT REFERENCES tags(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS transaction_tags (
+            \"transactionId\" TEXT NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+            \"tagId\" TEXT NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+            PRIMARY KEY (\"transactionId\", \"tagId\")
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS attachments (
+            id TEXT PRIMARY KEY,
+            \"transactionId\" TEXT NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+            hash TEXT NOT NULL,
+            \"mimeType\" TEXT NOT NULL,
+            \"originalName\" TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            \"createdAt\" TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
         )",
     )
     .execute(&mut *tx)
@@ -110,14 +312,59 @@ async fn create_tables(pool: &DbPool) -> Result<(), sqlx::Error> {
     .execute(&mut *tx)
     .await?;
 
-    // Indexes for query performance
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_transactions_account_id ON transactions(\"accountId\")")
-        .execute(&mut *tx)
-        .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_transactions_date ON transactions(date)")
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            \"timestamp\" TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+            entity TEXT NOT NULL,
+            \"entityId\" TEXT NOT NULL,
+            action TEXT NOT NULL,
+            \"before\" TEXT,
+            \"after\" TEXT
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS operations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            label TEXT NOT NULL,
+            \"createdAt\" TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+            undone BOOLEAN NOT NULL DEFAULT 0
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS rules (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            position INTEGER NOT NULL DEFAULT 0,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            \"descriptionContains\" TEXT,
+            \"descriptionRegex\" TEXT,
+            \"amountMin\" REAL,
+            \"amountMax\" REAL,
+            \"accountId\" TEXT REFERENCES accounts(id) ON DELETE CASCADE,
+            \"type\" TEXT,
+            \"setCategory\" TEXT REFERENCES categories(id) ON DELETE SET NULL,
+            \"setStatus\" TEXT,
+            \"setPayee\" TEXT REFERENCES payees(id) ON DELETE SET NULL,
+            \"setTags\" TEXT NOT NULL DEFAULT '[]',
+            \"stopProcessing\" BOOLEAN NOT NULL DEFAULT 0
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Indexes for query performance (the others are created once the legacy
+    // columns have been migrated, see create_indexes below)
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log(entity, \"entityId\")")
         .execute(&mut *tx)
         .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_scheduled_account_id ON scheduled_transactions(\"accountId\")")
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_log_timestamp ON audit_log(\"timestamp\")")
         .execute(&mut *tx)
         .await?;
 
@@ -300,7 +547,682 @@ async fn create_tables(pool: &DbPool) -> Result<(), sqlx::Error> {
             .await?;
     }
 
+    // Migration: Add operationId to audit_log
+    let has_operation_id: bool = sqlx::query_scalar(
+        "SELECT count(*) FROM pragma_table_info('audit_log') WHERE name='operationId'",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap_or(0)
+        > 0;
+
+    if !has_operation_id {
+        log::info!("Migrating audit_log table: adding operationId column");
+        sqlx::query("ALTER TABLE audit_log ADD COLUMN \"operationId\" INTEGER REFERENCES operations(id)")
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_log_operation_id ON audit_log(\"operationId\")")
+        .execute(&mut *tx)
+        .await?;
+
+    // Migration: Add parentId to categories
+    let has_parent_id: bool = sqlx::query_scalar(
+        "SELECT count(*) FROM pragma_table_info('categories') WHERE name='parentId'",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap_or(0)
+        > 0;
+
+    if !has_parent_id {
+        log::info!("Migrating categories table: adding parentId column");
+        sqlx::query("ALTER TABLE categories ADD COLUMN \"parentId\" TEXT REFERENCES categories(id) ON DELETE SET NULL")
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_categories_parent_id ON categories(\"parentId\")")
+        .execute(&mut *tx)
+        .await?;
+
+    // Categories referenced by the foreign keys below
+    seed_system_categories(&mut tx).await?;
+
+    // Migration: Rebuild transactions with foreign keys on category and linkedTransactionId
+    let has_category_fk: bool = sqlx::query_scalar(
+        "SELECT count(*) FROM pragma_foreign_key_list('transactions') WHERE \"table\"='categories'",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap_or(0)
+        > 0;
+
+    if !has_category_fk {
+        log::info!("Migrating transactions table: adding category and linkedTransactionId foreign keys");
+        sqlx::query(
+            "CREATE TABLE transactions_new (
+                id TEXT PRIMARY KEY,
+                date TEXT NOT NULL,
+                \"accountId\" TEXT NOT NULL,
+                \"type\" TEXT NOT NULL,
+                amount REAL NOT NULL,
+                category TEXT NOT NULL DEFAULT 'uncategorized',
+                description TEXT,
+                checked BOOLEAN DEFAULT 0,
+                \"isTransfer\" BOOLEAN DEFAULT 0,
+                \"linkedTransactionId\" TEXT,
+                FOREIGN KEY(\"accountId\") REFERENCES accounts(id) ON DELETE CASCADE,
+                FOREIGN KEY(category) REFERENCES categories(id) ON UPDATE CASCADE ON DELETE SET DEFAULT,
+                FOREIGN KEY(\"linkedTransactionId\") REFERENCES transactions(id) ON DELETE SET NULL DEFERRABLE INITIALLY DEFERRED
+            )",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Unknown categories fall back to uncategorized, links to missing rows are dropped
+        sqlx::query(
+            "INSERT INTO transactions_new (id, date, \"accountId\", \"type\", amount, category, description, checked, \"isTransfer\", \"linkedTransactionId\")
+             SELECT t.id, t.date, t.\"accountId\", t.\"type\", t.amount,
+                    CASE WHEN c.id IS NULL THEN 'uncategorized' ELSE t.category END,
+                    t.description, t.checked, t.\"isTransfer\", l.id
+             FROM transactions t
+             LEFT JOIN categories c ON c.id = t.category
+             LEFT JOIN transactions l ON l.id = t.\"linkedTransactionId\"",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DROP TABLE transactions").execute(&mut *tx).await?;
+        sqlx::query("ALTER TABLE transactions_new RENAME TO transactions")
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // Migration: Rebuild scheduled_transactions with foreign keys on category and toAccountId
+    let has_scheduled_category_fk: bool = sqlx::query_scalar(
+        "SELECT count(*) FROM pragma_foreign_key_list('scheduled_transactions') WHERE \"table\"='categories'",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap_or(0)
+        > 0;
+
+    if !has_scheduled_category_fk {
+        log::info!("Migrating scheduled_transactions table: adding category and toAccountId foreign keys");
+        sqlx::query(
+            "CREATE TABLE scheduled_transactions_new (
+                id TEXT PRIMARY KEY,
+                description TEXT NOT NULL,
+                amount REAL NOT NULL,
+                \"type\" TEXT NOT NULL,
+                frequency TEXT NOT NULL,
+                \"accountId\" TEXT NOT NULL,
+                \"nextDate\" TEXT NOT NULL,
+                category TEXT NOT NULL DEFAULT 'uncategorized',
+                \"toAccountId\" TEXT,
+                \"includeInForecast\" BOOLEAN DEFAULT 1,
+                \"endDate\" TEXT,
+                FOREIGN KEY(\"accountId\") REFERENCES accounts(id) ON DELETE CASCADE,
+                FOREIGN KEY(\"toAccountId\") REFERENCES accounts(id) ON DELETE SET NULL,
+                FOREIGN KEY(category) REFERENCES categories(id) ON UPDATE CASCADE ON DELETE SET DEFAULT
+            )",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO scheduled_transactions_new (id, description, amount, \"type\", frequency, \"accountId\", \"nextDate\", category, \"toAccountId\", \"includeInForecast\", \"endDate\")
+             SELECT s.id, s.description, s.amount, s.\"type\", s.frequency, s.\"accountId\", s.\"nextDate\",
+                    CASE WHEN c.id IS NULL THEN 'uncategorized' ELSE s.category END,
+                    a.id, s.\"includeInForecast\", s.\"endDate\"
+             FROM scheduled_transactions s
+             LEFT JOIN categories c ON c.id = s.category
+             LEFT JOIN accounts a ON a.id = s.\"toAccountId\"",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DROP TABLE scheduled_transactions").execute(&mut *tx).await?;
+        sqlx::query("ALTER TABLE scheduled_transactions_new RENAME TO scheduled_transactions")
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // Rebuilt tables lose their indexes, and some indexed columns only exist
+    // once the migrations above have run
+    create_indexes(&mut tx).await?;
+
+    // Migration: Add payeeId to transactions, scheduled_transactions and rules (as setPayee)
+    for (table, column) in [
+        ("transactions", "payeeId"),
+        ("scheduled_transactions", "payeeId"),
+        ("rules", "setPayee"),
+    ] {
+        let has_column: bool = sqlx::query_scalar(&format!(
+            "SELECT count(*) FROM pragma_table_info('{table}') WHERE name='{column}'"
+        ))
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap_or(0)
+            > 0;
+
+        if !has_column {
+            log::info!("Migrating {table} table: adding {column} column");
+            sqlx::query(&format!(
+                "ALTER TABLE {table} ADD COLUMN \"{column}\" TEXT REFERENCES payees(id) ON DELETE SET NULL"
+            ))
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_transactions_payee_id ON transactions(\"payeeId\")")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_scheduled_payee_id ON scheduled_transactions(\"payeeId\")")
+        .execute(&mut *tx)
+        .await?;
+
+    // Migration: Add setTags to rules
+    let has_set_tags: bool = sqlx::query_scalar(
+        "SELECT count(*) FROM pragma_table_info('rules') WHERE name='setTags'",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap_or(0)
+        > 0;
+
+    if !has_set_tags {
+        log::info!("Migrating rules table: adding setTags column");
+        sqlx::query("ALTER TABLE rules ADD COLUMN \"setTags\" TEXT NOT NULL DEFAULT '[]'")
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // Migration: Add reconciliationId to transactions
+    let has_reconciliation_id: bool = sqlx::query_scalar(
+        "SELECT count(*) FROM pragma_table_info('transactions') WHERE name='reconciliationId'",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap_or(0)
+        > 0;
+
+    if !has_reconciliation_id {
+        log::info!("Migrating transactions table: adding reconciliationId column");
+        sqlx::query("ALTER TABLE transactions ADD COLUMN \"reconciliationId\" TEXT REFERENCES reconciliations(id) ON DELETE SET NULL")
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_transactions_reconciliation_id ON transactions(\"reconciliationId\")")
+        .execute(&mut *tx)
+        .await?;
+
+    // Migration: Replace checked by status, with the dates it changed
+    let has_status: bool = sqlx::query_scalar(
+        "SELECT count(*) FROM pragma_table_info('transactions') WHERE name='status'",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap_or(0)
+        > 0;
+
+    if !has_status {
+        log::info!("Migrating transactions table: replacing checked with status");
+        sqlx::query("ALTER TABLE transactions ADD COLUMN status TEXT NOT NULL DEFAULT 'pending'")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("ALTER TABLE transactions ADD COLUMN \"clearedAt\" TEXT")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("ALTER TABLE transactions ADD COLUMN \"reconciledAt\" TEXT")
+            .execute(&mut *tx)
+            .await?;
+        // Checked transactions locked by a reconciliation become reconciled on
+        // the day it was finished; when they were cleared is unknown
+        sqlx::query(
+            "UPDATE transactions SET
+                status = CASE WHEN checked = 1 AND \"reconciliationId\" IS NOT NULL THEN 'reconciled'
+                              WHEN checked = 1 THEN 'cleared'
+                              ELSE 'pending' END,
+                \"reconciledAt\" = CASE WHEN checked = 1 THEN
+                    (SELECT substr(r.\"finishedAt\", 1, 10) FROM reconciliations r WHERE r.id = \"reconciliationId\") END",
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("ALTER TABLE transactions DROP COLUMN checked")
+            .execute(&mut *tx)
+            .await?;
+        migrate_checked_snapshots(&mut tx).await?;
+    }
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_transactions_account_status ON transactions(\"accountId\", status)")
+        .execute(&mut *tx)
+        .await?;
+
+    // Migration: Replace setChecked by setStatus in rules
+    let has_set_status: bool = sqlx::query_scalar(
+        "SELECT count(*) FROM pragma_table_info('rules') WHERE name='setStatus'",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap_or(0)
+        > 0;
+
+    if !has_set_status {
+        log::info!("Migrating rules table: replacing setChecked with setStatus");
+        sqlx::query("ALTER TABLE rules ADD COLUMN \"setStatus\" TEXT")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "UPDATE rules SET \"setStatus\" = CASE \"setChecked\" WHEN 1 THEN 'cleared' WHEN 0 THEN 'pending' END",
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("ALTER TABLE rules DROP COLUMN \"setChecked\"")
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // Migration: Add openingDate to accounts
+    let has_opening_date: bool = sqlx::query_scalar(
+        "SELECT count(*) FROM pragma_table_info('accounts') WHERE name='openingDate'",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap_or(0)
+        > 0;
+
+    if !has_opening_date {
+        log::info!("Migrating accounts table: adding openingDate column");
+        sqlx::query("ALTER TABLE accounts ADD COLUMN \"openingDate\" TEXT")
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // Migration: Add archived and closedOn to accounts
+    let has_archived: bool = sqlx::query_scalar(
+        "SELECT count(*) FROM pragma_table_info('accounts') WHERE name='archived'",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap_or(0)
+        > 0;
+
+    if !has_archived {
+        log::info!("Migrating accounts table: adding archived and closedOn columns");
+        sqlx::query("ALTER TABLE accounts ADD COLUMN archived BOOLEAN NOT NULL DEFAULT 0")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("ALTER TABLE accounts ADD COLUMN \"closedOn\" TEXT")
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // Migration: Typed account kinds, with a credit limit for credit cards
+    let has_credit_limit: bool = sqlx::query_scalar(
+        "SELECT count(*) FROM pragma_table_info('accounts') WHERE name='creditLimit'",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap_or(0)
+        > 0;
+
+    if !has_credit_limit {
+        log::info!("Migrating accounts table: adding creditLimit column and typed kinds");
+        sqlx::query("ALTER TABLE accounts ADD COLUMN \"creditLimit\" REAL")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&format!("UPDATE accounts SET \"type\" = {}", legacy_kind("\"type\"")))
+            .execute(&mut *tx)
+            .await?;
+        migrate_kind_snapshots(&mut tx).await?;
+    }
+
+    // Migration: Add credit card statement days to accounts
+    let has_statement_days: bool = sqlx::query_scalar(
+        "SELECT count(*) FROM pragma_table_info('accounts') WHERE name='statementClosingDay'",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap_or(0)
+        > 0;
+
+    if !has_statement_days {
+        log::info!("Migrating accounts table: adding statementClosingDay and paymentDueDay columns");
+        sqlx::query("ALTER TABLE accounts ADD COLUMN \"statementClosingDay\" INTEGER")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("ALTER TABLE accounts ADD COLUMN \"paymentDueDay\" INTEGER")
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // Migration: Add loan terms to accounts
+    let has_loan_terms: bool = sqlx::query_scalar(
+        "SELECT count(*) FROM pragma_table_info('accounts') WHERE name='loanPrincipal'",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap_or(0)
+        > 0;
+
+    if !has_loan_terms {
+        log::info!("Migrating accounts table: adding loan terms columns");
+        for column in ["\"loanPrincipal\" REAL", "\"loanRate\" REAL", "\"loanTermMonths\" INTEGER", "\"loanStartDate\" TEXT"] {
+            sqlx::query(&format!("ALTER TABLE accounts ADD COLUMN {column}"))
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    // Migration: Add depreciation to accounts
+    let has_depreciation: bool = sqlx::query_scalar(
+        "SELECT count(*) FROM pragma_table_info('accounts') WHERE name='depreciationMethod'",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap_or(0)
+        > 0;
+
+    if !has_depreciation {
+        log::info!("Migrating accounts table: adding depreciation columns");
+        for column in ["\"depreciationMethod\" TEXT", "\"depreciationRate\" REAL"] {
+            sqlx::query(&format!("ALTER TABLE accounts ADD COLUMN {column}"))
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    // Rows the rebuilds could not fix (e.g. transactions of a deleted account)
+    // are left for check_integrity / repair_integrity.
+    let violations: Vec<(String,)> = sqlx::query_as("SELECT \"table\" FROM pragma_foreign_key_check")
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap_or_default();
+    if !violations.is_empty() {
+        log::warn!("{} rows violate foreign key constraints", violations.len());
+    }
+
     tx.commit().await?;
 
     Ok(())
 }
+
+// Status of a transaction snapshot that still has `checked`
+fn snapshot_status(snapshot: &str) -> String {
+    format!(
+        "CASE WHEN json_extract({snapshot}, '$.checked') = 1 AND json_extract({snapshot}, '$.reconciliationId') IS NOT NULL THEN 'reconciled'
+              WHEN json_extract({snapshot}, '$.checked') = 1 THEN 'cleared'
+              ELSE 'pending' END"
+    )
+}
+
+// Status set by a rule snapshot that still has `setChecked`
+fn snapshot_set_status(snapshot: &str) -> String {
+    format!("CASE json_extract({snapshot}, '$.setChecked') WHEN 1 THEN 'cleared' WHEN 0 THEN 'pending' END")
+}
+
+/// Rewrites the audit snapshots taken before the status migration, so undo
+/// and redo restore the status instead of falling back to pending.
+async fn migrate_checked_snapshots(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    for column in ["\"before\"", "\"after\""] {
+        sqlx::query(&format!(
+            "UPDATE audit_log SET {column} = json_remove(json_set({column}, '$.status', {}), '$.checked')
+             WHERE entity = 'transaction' AND json_type({column}, '$.checked') IS NOT NULL",
+            snapshot_status(column)
+        ))
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query(&format!(
+            "UPDATE audit_log SET {column} = json_remove(json_set({column}, '$.setStatus', {}), '$.setChecked')
+             WHERE entity = 'rule' AND json_type({column}, '$.setChecked') IS NOT NULL",
+            snapshot_set_status(column)
+        ))
+        .execute(&mut *conn)
+        .await?;
+
+        // Whole-database snapshots of imports
+        sqlx::query(&format!(
+            "UPDATE audit_log SET {column} = json_set({column}, '$.transactions', (
+                 SELECT json_group_array(json(json_remove(json_set(value, '$.status', {}), '$.checked')))
+                 FROM json_each({column}, '$.transactions')))
+             WHERE entity = 'import' AND json_type({column}, '$.transactions') = 'array'",
+            snapshot_status("value")
+        ))
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query(&format!(
+            "UPDATE audit_log SET {column} = json_set({column}, '$.rules', (
+                 SELECT json_group_array(json(json_remove(json_set(value, '$.setStatus', {}), '$.setChecked')))
+                 FROM json_each({column}, '$.rules')))
+             WHERE entity = 'import' AND json_type({column}, '$.rules') = 'array'",
+            snapshot_set_status("value")
+        ))
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+// Kind of an account whose type may still be a legacy label
+fn legacy_kind(kind: &str) -> String {
+    let known = ACCOUNT_KINDS.map(|k| format!("'{k}'")).join(", ");
+    let legacy: String = LEGACY_KINDS
+        .iter()
+        .map(|(label, k)| format!(" WHEN '{label}' THEN '{k}'"))
+        .collect();
+    format!("CASE WHEN {kind} IN ({known}) THEN {kind} ELSE CASE {kind}{legacy} ELSE '{KIND_CHECKING}' END END")
+}
+
+/// Rewrites the account types of the audit snapshots taken before kinds were
+/// validated, so undo and redo restore valid accounts.
+async fn migrate_kind_snapshots(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    for column in ["\"before\"", "\"after\""] {
+        sqlx::query(&format!(
+            "UPDATE audit_log SET {column} = json_set({column}, '$.type', {})
+             WHERE entity = 'account' AND json_type({column}, '$.type') = 'text'",
+            legacy_kind(&format!("json_extract({column}, '$.type')"))
+        ))
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query(&format!(
+            "UPDATE audit_log SET {column} = json_set({column}, '$.accounts', (
+                 SELECT json_group_array(json(json_set(value, '$.type', {})))
+                 FROM json_each({column}, '$.accounts')))
+             WHERE entity = 'import' AND json_type({column}, '$.accounts') = 'array'",
+            legacy_kind("json_extract(value, '$.type')")
+        ))
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn create_indexes(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_transactions_account_id ON transactions(\"accountId\")")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_transactions_date ON transactions(date)")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_transactions_category ON transactions(category)")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_transactions_linked_id ON transactions(\"linkedTransactionId\")")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_scheduled_account_id ON scheduled_transactions(\"accountId\")")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_scheduled_to_account_id ON scheduled_transactions(\"toAccountId\")")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_scheduled_category ON scheduled_transactions(category)")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_rules_position ON rules(position)")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_transaction_tags_tag_id ON transaction_tags(\"tagId\")")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_attachments_transaction_id ON attachments(\"transactionId\")")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_attachments_hash ON attachments(hash)")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_reconciliations_account_id ON reconciliations(\"accountId\")")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_balance_assertions_account_date ON balance_assertions(\"accountId\", date)")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_trades_account_date ON trades(\"accountId\", date)")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_trades_security_id ON trades(\"securityId\")")
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Inserts the categories the schema relies on: the default target of
+/// ON DELETE SET DEFAULT and the category of transfer legs.
+pub async fn seed_system_categories(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO categories (id, name, icon, color) VALUES
+            ($1, 'Non catégorisé', 'Tag', '#9ca3af'),
+            ($2, 'Virement', 'ArrowRightLeft', '#6366f1')",
+    )
+    .bind(UNCATEGORIZED_CATEGORY_ID)
+    .bind(TRANSFER_CATEGORY_ID)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn run(conn: &mut SqliteConnection, statements: &[&str]) {
+        for sql in statements {
+            sqlx::query(sql).execute(&mut *conn).await.unwrap();
+        }
+    }
+
+    async fn column(conn: &mut SqliteConnection, sql: &str) -> Vec<Option<String>> {
+        sqlx::query_scalar(sql).fetch_all(&mut *conn).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn deletions_follow_the_referential_actions() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        run(&mut conn, &[
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'A', 'checking', 0), ('a2', 'B', 'checking', 0)",
+            "INSERT INTO categories (id, name, icon, color) VALUES ('c1', 'Courses', 'Tag', '#fff')",
+            "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, category, \"linkedTransactionId\") VALUES
+                ('t1', '2026-01-10', 'a1', 'expense', 10, 'c1', 't2'),
+                ('t2', '2026-01-10', 'a2', 'income', 10, 'transfer', 't1')",
+        ]).await;
+
+        run(&mut conn, &["DELETE FROM categories WHERE id = 'c1'"]).await;
+        assert_eq!(column(&mut conn, "SELECT category FROM transactions WHERE id = 't1'").await, [Some(UNCATEGORIZED_CATEGORY_ID.to_string())]);
+
+        run(&mut conn, &["DELETE FROM accounts WHERE id = 'a2'"]).await;
+        assert_eq!(column(&mut conn, "SELECT \"linkedTransactionId\" FROM transactions").await, [None]);
+
+        let unknown = sqlx::query("INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, category) VALUES ('t3', '2026-01-10', 'gone', 'expense', 1, 'c1')")
+            .execute(&mut *conn)
+            .await;
+        assert!(unknown.is_err());
+    }
+
+    #[tokio::test]
+    async fn rebuilds_legacy_tables_with_foreign_keys() {
+        let pool = memory_pool_without_schema().await;
+        let mut conn = pool.acquire().await.unwrap();
+        run(&mut conn, &[
+            "CREATE TABLE accounts (id TEXT PRIMARY KEY, name TEXT NOT NULL, \"type\" TEXT NOT NULL, \"initialBalance\" REAL NOT NULL, color TEXT, icon TEXT)",
+            "CREATE TABLE categories (id TEXT PRIMARY KEY, name TEXT NOT NULL, icon TEXT NOT NULL, color TEXT NOT NULL)",
+            "CREATE TABLE transactions (id TEXT PRIMARY KEY, date TEXT NOT NULL, \"accountId\" TEXT NOT NULL, \"type\" TEXT NOT NULL, amount REAL NOT NULL, category TEXT NOT NULL, description TEXT, checked BOOLEAN DEFAULT 0, \"isTransfer\" BOOLEAN DEFAULT 0, \"linkedTransactionId\" TEXT, FOREIGN KEY(\"accountId\") REFERENCES accounts(id))",
+            "CREATE TABLE scheduled_transactions (id TEXT PRIMARY KEY, description TEXT NOT NULL, amount REAL NOT NULL, \"type\" TEXT NOT NULL, frequency TEXT NOT NULL, \"accountId\" TEXT NOT NULL, \"nextDate\" TEXT NOT NULL, category TEXT NOT NULL, \"toAccountId\" TEXT, \"includeInForecast\" BOOLEAN DEFAULT 1, \"endDate\" TEXT, FOREIGN KEY(\"accountId\") REFERENCES accounts(id))",
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'A', 'checking', 0)",
+            "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, category, \"linkedTransactionId\") VALUES ('t1', '2026-01-10', 'a1', 'expense', 10, 'gone', 'nowhere')",
+            "INSERT INTO scheduled_transactions (id, description, amount, \"type\", frequency, \"accountId\", \"nextDate\", category, \"toAccountId\") VALUES ('s1', 'Loyer', 500, 'expense', 'monthly', 'a1', '2026-02-01', 'gone', 'nowhere')",
+        ]).await;
+        drop(conn);
+
+        create_tables(&pool).await.unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+        assert_eq!(
+            column(&mut conn, "SELECT category || ':' || IFNULL(\"linkedTransactionId\", '-') FROM transactions").await,
+            [Some("uncategorized:-".to_string())]
+        );
+        assert_eq!(
+            column(&mut conn, "SELECT category || ':' || IFNULL(\"toAccountId\", '-') FROM scheduled_transactions").await,
+            [Some("uncategorized:-".to_string())]
+        );
+        let keys: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pragma_foreign_key_list('transactions') WHERE \"from\" IN ('accountId', 'category', 'linkedTransactionId')",
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+        assert_eq!(keys, 3);
+    }
+
+    #[tokio::test]
+    async fn migrates_scheduled_transactions_without_transfer_columns() {
+        let pool = memory_pool_without_schema().await;
+        let mut conn = pool.acquire().await.unwrap();
+        run(&mut conn, &[
+            "CREATE TABLE accounts (id TEXT PRIMARY KEY, name TEXT NOT NULL, \"type\" TEXT NOT NULL, \"initialBalance\" REAL NOT NULL, color TEXT, icon TEXT)",
+            "CREATE TABLE scheduled_transactions (id TEXT PRIMARY KEY, description TEXT NOT NULL, amount REAL NOT NULL, \"type\" TEXT NOT NULL, frequency TEXT NOT NULL, \"accountId\" TEXT NOT NULL, \"nextDate\" TEXT NOT NULL, category TEXT NOT NULL, FOREIGN KEY(\"accountId\") REFERENCES accounts(id))",
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'A', 'checking', 0)",
+            "INSERT INTO scheduled_transactions (id, description, amount, \"type\", frequency, \"accountId\", \"nextDate\", category) VALUES ('s1', 'Loyer', 500, 'expense', 'monthly', 'a1', '2026-02-01', 'uncategorized')",
+        ]).await;
+        drop(conn);
+
+        create_tables(&pool).await.unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+        assert_eq!(
+            column(&mut conn, "SELECT id || ':' || IFNULL(\"toAccountId\", '-') FROM scheduled_transactions").await,
+            [Some("s1:-".to_string())]
+        );
+        assert_eq!(
+            column(&mut conn, "SELECT name FROM pragma_index_info('idx_scheduled_to_account_id')").await,
+            [Some("toAccountId".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn checked_becomes_status_in_rows_and_snapshots() {
+        let pool = memory_pool_without_schema().await;
+        let mut conn = pool.acquire().await.unwrap();
+        run(&mut conn, &[
+            "CREATE TABLE accounts (id TEXT PRIMARY KEY, name TEXT NOT NULL, \"type\" TEXT NOT NULL, \"initialBalance\" REAL NOT NULL, color TEXT, icon TEXT)",
+            "CREATE TABLE transactions (id TEXT PRIMARY KEY, date TEXT NOT NULL, \"accountId\" TEXT NOT NULL, \"type\" TEXT NOT NULL, amount REAL NOT NULL, category TEXT NOT NULL, description TEXT, checked BOOLEAN DEFAULT 0, \"isTransfer\" BOOLEAN DEFAULT 0, \"linkedTransactionId\" TEXT, FOREIGN KEY(\"accountId\") REFERENCES accounts(id))",
+            "CREATE TABLE audit_log (id INTEGER PRIMARY KEY AUTOINCREMENT, \"timestamp\" TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')), entity TEXT NOT NULL, \"entityId\" TEXT NOT NULL, action TEXT NOT NULL, \"before\" TEXT, \"after\" TEXT)",
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'A', 'checking', 0)",
+            "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, category, checked) VALUES
+                ('t1', '2026-01-10', 'a1', 'expense', 10, 'uncategorized', 1),
+                ('t2', '2026-01-11', 'a1', 'expense', 20, 'uncategorized', 0)",
+            "INSERT INTO audit_log (entity, \"entityId\", action, \"before\", \"after\") VALUES
+                ('transaction', 't1', 'update', '{\"id\":\"t1\",\"checked\":false}', '{\"id\":\"t1\",\"checked\":true}')",
+        ]).await;
+        drop(conn);
+
+        create_tables(&pool).await.unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+        assert_eq!(
+            column(&mut conn, "SELECT id || ':' || status FROM transactions ORDER BY id").await,
+            [Some("t1:cleared".to_string()), Some("t2:pending".to_string())]
+        );
+        assert_eq!(
+            column(&mut conn, "SELECT \"before\" || ' ' || \"after\" FROM audit_log").await,
+            [Some(r#"{"id":"t1","status":"pending"} {"id":"t1","status":"cleared"}"#.to_string())]
+        );
+    }
+}
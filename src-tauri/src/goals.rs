@@ -0,0 +1,151 @@
+use crate::audit;
+use crate::balances::{balance_at, round_cents, SIGNED_AMOUNT};
+use crate::dates::{day_of_month, format_date, months_between, parse_date};
+use crate::models::{Goal, GoalProgress};
+use sqlx::SqliteConnection;
+
+/// Months of contributions the pace of a goal is averaged over.
+pub const CONTRIBUTION_MONTHS: u32 = 3;
+
+// Beyond this the goal is out of reach at the current pace
+const MAX_PROJECTION_MONTHS: f64 = 1200.0;
+
+pub async fn fetch_goals(conn: &mut SqliteConnection) -> Result<Vec<Goal>, sqlx::Error> {
+    sqlx::query_as::<_, Goal>("SELECT * FROM goals ORDER BY name")
+        .fetch_all(&mut *conn)
+        .await
+}
+
+pub fn validate(goal: &Goal) -> Result<(), String> {
+    if goal.name.trim().is_empty() {
+        return Err("Le nom de l'objectif est obligatoire.".to_string());
+    }
+    if goal.target_amount <= 0.0 {
+        return Err("Le montant visé doit être positif.".to_string());
+    }
+    if let Some(date) = &goal.target_date {
+        if parse_date(date).is_none() {
+            return Err(format!("Date invalide : {date}"));
+        }
+    }
+    if goal.account_id.is_some() == goal.tag_id.is_some() {
+        return Err("Un objectif suit soit un compte, soit un tag.".to_string());
+    }
+    Ok(())
+}
+
+/// Amount saved towards `goal` at the end of `date`: the balance of its
+/// account, or the transactions carrying its tag, incomes adding to it and
+/// expenses taking from it.
+async fn amount_at(conn: &mut SqliteConnection, goal: &Goal, date: &str) -> Result<f64, sqlx::Error> {
+    if let Some(account_id) = &goal.account_id {
+        return match audit::fetch_account(conn, account_id).await? {
+            Some(account) => balance_at(conn, &account, date).await,
+            None => Ok(0.0),
+        };
+    }
+    let total: f64 = sqlx::query_scalar(&format!(
+        "SELECT TOTAL({SIGNED_AMOUNT}) FROM transactions t
+         JOIN transaction_tags l ON l.\"transactionId\" = t.id
+         WHERE l.\"tagId\" = $1 AND t.date <= $2"
+    ))
+    .bind(&goal.tag_id)
+    .bind(date)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(round_cents(total))
+}
+
+/// Progress of `goal` at the end of `as_of` (YYYY-MM-DD). The projection
+/// carries on the average contribution of the last CONTRIBUTION_MONTHS
+/// months.
+pub async fn progress(conn: &mut SqliteConnection, goal: &Goal, as_of: &str) -> Result<GoalProgress, String> {
+    let date = parse_date(as_of).ok_or_else(|| format!("Date invalide : {as_of}"))?;
+    let since = format_date(day_of_month(date, -(CONTRIBUTION_MONTHS as i32), date.2));
+
+    let current = amount_at(conn, goal, as_of)
+        .await
+        .map_err(|e| format!("Erreur BDD (progression de l'objectif): {e}"))?;
+    let earlier = amount_at(conn, goal, &since)
+        .await
+        .map_err(|e| format!("Erreur BDD (progression de l'objectif): {e}"))?;
+    let remaining = round_cents((goal.target_amount - current).max(0.0));
+    let monthly_contribution = round_cents((current - earlier) / CONTRIBUTION_MONTHS as f64);
+
+    // Whatever is left is due at once when the target date has come
+    let required_monthly_contribution = goal.target_date.as_deref().and_then(parse_date).map(|target| {
+        let months = months_between(date, target).max(1);
+        round_cents(remaining / months as f64)
+    });
+
+    let projected_date = if remaining == 0.0 {
+        Some(as_of.to_string())
+    } else if monthly_contribution > 0.0 {
+        let months = (remaining / monthly_contribution).ceil();
+        (months <= MAX_PROJECTION_MONTHS).then(|| format_date(day_of_month(date, months as i32, date.2)))
+    } else {
+        None
+    };
+
+    Ok(GoalProgress {
+        goal_id: goal.id.clone(),
+        date: as_of.to_string(),
+        current,
+        remaining,
+        monthly_contribution,
+        required_monthly_contribution,
+        projected_date,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    fn goal(account_id: Option<&str>, tag_id: Option<&str>) -> Goal {
+        Goal {
+            id: "g1".to_string(),
+            name: "Vacances".to_string(),
+            target_amount: 1000.0,
+            target_date: Some("2026-12-31".to_string()),
+            account_id: account_id.map(str::to_string),
+            tag_id: tag_id.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn goals_follow_an_account_or_a_tag() {
+        assert!(validate(&goal(Some("a1"), None)).is_ok());
+        assert!(validate(&goal(None, Some("travel"))).is_ok());
+        assert!(validate(&goal(None, None)).is_err());
+        assert!(validate(&goal(Some("a1"), Some("travel"))).is_err());
+        assert!(validate(&Goal { target_amount: 0.0, ..goal(Some("a1"), None) }).is_err());
+        assert!(validate(&Goal { target_date: Some("2026-02-30".to_string()), ..goal(Some("a1"), None) }).is_err());
+    }
+
+    #[tokio::test]
+    async fn progress_projects_the_recent_pace() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        sqlx::query(
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'Livret', 'savings', 100);
+             INSERT INTO transactions (id, date, \"accountId\", \"type\", amount) VALUES
+             ('t1', '2026-02-01', 'a1', 'income', 100), ('t2', '2026-03-01', 'a1', 'income', 100),
+             ('t3', '2026-04-01', 'a1', 'income', 100)",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+        let progress = progress(&mut conn, &goal(Some("a1"), None), "2026-04-15").await.unwrap();
+        assert_eq!((progress.current, progress.remaining, progress.monthly_contribution), (400.0, 600.0, 100.0));
+        assert_eq!(progress.required_monthly_contribution, Some(75.0));
+        assert_eq!(progress.projected_date.as_deref(), Some("2026-10-15"));
+
+        let reached = super::progress(&mut conn, &Goal { target_amount: 300.0, ..goal(Some("a1"), None) }, "2026-04-15")
+            .await
+            .unwrap();
+        assert_eq!((reached.remaining, reached.projected_date.as_deref()), (0.0, Some("2026-04-15")));
+    }
+}
@@ -0,0 +1,710 @@
+use crate::audit::{self, Operation};
+use crate::db;
+use crate::models::{
+    Account, AppData, Attachment, BalanceAssertion, Category, Goal, Reconciliation, HistoryEntry, Payee, Price, Rule, ScheduledTransaction, Security, Tag,
+    Trade, Transaction, Valuation,
+    STATUS_CLEARED, STATUS_PENDING, STATUS_RECONCILED, TRANSFER_CATEGORY_ID, UNCATEGORIZED_CATEGORY_ID,
+};
+use serde::de::DeserializeOwned;
+use sqlx::SqliteConnection;
+use std::collections::HashSet;
+
+#[derive(sqlx::FromRow)]
+struct Change {
+    entity: String,
+    #[sqlx(rename = "entityId")]
+    entity_id: String,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Direction {
+    Undo,
+    Redo,
+}
+
+/// Reverts the last `steps` operations, most recent first.
+pub async fn undo(conn: &mut SqliteConnection, steps: u32) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+    replay(conn, steps, Direction::Undo).await
+}
+
+/// Re-applies the last `steps` undone operations, in the order they were undone.
+pub async fn redo(conn: &mut SqliteConnection, steps: u32) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+    replay(conn, steps, Direction::Redo).await
+}
+
+async fn replay(
+    conn: &mut SqliteConnection,
+    steps: u32,
+    direction: Direction,
+) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+    // Undo pops the newest applied operation; redo pops the oldest undone one,
+    // which is the last one that was undone.
+    let (next_query, entries_query, mut journal) = match direction {
+        Direction::Undo => (
+            "SELECT * FROM operations WHERE undone = 0 ORDER BY id DESC LIMIT 1",
+            "SELECT entity, \"entityId\", \"before\", \"after\" FROM audit_log WHERE \"operationId\" = $1 ORDER BY id DESC",
+            Operation::untracked("undo"),
+        ),
+        Direction::Redo => (
+            "SELECT * FROM operations WHERE undone = 1 ORDER BY id ASC LIMIT 1",
+            "SELECT entity, \"entityId\", \"before\", \"after\" FROM audit_log WHERE \"operationId\" = $1 ORDER BY id ASC",
+            Operation::untracked("redo"),
+        ),
+    };
+    let mut replayed = Vec::new();
+
+    for _ in 0..steps {
+        let Some(mut operation) = sqlx::query_as::<_, HistoryEntry>(next_query)
+            .fetch_optional(&mut *conn)
+            .await?
+        else {
+            break;
+        };
+
+        let changes = sqlx::query_as::<_, Change>(entries_query)
+            .bind(operation.id)
+            .fetch_all(&mut *conn)
+            .await?;
+
+        for change in changes {
+            let (current, target, action) = match direction {
+                Direction::Undo => (change.after, change.before, audit::ACTION_UNDO),
+                Direction::Redo => (change.before, change.after, audit::ACTION_REDO),
+            };
+            restore(conn, &change.entity, &change.entity_id, target.as_deref()).await?;
+            audit::record_json(conn, &mut journal, &change.entity, &change.entity_id, action, current, target)
+                .await?;
+        }
+
+        operation.undone = direction == Direction::Undo;
+        sqlx::query("UPDATE operations SET undone = $1 WHERE id = $2")
+            .bind(operation.undone)
+            .bind(operation.id)
+            .execute(&mut *conn)
+            .await?;
+        replayed.push(operation);
+    }
+
+    Ok(replayed)
+}
+
+fn parse<T: DeserializeOwned>(snapshot: &str) -> Result<T, sqlx::Error> {
+    serde_json::from_str(snapshot).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+}
+
+/// Puts a row back into the state described by an audit snapshot.
+/// A missing snapshot means the row did not exist.
+async fn restore(
+    conn: &mut SqliteConnection,
+    entity: &str,
+    entity_id: &str,
+    snapshot: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let table = match entity {
+        audit::ENTITY_ACCOUNT => "accounts",
+        audit::ENTITY_TRANSACTION => "transactions",
+        audit::ENTITY_CATEGORY => "categories",
+        audit::ENTITY_SCHEDULED => "scheduled_transactions",
+        audit::ENTITY_RULE => "rules",
+        audit::ENTITY_PAYEE => "payees",
+        audit::ENTITY_TAG => "tags",
+        audit::ENTITY_ATTACHMENT => "attachments",
+        audit::ENTITY_RECONCILIATION => "reconciliations",
+        audit::ENTITY_BALANCE_ASSERTION => "balance_assertions",
+        audit::ENTITY_SECURITY => "securities",
+        audit::ENTITY_TRADE => "trades",
+        audit::ENTITY_PRICE => "prices",
+        audit::ENTITY_VALUATION => "valuations",
+        audit::ENTITY_GOAL => "goals",
+        audit::ENTITY_TRANSACTION_TAG => {
+            return match snapshot {
+                Some(snapshot) => crate::tags::insert_link(conn, &parse(snapshot)?).await.map(|_| ()),
+                None => match entity_id.split_once(':') {
+                    Some((transaction_id, tag_id)) => crate::tags::delete_link(conn, transaction_id, tag_id).await.map(|_| ()),
+                    None => Ok(()),
+                },
+            };
+        }
+        audit::ENTITY_IMPORT => {
+            return match snapshot {
+                Some(snapshot) => replace_app_data(conn, &parse::<AppData>(snapshot)?).await,
+                None => Ok(()),
+            };
+        }
+        _ => {
+            log::warn!("Skipping restore of unsupported entity {entity}");
+            return Ok(());
+        }
+    };
+
+    let Some(snapshot) = snapshot else {
+        sqlx::query(&format!("DELETE FROM {table} WHERE id = $1"))
+            .bind(entity_id)
+            .execute(&mut *conn)
+            .await?;
+        return Ok(());
+    };
+
+    match entity {
+        audit::ENTITY_ACCOUNT => upsert_account(conn, &parse(snapshot)?).await,
+        audit::ENTITY_TRANSACTION => upsert_transaction(conn, &parse(snapshot)?).await,
+        audit::ENTITY_CATEGORY => upsert_category(conn, &parse(snapshot)?).await,
+        audit::ENTITY_RULE => upsert_rule(conn, &parse(snapshot)?).await,
+        audit::ENTITY_PAYEE => upsert_payee(conn, &parse(snapshot)?).await,
+        audit::ENTITY_TAG => upsert_tag(conn, &parse(snapshot)?).await,
+        audit::ENTITY_ATTACHMENT => upsert_attachment(conn, &parse(snapshot)?).await,
+        audit::ENTITY_RECONCILIATION => upsert_reconciliation(conn, &parse(snapshot)?).await,
+        audit::ENTITY_BALANCE_ASSERTION => upsert_balance_assertion(conn, &parse(snapshot)?).await,
+        audit::ENTITY_SECURITY => upsert_security(conn, &parse(snapshot)?).await,
+        audit::ENTITY_TRADE => upsert_trade(conn, &parse(snapshot)?).await,
+        audit::ENTITY_PRICE => upsert_price(conn, &parse(snapshot)?).await,
+        audit::ENTITY_VALUATION => upsert_valuation(conn, &parse(snapshot)?).await,
+        audit::ENTITY_GOAL => upsert_goal(conn, &parse(snapshot)?).await,
+        _ => upsert_scheduled(conn, &parse(snapshot)?).await,
+    }
+}
+
+/// Replaces every account, transaction, category, scheduled transaction,
+/// rule, payee, tag, reconciliation, balance assertion, attachment,
+/// security, trade, price, valuation and goal. Data without some of the optional lists keeps
+/// the current rows for them.
+pub async fn replace_app_data(conn: &mut SqliteConnection, data: &AppData) -> Result<(), sqlx::Error> {
+    let rules = match &data.rules {
+        Some(rules) => rules.clone(),
+        None => crate::rules::fetch_rules(conn).await?,
+    };
+    let payees = match &data.payees {
+        Some(payees) => payees.clone(),
+        None => crate::payees::fetch_payees(conn).await?,
+    };
+    let tags = match &data.tags {
+        Some(tags) => tags.clone(),
+        None => crate::tags::fetch_tags(conn).await?,
+    };
+    let links = match &data.transaction_tags {
+        Some(links) => links.clone(),
+        None => crate::tags::fetch_links(conn).await?,
+    };
+    let reconciliations = match &data.reconciliations {
+        Some(reconciliations) => reconciliations.clone(),
+        None => crate::reconciliation::fetch_reconciliations(conn, None).await?,
+    };
+    let balance_assertions = match &data.balance_assertions {
+        Some(assertions) => assertions.clone(),
+        None => crate::balances::fetch_assertions(conn, None).await?,
+    };
+    let attachments = match &data.attachments {
+        Some(attachments) => attachments.clone(),
+        None => crate::attachments::fetch_attachments(conn, None).await?,
+    };
+    let securities = match &data.securities {
+        Some(securities) => securities.clone(),
+        None => crate::investments::fetch_securities(conn).await?,
+    };
+    let trades = match &data.trades {
+        Some(trades) => trades.clone(),
+        None => crate::investments::fetch_trades(conn, None).await?,
+    };
+    let prices = match &data.prices {
+        Some(prices) => prices.clone(),
+        None => crate::investments::fetch_prices(conn, None).await?,
+    };
+    let valuations = match &data.valuations {
+        Some(valuations) => valuations.clone(),
+        None => crate::assets::fetch_valuations(conn, None).await?,
+    };
+    let goals = match &data.goals {
+        Some(goals) => goals.clone(),
+        None => crate::goals::fetch_goals(conn).await?,
+    };
+
+    sqlx::query("DELETE FROM goals").execute(&mut *conn).await?;
+    sqlx::query("DELETE FROM attachments").execute(&mut *conn).await?;
+    sqlx::query("DELETE FROM transaction_tags").execute(&mut *conn).await?;
+    sqlx::query("DELETE FROM tags").execute(&mut *conn).await?;
+    sqlx::query("DELETE FROM rules").execute(&mut *conn).await?;
+    sqlx::query("DELETE FROM transactions").execute(&mut *conn).await?;
+    sqlx::query("DELETE FROM reconciliations").execute(&mut *conn).await?;
+    sqlx::query("DELETE FROM balance_assertions").execute(&mut *conn).await?;
+    sqlx::query("DELETE FROM trades").execute(&mut *conn).await?;
+    sqlx::query("DELETE FROM valuations").execute(&mut *conn).await?;
+    sqlx::query("DELETE FROM prices").execute(&mut *conn).await?;
+    sqlx::query("DELETE FROM securities").execute(&mut *conn).await?;
+    sqlx::query("DELETE FROM scheduled_transactions").execute(&mut *conn).await?;
+    sqlx::query("DELETE FROM accounts").execute(&mut *conn).await?;
+    sqlx::query("DELETE FROM categories").execute(&mut *conn).await?;
+    sqlx::query("DELETE FROM payees").execute(&mut *conn).await?;
+    db::seed_system_categories(conn).await?;
+
+    for acc in &data.accounts {
+        let acc = Account {
+            account_type: crate::accounts::known_kind(&acc.account_type),
+            ..acc.clone()
+        };
+        upsert_account(conn, &acc).await?;
+    }
+    for payee in &payees {
+        upsert_payee(conn, payee).await?;
+    }
+    let account_ids: HashSet<&str> = data.accounts.iter().map(|a| a.id.as_str()).collect();
+    let mut reconciliation_ids = HashSet::new();
+    for reconciliation in reconciliations
+        .iter()
+        .filter(|r| account_ids.contains(r.account_id.as_str()))
+    {
+        upsert_reconciliation(conn, reconciliation).await?;
+        reconciliation_ids.insert(reconciliation.id.as_str());
+    }
+    for assertion in balance_assertions
+        .iter()
+        .filter(|a| account_ids.contains(a.account_id.as_str()))
+    {
+        upsert_balance_assertion(conn, assertion).await?;
+    }
+    for valuation in valuations
+        .iter()
+        .filter(|v| account_ids.contains(v.account_id.as_str()))
+    {
+        upsert_valuation(conn, valuation).await?;
+    }
+    for security in &securities {
+        upsert_security(conn, security).await?;
+    }
+    let security_ids: HashSet<&str> = securities.iter().map(|s| s.id.as_str()).collect();
+    for price in prices.iter().filter(|p| security_ids.contains(p.security_id.as_str())) {
+        upsert_price(conn, price).await?;
+    }
+    for trade in trades
+        .iter()
+        .filter(|t| account_ids.contains(t.account_id.as_str()) && security_ids.contains(t.security_id.as_str()))
+    {
+        upsert_trade(conn, trade).await?;
+    }
+    for tag in &tags {
+        upsert_tag(conn, tag).await?;
+    }
+    let tag_ids: HashSet<&str> = tags.iter().map(|t| t.id.as_str()).collect();
+    for goal in goals.iter().filter(|g| {
+        g.account_id.as_deref().is_some_and(|id| account_ids.contains(id)) || g.tag_id.as_deref().is_some_and(|id| tag_ids.contains(id))
+    }) {
+        upsert_goal(conn, goal).await?;
+    }
+    // Parents may come after their children: insert flat, then attach
+    for cat in &data.categories {
+        let flat = Category {
+            parent_id: None,
+            ..cat.clone()
+        };
+        upsert_category(conn, &flat).await?;
+    }
+    for cat in data.categories.iter().filter(|c| c.parent_id.is_some()) {
+        upsert_category(conn, cat).await?;
+    }
+
+    // Older backups may reference categories that no longer exist
+    let known: HashSet<&str> = data
+        .categories
+        .iter()
+        .map(|c| c.id.as_str())
+        .chain([UNCATEGORIZED_CATEGORY_ID, TRANSFER_CATEGORY_ID])
+        .collect();
+    let known_category = |category: &str| {
+        if known.contains(category) {
+            category.to_string()
+        } else {
+            log::warn!("Unknown category {category} replaced by {UNCATEGORIZED_CATEGORY_ID}");
+            UNCATEGORIZED_CATEGORY_ID.to_string()
+        }
+    };
+
+    let known_payees: HashSet<&str> = payees.iter().map(|p| p.id.as_str()).collect();
+    let known_payee = |payee_id: &Option<String>| payee_id.clone().filter(|id| known_payees.contains(id.as_str()));
+
+    let known_status = |status: &str| {
+        if [STATUS_PENDING, STATUS_CLEARED, STATUS_RECONCILED].contains(&status) {
+            status.to_string()
+        } else {
+            log::warn!("Unknown transaction status {status} replaced by {STATUS_PENDING}");
+            STATUS_PENDING.to_string()
+        }
+    };
+
+    for t in &data.transactions {
+        let t = Transaction {
+            category: known_category(&t.category),
+            status: known_status(&t.status),
+            payee_id: known_payee(&t.payee_id),
+            reconciliation_id: t.reconciliation_id.clone().filter(|id| reconciliation_ids.contains(id.as_str())),
+            ..t.clone()
+        };
+        upsert_transaction(conn, &t).await?;
+    }
+    for s in &data.scheduled {
+        let s = ScheduledTransaction {
+            category: known_category(&s.category),
+            payee_id: known_payee(&s.payee_id),
+            ..s.clone()
+        };
+        upsert_scheduled(conn, &s).await?;
+    }
+
+    let transaction_ids: HashSet<&str> = data.transactions.iter().map(|t| t.id.as_str()).collect();
+    for link in links
+        .iter()
+        .filter(|l| transaction_ids.contains(l.transaction_id.as_str()) && tag_ids.contains(l.tag_id.as_str()))
+    {
+        crate::tags::insert_link(conn, link).await?;
+    }
+    for attachment in attachments
+        .iter()
+        .filter(|a| transaction_ids.contains(a.transaction_id.as_str()))
+    {
+        upsert_attachment(conn, attachment).await?;
+    }
+
+    for rule in &rules {
+        if rule.account_id.as_deref().is_some_and(|id| !account_ids.contains(id)) {
+            log::warn!("Dropping rule {} of unknown account", rule.id);
+            continue;
+        }
+        let rule = Rule {
+            set_category: rule.set_category.as_deref().map(known_category),
+            set_status: rule.set_status.as_deref().map(known_status),
+            set_payee: known_payee(&rule.set_payee),
+            set_tags: rule.set_tags.iter().filter(|id| tag_ids.contains(id.as_str())).cloned().collect(),
+            ..rule.clone()
+        };
+        upsert_rule(conn, &rule).await?;
+    }
+
+    Ok(())
+}
+
+// --- Upserts ---
+// ON CONFLICT DO UPDATE rather than INSERT OR REPLACE, which would delete the row first.
+
+pub async fn upsert_account(conn: &mut SqliteConnection, account: &Account) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO accounts (id, name, \"type\", \"initialBalance\", color, icon, \"openingDate\", archived, \"closedOn\", \"creditLimit\", \"statementClosingDay\", \"paymentDueDay\", \"loanPrincipal\", \"loanRate\", \"loanTermMonths\", \"loanStartDate\", \"depreciationMethod\", \"depreciationRate\") VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, \"type\" = excluded.\"type\", \"initialBalance\" = excluded.\"initialBalance\", color = excluded.color, icon = excluded.icon, \"openingDate\" = excluded.\"openingDate\", archived = excluded.archived, \"closedOn\" = excluded.\"closedOn\", \"creditLimit\" = excluded.\"creditLimit\", \"statementClosingDay\" = excluded.\"statementClosingDay\", \"paymentDueDay\" = excluded.\"paymentDueDay\", \"loanPrincipal\" = excluded.\"loanPrincipal\", \"loanRate\" = excluded.\"loanRate\", \"loanTermMonths\" = excluded.\"loanTermMonths\", \"loanStartDate\" = excluded.\"loanStartDate\", \"depreciationMethod\" = excluded.\"depreciationMethod\", \"depreciationRate\" = excluded.\"depreciationRate\""
+    )
+    .bind(&account.id)
+    .bind(&account.name)
+    .bind(&account.account_type)
+    .bind(account.initial_balance)
+    .bind(&account.color)
+    .bind(&account.icon)
+    .bind(&account.opening_date)
+    .bind(account.archived)
+    .bind(&account.closed_on)
+    .bind(account.credit_limit)
+    .bind(account.statement_closing_day)
+    .bind(account.payment_due_day)
+    .bind(account.loan_principal)
+    .bind(account.loan_rate)
+    .bind(account.loan_term_months)
+    .bind(&account.loan_start_date)
+    .bind(&account.depreciation_method)
+    .bind(account.depreciation_rate)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn upsert_transaction(conn: &mut SqliteConnection, t: &Transaction) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, category, description, status, \"clearedAt\", \"reconciledAt\", \"isTransfer\", \"linkedTransactionId\", \"payeeId\", \"reconciliationId\") VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+         ON CONFLICT(id) DO UPDATE SET date = excluded.date, \"accountId\" = excluded.\"accountId\", \"type\" = excluded.\"type\", amount = excluded.amount, category = excluded.category, description = excluded.description, status = excluded.status, \"clearedAt\" = excluded.\"clearedAt\", \"reconciledAt\" = excluded.\"reconciledAt\", \"isTransfer\" = excluded.\"isTransfer\", \"linkedTransactionId\" = excluded.\"linkedTransactionId\", \"payeeId\" = excluded.\"payeeId\", \"reconciliationId\" = excluded.\"reconciliationId\""
+    )
+    .bind(&t.id)
+    .bind(&t.date)
+    .bind(&t.account_id)
+    .bind(&t.transaction_type)
+    .bind(t.amount)
+    .bind(&t.category)
+    .bind(&t.description)
+    .bind(&t.status)
+    .bind(&t.cleared_at)
+    .bind(&t.reconciled_at)
+    .bind(t.is_transfer)
+    .bind(&t.linked_transaction_id)
+    .bind(&t.payee_id)
+    .bind(&t.reconciliation_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn upsert_category(conn: &mut SqliteConnection, category: &Category) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO categories (id, name, icon, color, \"parentId\") VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, icon = excluded.icon, color = excluded.color, \"parentId\" = excluded.\"parentId\""
+    )
+    .bind(&category.id)
+    .bind(&category.name)
+    .bind(&category.icon)
+    .bind(&category.color)
+    .bind(&category.parent_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn upsert_scheduled(conn: &mut SqliteConnection, s: &ScheduledTransaction) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO scheduled_transactions (id, description, amount, \"type\", frequency, \"accountId\", \"nextDate\", category, \"toAccountId\", \"includeInForecast\", \"endDate\", \"payeeId\") VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+         ON CONFLICT(id) DO UPDATE SET description = excluded.description, amount = excluded.amount, \"type\" = excluded.\"type\", frequency = excluded.frequency, \"accountId\" = excluded.\"accountId\", \"nextDate\" = excluded.\"nextDate\", category = excluded.category, \"toAccountId\" = excluded.\"toAccountId\", \"includeInForecast\" = excluded.\"includeInForecast\", \"endDate\" = excluded.\"endDate\", \"payeeId\" = excluded.\"payeeId\""
+    )
+    .bind(&s.id)
+    .bind(&s.description)
+    .bind(s.amount)
+    .bind(&s.transaction_type)
+    .bind(&s.frequency)
+    .bind(&s.account_id)
+    .bind(&s.next_date)
+    .bind(&s.category)
+    .bind(&s.to_account_id)
+    .bind(s.include_in_forecast)
+    .bind(&s.end_date)
+    .bind(&s.payee_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn upsert_rule(conn: &mut SqliteConnection, rule: &Rule) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO rules (id, name, position, enabled, \"descriptionContains\", \"descriptionRegex\", \"amountMin\", \"amountMax\", \"accountId\", \"type\", \"setCategory\", \"setStatus\", \"setPayee\", \"setTags\", \"stopProcessing\") VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, position = excluded.position, enabled = excluded.enabled, \"descriptionContains\" = excluded.\"descriptionContains\", \"descriptionRegex\" = excluded.\"descriptionRegex\", \"amountMin\" = excluded.\"amountMin\", \"amountMax\" = excluded.\"amountMax\", \"accountId\" = excluded.\"accountId\", \"type\" = excluded.\"type\", \"setCategory\" = excluded.\"setCategory\", \"setStatus\" = excluded.\"setStatus\", \"setPayee\" = excluded.\"setPayee\", \"setTags\" = excluded.\"setTags\", \"stopProcessing\" = excluded.\"stopProcessing\""
+    )
+    .bind(&rule.id)
+    .bind(&rule.name)
+    .bind(rule.position)
+    .bind(rule.enabled)
+    .bind(&rule.description_contains)
+    .bind(&rule.description_regex)
+    .bind(rule.amount_min)
+    .bind(rule.amount_max)
+    .bind(&rule.account_id)
+    .bind(&rule.transaction_type)
+    .bind(&rule.set_category)
+    .bind(&rule.set_status)
+    .bind(&rule.set_payee)
+    .bind(sqlx::types::Json(&rule.set_tags))
+    .bind(rule.stop_processing)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn upsert_payee(conn: &mut SqliteConnection, payee: &Payee) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO payees (id, name, aliases) VALUES ($1, $2, $3)
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, aliases = excluded.aliases"
+    )
+    .bind(&payee.id)
+    .bind(&payee.name)
+    .bind(sqlx::types::Json(&payee.aliases))
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn upsert_tag(conn: &mut SqliteConnection, tag: &Tag) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO tags (id, name, color) VALUES ($1, $2, $3)
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, color = excluded.color"
+    )
+    .bind(&tag.id)
+    .bind(&tag.name)
+    .bind(&tag.color)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn upsert_attachment(conn: &mut SqliteConnection, attachment: &Attachment) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO attachments (id, \"transactionId\", hash, \"mimeType\", \"originalName\", size, \"createdAt\") VALUES ($1, $2, $3, $4, $5, $6, $7)
+         ON CONFLICT(id) DO UPDATE SET \"transactionId\" = excluded.\"transactionId\", hash = excluded.hash, \"mimeType\" = excluded.\"mimeType\", \"originalName\" = excluded.\"originalName\", size = excluded.size, \"createdAt\" = excluded.\"createdAt\""
+    )
+    .bind(&attachment.id)
+    .bind(&attachment.transaction_id)
+    .bind(&attachment.hash)
+    .bind(&attachment.mime_type)
+    .bind(&attachment.original_name)
+    .bind(attachment.size)
+    .bind(&attachment.created_at)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn upsert_reconciliation(conn: &mut SqliteConnection, r: &Reconciliation) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO reconciliations (id, \"accountId\", \"statementDate\", \"statementBalance\", status, \"createdAt\", \"finishedAt\") VALUES ($1, $2, $3, $4, $5, $6, $7)
+         ON CONFLICT(id) DO UPDATE SET \"accountId\" = excluded.\"accountId\", \"statementDate\" = excluded.\"statementDate\", \"statementBalance\" = excluded.\"statementBalance\", status = excluded.status, \"createdAt\" = excluded.\"createdAt\", \"finishedAt\" = excluded.\"finishedAt\""
+    )
+    .bind(&r.id)
+    .bind(&r.account_id)
+    .bind(&r.statement_date)
+    .bind(r.statement_balance)
+    .bind(&r.status)
+    .bind(&r.created_at)
+    .bind(&r.finished_at)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn upsert_balance_assertion(conn: &mut SqliteConnection, a: &BalanceAssertion) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO balance_assertions (id, \"accountId\", date, balance, \"createdAt\") VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT(id) DO UPDATE SET \"accountId\" = excluded.\"accountId\", date = excluded.date, balance = excluded.balance, \"createdAt\" = excluded.\"createdAt\""
+    )
+    .bind(&a.id)
+    .bind(&a.account_id)
+    .bind(&a.date)
+    .bind(a.balance)
+    .bind(&a.created_at)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn upsert_security(conn: &mut SqliteConnection, security: &Security) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO securities (id, symbol, name) VALUES ($1, $2, $3)
+         ON CONFLICT(id) DO UPDATE SET symbol = excluded.symbol, name = excluded.name"
+    )
+    .bind(&security.id)
+    .bind(&security.symbol)
+    .bind(&security.name)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn upsert_trade(conn: &mut SqliteConnection, trade: &Trade) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO trades (id, \"accountId\", \"securityId\", date, \"type\", quantity, price, fees) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         ON CONFLICT(id) DO UPDATE SET \"accountId\" = excluded.\"accountId\", \"securityId\" = excluded.\"securityId\", date = excluded.date, \"type\" = excluded.\"type\", quantity = excluded.quantity, price = excluded.price, fees = excluded.fees"
+    )
+    .bind(&trade.id)
+    .bind(&trade.account_id)
+    .bind(&trade.security_id)
+    .bind(&trade.date)
+    .bind(&trade.trade_type)
+    .bind(trade.quantity)
+    .bind(trade.price)
+    .bind(trade.fees)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn upsert_price(conn: &mut SqliteConnection, price: &Price) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO prices (id, \"securityId\", date, price) VALUES ($1, $2, $3, $4)
+         ON CONFLICT(id) DO UPDATE SET \"securityId\" = excluded.\"securityId\", date = excluded.date, price = excluded.price"
+    )
+    .bind(&price.id)
+    .bind(&price.security_id)
+    .bind(&price.date)
+    .bind(price.price)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn upsert_valuation(conn: &mut SqliteConnection, v: &Valuation) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO valuations (id, \"accountId\", date, value) VALUES ($1, $2, $3, $4)
+         ON CONFLICT(id) DO UPDATE SET \"accountId\" = excluded.\"accountId\", date = excluded.date, value = excluded.value"
+    )
+    .bind(&v.id)
+    .bind(&v.account_id)
+    .bind(&v.date)
+    .bind(v.value)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn upsert_goal(conn: &mut SqliteConnection, g: &Goal) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO goals (id, name, \"targetAmount\", \"targetDate\", \"accountId\", \"tagId\") VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, \"targetAmount\" = excluded.\"targetAmount\", \"targetDate\" = excluded.\"targetDate\",
+         \"accountId\" = excluded.\"accountId\", \"tagId\" = excluded.\"tagId\""
+    )
+    .bind(&g.id)
+    .bind(&g.name)
+    .bind(g.target_amount)
+    .bind(&g.target_date)
+    .bind(&g.account_id)
+    .bind(&g.tag_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    fn category(name: &str) -> Category {
+        serde_json::from_value(serde_json::json!({"id": "c1", "name": name, "icon": "Tag", "color": "#fff"})).unwrap()
+    }
+
+    async fn save(conn: &mut SqliteConnection, before: Option<&Category>, after: &Category) {
+        let mut op = Operation::new("save_category");
+        upsert_category(conn, after).await.unwrap();
+        let action = if before.is_some() { audit::ACTION_UPDATE } else { audit::ACTION_CREATE };
+        audit::record(conn, &mut op, audit::ENTITY_CATEGORY, &after.id, action, before, Some(after))
+            .await
+            .unwrap();
+    }
+
+    async fn name(conn: &mut SqliteConnection) -> Option<String> {
+        audit::fetch_category(conn, "c1").await.unwrap().map(|category| category.name)
+    }
+
+    #[tokio::test]
+    async fn undo_and_redo_walk_the_operations() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        let (food, groceries) = (category("Food"), category("Groceries"));
+        save(&mut conn, None, &food).await;
+        save(&mut conn, Some(&food), &groceries).await;
+
+        assert_eq!(undo(&mut conn, 1).await.unwrap().len(), 1);
+        assert_eq!(name(&mut conn).await.as_deref(), Some("Food"));
+        assert_eq!(undo(&mut conn, 5).await.unwrap().len(), 1);
+        assert_eq!(name(&mut conn).await, None);
+
+        let redone = redo(&mut conn, 1).await.unwrap();
+        assert!(!redone[0].undone);
+        assert_eq!(name(&mut conn).await.as_deref(), Some("Food"));
+        redo(&mut conn, 1).await.unwrap();
+        assert_eq!(name(&mut conn).await.as_deref(), Some("Groceries"));
+        assert!(redo(&mut conn, 1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_new_change_drops_what_was_undone() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        let (food, groceries) = (category("Food"), category("Groceries"));
+        save(&mut conn, None, &food).await;
+        save(&mut conn, Some(&food), &groceries).await;
+        undo(&mut conn, 1).await.unwrap();
+
+        save(&mut conn, Some(&food), &category("Market")).await;
+        assert!(redo(&mut conn, 1).await.unwrap().is_empty());
+        undo(&mut conn, 1).await.unwrap();
+        assert_eq!(name(&mut conn).await.as_deref(), Some("Food"));
+    }
+}
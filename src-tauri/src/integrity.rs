@@ -0,0 +1,423 @@
+use crate::attachments;
+use crate::audit::{self, Operation};
+use crate::history;
+use crate::tags;
+use crate::models::{IntegrityIssue, IntegrityRepair, UNCATEGORIZED_CATEGORY_ID};
+use sqlx::SqliteConnection;
+
+// Issue kinds reported by check_integrity
+pub const ORPHAN_TRANSACTION: &str = "orphan_transaction";
+pub const ORPHAN_SCHEDULED: &str = "orphan_scheduled";
+pub const DANGLING_SCHEDULED_TARGET: &str = "dangling_scheduled_target";
+pub const DANGLING_TRANSFER_LINK: &str = "dangling_transfer_link";
+pub const NON_RECIPROCAL_TRANSFER: &str = "non_reciprocal_transfer";
+pub const MISSING_CATEGORY: &str = "missing_category";
+
+#[derive(sqlx::FromRow)]
+struct Row {
+    id: String,
+    reference: String,
+}
+
+async fn find(
+    conn: &mut SqliteConnection,
+    sql: &str,
+    kind: &str,
+    entity: &str,
+    message: &str,
+    issues: &mut Vec<IntegrityIssue>,
+) -> Result<(), sqlx::Error> {
+    let rows = sqlx::query_as::<_, Row>(sql)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    issues.extend(rows.into_iter().map(|row| IntegrityIssue {
+        kind: kind.to_string(),
+        entity: entity.to_string(),
+        entity_id: row.id,
+        message: format!("{message} : {}", row.reference),
+        reference: row.reference,
+    }));
+    Ok(())
+}
+
+/// Lists every dangling reference in the database.
+pub async fn check(conn: &mut SqliteConnection) -> Result<Vec<IntegrityIssue>, sqlx::Error> {
+    let mut issues = Vec::new();
+
+    find(
+        conn,
+        "SELECT t.id, t.\"accountId\" AS reference FROM transactions t
+         LEFT JOIN accounts a ON a.id = t.\"accountId\" WHERE a.id IS NULL",
+        ORPHAN_TRANSACTION,
+        audit::ENTITY_TRANSACTION,
+        "Compte introuvable",
+        &mut issues,
+    )
+    .await?;
+
+    find(
+        conn,
+        "SELECT s.id, s.\"accountId\" AS reference FROM scheduled_transactions s
+         LEFT JOIN accounts a ON a.id = s.\"accountId\" WHERE a.id IS NULL",
+        ORPHAN_SCHEDULED,
+        audit::ENTITY_SCHEDULED,
+        "Compte introuvable",
+        &mut issues,
+    )
+    .await?;
+
+    find(
+        conn,
+        "SELECT s.id, s.\"toAccountId\" AS reference FROM scheduled_transactions s
+         LEFT JOIN accounts a ON a.id = s.\"toAccountId\" WHERE s.\"toAccountId\" IS NOT NULL AND a.id IS NULL",
+        DANGLING_SCHEDULED_TARGET,
+        audit::ENTITY_SCHEDULED,
+        "Compte de destination introuvable",
+        &mut issues,
+    )
+    .await?;
+
+    find(
+        conn,
+        "SELECT t.id, t.\"linkedTransactionId\" AS reference FROM transactions t
+         LEFT JOIN transactions l ON l.id = t.\"linkedTransactionId\"
+         WHERE t.\"linkedTransactionId\" IS NOT NULL AND l.id IS NULL",
+        DANGLING_TRANSFER_LINK,
+        audit::ENTITY_TRANSACTION,
+        "Transaction liée introuvable",
+        &mut issues,
+    )
+    .await?;
+
+    find(
+        conn,
+        "SELECT t.id, t.\"linkedTransactionId\" AS reference FROM transactions t
+         JOIN transactions l ON l.id = t.\"linkedTransactionId\"
+         WHERE l.\"linkedTransactionId\" IS NULL OR l.\"linkedTransactionId\" != t.id",
+        NON_RECIPROCAL_TRANSFER,
+        audit::ENTITY_TRANSACTION,
+        "La transaction liée ne pointe pas en retour",
+        &mut issues,
+    )
+    .await?;
+
+    find(
+        conn,
+        &format!(
+            "SELECT t.id, t.category AS reference FROM transactions t
+             LEFT JOIN categories c ON c.id = t.category WHERE c.id IS NULL AND t.category != '{UNCATEGORIZED_CATEGORY_ID}'"
+        ),
+        MISSING_CATEGORY,
+        audit::ENTITY_TRANSACTION,
+        "Catégorie introuvable",
+        &mut issues,
+    )
+    .await?;
+
+    find(
+        conn,
+        &format!(
+            "SELECT s.id, s.category AS reference FROM scheduled_transactions s
+             LEFT JOIN categories c ON c.id = s.category WHERE c.id IS NULL AND s.category != '{UNCATEGORIZED_CATEGORY_ID}'"
+        ),
+        MISSING_CATEGORY,
+        audit::ENTITY_SCHEDULED,
+        "Catégorie introuvable",
+        &mut issues,
+    )
+    .await?;
+
+    Ok(issues)
+}
+
+/// Rejects a repair moving orphans to an account that is missing or closed.
+pub async fn check_target(conn: &mut SqliteConnection, repair: &IntegrityRepair) -> Result<(), String> {
+    let Some(account_id) = &repair.account_id else {
+        return Ok(());
+    };
+    let account = audit::fetch_account(conn, account_id)
+        .await
+        .map_err(|e| format!("Erreur BDD (lecture du compte): {e}"))?;
+    match account {
+        None => Err("Compte introuvable.".to_string()),
+        Some(account) if account.archived => Err("Ce compte est clôturé.".to_string()),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Applies the requested fixes and returns the issues that were repaired,
+/// leaving out those an earlier fix already took care of.
+///
+/// - orphan rows are moved to `repair.account_id` (see check_target), or deleted when
+///   no account is given; transactions dated before its opening are left as they are
+/// - scheduled transfers to a missing account lose their destination
+/// - transfer legs pointing nowhere become regular transactions
+/// - a non-reciprocal link is completed when the other leg has no link, otherwise dropped
+/// - missing categories are replaced by `repair.category_id` (uncategorized by default)
+pub async fn repair(
+    conn: &mut SqliteConnection,
+    repair: &IntegrityRepair,
+) -> Result<Vec<IntegrityIssue>, sqlx::Error> {
+    let mut op = Operation::new("repair_integrity");
+    let category_id = repair
+        .category_id
+        .clone()
+        .unwrap_or_else(|| UNCATEGORIZED_CATEGORY_ID.to_string());
+
+    let issues: Vec<IntegrityIssue> = check(conn)
+        .await?
+        .into_iter()
+        .filter(|issue| repair.kinds.contains(&issue.kind))
+        .collect();
+    let opening_date = match &repair.account_id {
+        Some(account_id) => audit::fetch_account(conn, account_id).await?.and_then(|a| a.opening_date),
+        None => None,
+    };
+
+    let mut repaired = Vec::new();
+    for issue in issues {
+        match issue.entity.as_str() {
+            audit::ENTITY_TRANSACTION => {
+                let Some(before) = audit::fetch_transaction(conn, &issue.entity_id).await? else {
+                    continue;
+                };
+                let mut after = before.clone();
+
+                match issue.kind.as_str() {
+                    ORPHAN_TRANSACTION => match &repair.account_id {
+                        Some(_) if opening_date.as_deref().is_some_and(|opening| before.date.as_str() < opening) => continue,
+                        Some(account_id) => after.account_id = account_id.clone(),
+                        None => {
+                            tags::unlink_transaction(conn, &mut op, &before.id).await?;
+                            attachments::delete_for_transaction(conn, &mut op, &before.id).await?;
+                            sqlx::query("DELETE FROM transactions WHERE id = $1")
+                                .bind(&before.id)
+                                .execute(&mut *conn)
+                                .await?;
+                            audit::record(conn, &mut op, audit::ENTITY_TRANSACTION, &before.id, audit::ACTION_DELETE, Some(&before), None)
+                                .await?;
+                            repaired.push(issue);
+                            continue;
+                        }
+                    },
+                    DANGLING_TRANSFER_LINK => {
+                        after.linked_transaction_id = None;
+                        after.is_transfer = false;
+                    }
+                    NON_RECIPROCAL_TRANSFER => {
+                        // Re-read the other leg: an earlier fix in this loop may have linked it
+                        let other = audit::fetch_transaction(conn, &issue.reference).await?;
+                        match other {
+                            Some(other) if other.linked_transaction_id.is_none() => {
+                                let mut linked = other.clone();
+                                linked.linked_transaction_id = Some(before.id.clone());
+                                linked.is_transfer = true;
+                                history::upsert_transaction(conn, &linked).await?;
+                                audit::record(conn, &mut op, audit::ENTITY_TRANSACTION, &linked.id, audit::ACTION_UPDATE, Some(&other), Some(&linked))
+                                    .await?;
+                                repaired.push(issue);
+                                continue;
+                            }
+                            Some(other) if other.linked_transaction_id.as_deref() == Some(&before.id) => continue,
+                            _ => {
+                                after.linked_transaction_id = None;
+                                after.is_transfer = false;
+                            }
+                        }
+                    }
+                    MISSING_CATEGORY => after.category = category_id.clone(),
+                    _ => continue,
+                }
+                if after == before {
+                    continue;
+                }
+
+                history::upsert_transaction(conn, &after).await?;
+                audit::record(conn, &mut op, audit::ENTITY_TRANSACTION, &after.id, audit::ACTION_UPDATE, Some(&before), Some(&after))
+                    .await?;
+                repaired.push(issue);
+            }
+            audit::ENTITY_SCHEDULED => {
+                let Some(before) = audit::fetch_scheduled(conn, &issue.entity_id).await? else {
+                    continue;
+                };
+                let mut after = before.clone();
+
+                match issue.kind.as_str() {
+                    ORPHAN_SCHEDULED => match &repair.account_id {
+                        Some(account_id) => after.account_id = account_id.clone(),
+                        None => {
+                            sqlx::query("DELETE FROM scheduled_transactions WHERE id = $1")
+                                .bind(&before.id)
+                                .execute(&mut *conn)
+                                .await?;
+                            audit::record(conn, &mut op, audit::ENTITY_SCHEDULED, &before.id, audit::ACTION_DELETE, Some(&before), None)
+                                .await?;
+                            repaired.push(issue);
+                            continue;
+                        }
+                    },
+                    DANGLING_SCHEDULED_TARGET => after.to_account_id = None,
+                    MISSING_CATEGORY => after.category = category_id.clone(),
+                    _ => continue,
+                }
+
+                history::upsert_scheduled(conn, &after).await?;
+                audit::record(conn, &mut op, audit::ENTITY_SCHEDULED, &after.id, audit::ACTION_UPDATE, Some(&before), Some(&after))
+                    .await?;
+                repaired.push(issue);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(repaired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    async fn seed(conn: &mut SqliteConnection) {
+        // Dangling references can only come from databases written without foreign keys
+        for sql in [
+            "PRAGMA foreign_keys = OFF",
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'Compte', 'checking', 0)",
+            "INSERT INTO categories (id, name, icon, color) VALUES ('c1', 'Courses', 'Tag', '#fff')",
+            "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, category) VALUES
+                ('orphan', '2026-01-10', 'gone', 'expense', 10, 'c1'),
+                ('nocat', '2026-01-10', 'a1', 'expense', 10, 'gone'),
+                ('from', '2026-01-10', 'a1', 'expense', 10, 'c1'),
+                ('to', '2026-01-10', 'a1', 'income', 10, 'c1'),
+                ('lost', '2026-01-10', 'a1', 'expense', 10, 'c1')",
+            "UPDATE transactions SET \"isTransfer\" = 1, \"linkedTransactionId\" = 'to' WHERE id = 'from'",
+            "UPDATE transactions SET \"isTransfer\" = 1, \"linkedTransactionId\" = 'nowhere' WHERE id = 'lost'",
+        ] {
+            sqlx::query(sql).execute(&mut *conn).await.unwrap();
+        }
+    }
+
+    fn kinds(issues: &[IntegrityIssue]) -> Vec<(&str, &str)> {
+        let mut kinds: Vec<_> = issues.iter().map(|issue| (issue.kind.as_str(), issue.entity_id.as_str())).collect();
+        kinds.sort();
+        kinds
+    }
+
+    #[tokio::test]
+    async fn check_lists_dangling_references() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+
+        let issues = check(&mut conn).await.unwrap();
+        assert_eq!(
+            kinds(&issues),
+            [
+                (DANGLING_TRANSFER_LINK, "lost"),
+                (MISSING_CATEGORY, "nocat"),
+                (NON_RECIPROCAL_TRANSFER, "from"),
+                (ORPHAN_TRANSACTION, "orphan"),
+            ]
+        );
+        let orphan = issues.iter().find(|issue| issue.kind == ORPHAN_TRANSACTION).unwrap();
+        assert_eq!(orphan.reference, "gone");
+    }
+
+    #[tokio::test]
+    async fn repair_fixes_only_the_requested_kinds() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+
+        let request = IntegrityRepair {
+            kinds: vec![NON_RECIPROCAL_TRANSFER.to_string(), MISSING_CATEGORY.to_string(), ORPHAN_TRANSACTION.to_string()],
+            account_id: None,
+            category_id: None,
+        };
+        let repaired = repair(&mut conn, &request).await.unwrap();
+        assert_eq!(repaired.len(), 3);
+
+        assert_eq!(kinds(&check(&mut conn).await.unwrap()), [(DANGLING_TRANSFER_LINK, "lost")]);
+        assert!(audit::fetch_transaction(&mut conn, "orphan").await.unwrap().is_none());
+        let nocat = audit::fetch_transaction(&mut conn, "nocat").await.unwrap().unwrap();
+        assert_eq!(nocat.category, UNCATEGORIZED_CATEGORY_ID);
+        let to = audit::fetch_transaction(&mut conn, "to").await.unwrap().unwrap();
+        assert_eq!((to.linked_transaction_id.as_deref(), to.is_transfer), (Some("from"), true));
+
+        // The repair is one undoable operation
+        history::undo(&mut conn, 1).await.unwrap();
+        assert_eq!(check(&mut conn).await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn orphans_move_to_the_given_account() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+
+        let request = IntegrityRepair {
+            kinds: vec![ORPHAN_TRANSACTION.to_string(), DANGLING_TRANSFER_LINK.to_string()],
+            account_id: Some("a1".to_string()),
+            category_id: None,
+        };
+        repair(&mut conn, &request).await.unwrap();
+        let orphan = audit::fetch_transaction(&mut conn, "orphan").await.unwrap().unwrap();
+        assert_eq!(orphan.account_id, "a1");
+        let lost = audit::fetch_transaction(&mut conn, "lost").await.unwrap().unwrap();
+        assert_eq!((lost.linked_transaction_id, lost.is_transfer), (None, false));
+    }
+
+    #[tokio::test]
+    async fn repair_reports_only_what_it_fixed() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+        // Deleting this orphan leaves nothing to recategorize
+        sqlx::query(
+            "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, category) VALUES ('both', '2026-01-10', 'gone', 'expense', 10, 'gone')",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+        let request = IntegrityRepair {
+            kinds: vec![ORPHAN_TRANSACTION.to_string(), MISSING_CATEGORY.to_string()],
+            account_id: None,
+            category_id: None,
+        };
+        let repaired = repair(&mut conn, &request).await.unwrap();
+        assert_eq!(
+            kinds(&repaired),
+            [(MISSING_CATEGORY, "nocat"), (ORPHAN_TRANSACTION, "both"), (ORPHAN_TRANSACTION, "orphan")]
+        );
+    }
+
+    #[tokio::test]
+    async fn orphans_only_move_to_an_open_account_of_their_time() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        seed(&mut conn).await;
+        sqlx::query(
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\", archived) VALUES ('closed', 'Ancien', 'checking', 0, 1);
+             INSERT INTO accounts (id, name, \"type\", \"initialBalance\", \"openingDate\") VALUES ('late', 'Récent', 'checking', 0, '2026-02-01')",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+        let request = |account_id: &str| IntegrityRepair {
+            kinds: vec![ORPHAN_TRANSACTION.to_string()],
+            account_id: Some(account_id.to_string()),
+            category_id: None,
+        };
+        assert!(check_target(&mut conn, &request("nowhere")).await.is_err());
+        assert!(check_target(&mut conn, &request("closed")).await.is_err());
+        check_target(&mut conn, &request("late")).await.unwrap();
+
+        assert!(repair(&mut conn, &request("late")).await.unwrap().is_empty());
+        let orphan = audit::fetch_transaction(&mut conn, "orphan").await.unwrap().unwrap();
+        assert_eq!(orphan.account_id, "gone");
+    }
+}
@@ -0,0 +1,444 @@
+use crate::balances::round_cents;
+use crate::dates::parse_date;
+use crate::models::{Holding, InvestmentSummary, Price, Security, Trade, TRADE_BUY, TRADE_SELL};
+use sqlx::SqliteConnection;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+// Quantities below this are what is left of rounding, not a position
+const QUANTITY_EPSILON: f64 = 1e-9;
+
+/// Cash a row of `trades` `tr` moves in its account: a purchase takes its
+/// cost and fees out, a sale brings its proceeds net of fees in.
+pub const TRADE_CASH: &str =
+    "(CASE WHEN tr.\"type\" = 'buy' THEN -(tr.quantity * tr.price + tr.fees) ELSE tr.quantity * tr.price - tr.fees END)";
+
+pub async fn fetch_securities(conn: &mut SqliteConnection) -> Result<Vec<Security>, sqlx::Error> {
+    sqlx::query_as::<_, Security>("SELECT * FROM securities ORDER BY symbol")
+        .fetch_all(&mut *conn)
+        .await
+}
+
+/// Trades oldest first; trades of the same day in the order they were entered.
+pub async fn fetch_trades(conn: &mut SqliteConnection, account_id: Option<&str>) -> Result<Vec<Trade>, sqlx::Error> {
+    sqlx::query_as::<_, Trade>("SELECT * FROM trades WHERE $1 IS NULL OR \"accountId\" = $1 ORDER BY date, rowid")
+        .bind(account_id)
+        .fetch_all(&mut *conn)
+        .await
+}
+
+pub async fn fetch_prices(conn: &mut SqliteConnection, security_id: Option<&str>) -> Result<Vec<Price>, sqlx::Error> {
+    sqlx::query_as::<_, Price>("SELECT * FROM prices WHERE $1 IS NULL OR \"securityId\" = $1 ORDER BY \"securityId\", date")
+        .bind(security_id)
+        .fetch_all(&mut *conn)
+        .await
+}
+
+/// Id of the price of a security on a day: there is one at most.
+pub fn price_id(security_id: &str, date: &str) -> String {
+    format!("{security_id}:{date}")
+}
+
+pub fn validate_security(security: &Security) -> Result<(), String> {
+    if security.symbol.trim().is_empty() || security.name.trim().is_empty() {
+        return Err("Le symbole et le nom du titre sont obligatoires.".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_trade(trade: &Trade) -> Result<(), String> {
+    if ![TRADE_BUY, TRADE_SELL].contains(&trade.trade_type.as_str()) {
+        return Err(format!("Type d'opération inconnu : {}", trade.trade_type));
+    }
+    if parse_date(&trade.date).is_none() {
+        return Err(format!("Date invalide : {}", trade.date));
+    }
+    if !trade.quantity.is_finite() || trade.quantity <= 0.0 {
+        return Err("La quantité doit être positive.".to_string());
+    }
+    if !trade.price.is_finite() || !trade.fees.is_finite() || trade.price < 0.0 || trade.fees < 0.0 {
+        return Err("Le prix et les frais ne peuvent pas être négatifs.".to_string());
+    }
+    Ok(())
+}
+
+/// Lots of a security still held in an account, first bought first.
+struct Position {
+    // (quantity, cost per unit fees included)
+    lots: VecDeque<(f64, f64)>,
+    realized: f64,
+    last_trade: (f64, String),
+}
+
+type Positions = BTreeMap<(String, String), Position>;
+
+/// Applies `trade` to the positions it follows. A sale of more than is held
+/// only sells what was held; returns false for such a sale.
+fn apply(positions: &mut Positions, trade: &Trade) -> bool {
+    let position = positions
+        .entry((trade.account_id.clone(), trade.security_id.clone()))
+        .or_insert_with(|| Position {
+            lots: VecDeque::new(),
+            realized: 0.0,
+            last_trade: (trade.price, trade.date.clone()),
+        });
+    position.last_trade = (trade.price, trade.date.clone());
+
+    if trade.trade_type == TRADE_BUY {
+        position
+            .lots
+            .push_back((trade.quantity, (trade.quantity * trade.price + trade.fees) / trade.quantity));
+        return true;
+    }
+
+    let mut remaining = trade.quantity;
+    let mut cost = 0.0;
+    let mut held = true;
+    while remaining > QUANTITY_EPSILON {
+        let Some(lot) = position.lots.front_mut() else {
+            held = false;
+            break;
+        };
+        let sold = lot.0.min(remaining);
+        cost += sold * lot.1;
+        lot.0 -= sold;
+        remaining -= sold;
+        if lot.0 <= QUANTITY_EPSILON {
+            position.lots.pop_front();
+        }
+    }
+    position.realized += (trade.quantity - remaining.max(0.0)) * trade.price - trade.fees - cost;
+    held
+}
+
+/// Positions by (account, security) after `trades`, oldest first, with the
+/// first sale of more than was held.
+fn replay(trades: &[Trade]) -> (Positions, Option<&Trade>) {
+    let mut positions = Positions::new();
+    let mut oversold = None;
+    for trade in trades {
+        if !apply(&mut positions, trade) {
+            oversold = oversold.or(Some(trade));
+        }
+    }
+    (positions, oversold)
+}
+
+/// Holding of a position valued at `latest`, the last known (price, date)
+/// of the security, or at its last trade price when that is older.
+fn holding((account_id, security_id): &(String, String), position: &Position, latest: Option<&(f64, String)>) -> Holding {
+    let quantity: f64 = position.lots.iter().map(|(quantity, _)| quantity).sum();
+    let cost_basis: f64 = position.lots.iter().map(|(quantity, cost)| quantity * cost).sum();
+    // A price entered before the last trade is older than the trade price
+    let (price, price_date) = match latest {
+        Some((price, date)) if *date >= position.last_trade.1 => (*price, date.clone()),
+        _ => position.last_trade.clone(),
+    };
+    let market_value = quantity * price;
+    Holding {
+        account_id: account_id.clone(),
+        security_id: security_id.clone(),
+        quantity: if quantity > QUANTITY_EPSILON { quantity } else { 0.0 },
+        cost_basis: round_cents(cost_basis),
+        price,
+        price_date,
+        market_value: round_cents(market_value),
+        unrealized_gain: round_cents(market_value - cost_basis),
+        realized_gain: round_cents(position.realized),
+    }
+}
+
+/// Rejects trades of an account that sell more of a security than it holds
+/// at the time.
+pub async fn check_quantities(conn: &mut SqliteConnection, account_id: &str) -> Result<(), String> {
+    let trades = fetch_trades(conn, Some(account_id))
+        .await
+        .map_err(|e| format!("Erreur BDD (lecture des opérations): {e}"))?;
+    match replay(&trades).1 {
+        Some(sale) => Err(format!(
+            "La vente du {} porte sur plus de titres que le compte n'en détient.",
+            sale.date
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Holdings at the end of `as_of` (every trade when not given), of one
+/// investment account or all of them. Amounts are rounded to the cent.
+pub async fn holdings(
+    conn: &mut SqliteConnection,
+    account_id: Option<&str>,
+    as_of: Option<&str>,
+) -> Result<Vec<Holding>, sqlx::Error> {
+    let trades: Vec<Trade> = fetch_trades(conn, account_id)
+        .await?
+        .into_iter()
+        .filter(|t| as_of.map_or(true, |as_of| t.date.as_str() <= as_of))
+        .collect();
+    let prices: HashMap<String, (f64, String)> = sqlx::query_as::<_, (String, f64, String)>(
+        "SELECT p.\"securityId\", p.price, p.date FROM prices p
+         WHERE p.date = (SELECT MAX(date) FROM prices WHERE \"securityId\" = p.\"securityId\" AND ($1 IS NULL OR date <= $1))",
+    )
+    .bind(as_of)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|(security_id, price, date)| (security_id, (price, date)))
+    .collect();
+
+    let (positions, _) = replay(&trades);
+    Ok(positions
+        .iter()
+        .map(|(key, position)| holding(key, position, prices.get(&key.1)))
+        .collect())
+}
+
+/// Market value of the holdings of every investment account with trades at
+/// the end of each of `dates`, in the same order. Trades and prices are read
+/// once and the trades replayed across the dates from the earliest.
+pub async fn market_values(
+    conn: &mut SqliteConnection,
+    dates: &[String],
+) -> Result<Vec<HashMap<String, f64>>, sqlx::Error> {
+    let trades = fetch_trades(conn, None).await?;
+    // Oldest first for each security
+    let mut prices: HashMap<String, Vec<(f64, String)>> = HashMap::new();
+    for price in fetch_prices(conn, None).await? {
+        prices.entry(price.security_id).or_default().push((price.price, price.date));
+    }
+
+    let mut order: Vec<usize> = (0..dates.len()).collect();
+    order.sort_by(|a, b| dates[*a].cmp(&dates[*b]));
+    let mut values = vec![HashMap::new(); dates.len()];
+    let mut positions = Positions::new();
+    let mut next = 0;
+    for index in order {
+        let date = dates[index].as_str();
+        while let Some(trade) = trades.get(next).filter(|t| t.date.as_str() <= date) {
+            apply(&mut positions, trade);
+            next += 1;
+        }
+        let holdings: Vec<Holding> = positions
+            .iter()
+            .map(|(key, position)| {
+                let known = prices.get(&key.1).map_or(&[][..], Vec::as_slice);
+                let latest = known[..known.partition_point(|(_, d)| d.as_str() <= date)].last();
+                holding(key, position, latest)
+            })
+            .collect();
+        values[index] = summaries(&holdings)
+            .into_iter()
+            .map(|s| (s.account_id, s.market_value))
+            .collect();
+    }
+    Ok(values)
+}
+
+/// Cash moved by the trades of every investment account with trades up to the
+/// end of `as_of` (every trade when not given). Trades don't move the balance
+/// of their account, so this is what is left to add to it besides holdings.
+pub async fn trade_cash(conn: &mut SqliteConnection, as_of: Option<&str>) -> Result<HashMap<String, f64>, sqlx::Error> {
+    let rows: Vec<(String, f64)> = sqlx::query_as(&format!(
+        "SELECT tr.\"accountId\", TOTAL({TRADE_CASH}) FROM trades tr
+         WHERE $1 IS NULL OR tr.date <= $1
+         GROUP BY tr.\"accountId\""
+    ))
+    .bind(as_of)
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Holdings added up by account.
+pub fn summaries(holdings: &[Holding]) -> Vec<InvestmentSummary> {
+    let mut by_account: BTreeMap<&str, InvestmentSummary> = BTreeMap::new();
+    for holding in holdings {
+        let summary = by_account
+            .entry(&holding.account_id)
+            .or_insert_with(|| InvestmentSummary {
+                account_id: holding.account_id.clone(),
+                ..Default::default()
+            });
+        summary.market_value += holding.market_value;
+        summary.cost_basis += holding.cost_basis;
+        summary.realized_gain += holding.realized_gain;
+        summary.unrealized_gain += holding.unrealized_gain;
+    }
+    by_account
+        .into_values()
+        .map(|s| InvestmentSummary {
+            market_value: round_cents(s.market_value),
+            cost_basis: round_cents(s.cost_basis),
+            realized_gain: round_cents(s.realized_gain),
+            unrealized_gain: round_cents(s.unrealized_gain),
+            ..s
+        })
+        .collect()
+}
+
+/// Prices from a CSV file with one `symbol,date,price` line per price (or
+/// `;`-separated with decimal commas), dates as YYYY-MM-DD. A header line
+/// is skipped.
+pub fn parse_prices_csv(content: &str) -> Result<Vec<(String, String, f64)>, String> {
+    let lines: Vec<(usize, &str)> = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .collect();
+    let delimiter = if lines.first().is_some_and(|(_, line)| line.contains(';')) { ';' } else { ',' };
+
+    let mut prices = Vec::new();
+    for (position, (number, line)) in lines.iter().enumerate() {
+        let fields: Vec<&str> = line.split(delimiter).map(|f| f.trim().trim_matches('"')).collect();
+        let parsed = match fields.as_slice() {
+            [symbol, date, price] if !symbol.is_empty() && parse_date(date).is_some() => {
+                let price = if delimiter == ';' { price.replace(',', ".") } else { price.to_string() };
+                price.parse::<f64>().ok().filter(|p| p.is_finite() && *p >= 0.0).map(|p| (symbol.to_string(), date.to_string(), p))
+            }
+            _ => None,
+        };
+        match parsed {
+            Some(price) => prices.push(price),
+            None if position == 0 => continue,
+            None => return Err(format!("Ligne {number} invalide : {line}")),
+        }
+    }
+    Ok(prices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    fn trade(id: &str, account_id: &str, trade_type: &str, quantity: f64, price: f64, fees: f64) -> Trade {
+        Trade {
+            id: id.to_string(),
+            account_id: account_id.to_string(),
+            security_id: "s1".to_string(),
+            date: "2026-01-10".to_string(),
+            trade_type: trade_type.to_string(),
+            quantity,
+            price,
+            fees,
+        }
+    }
+
+    fn key(account_id: &str) -> (String, String) {
+        (account_id.to_string(), "s1".to_string())
+    }
+
+    #[test]
+    fn sales_consume_the_oldest_lots_first() {
+        let trades = [
+            trade("t1", "a1", TRADE_BUY, 10.0, 10.0, 0.0),
+            trade("t2", "a1", TRADE_BUY, 10.0, 20.0, 0.0),
+            trade("t3", "a1", TRADE_SELL, 15.0, 30.0, 5.0),
+        ];
+        let (positions, oversold) = replay(&trades);
+        assert!(oversold.is_none());
+        let position = &positions[&key("a1")];
+        assert_eq!(position.lots, [(5.0, 20.0)]);
+        assert_eq!(position.realized, 15.0 * 30.0 - 5.0 - (10.0 * 10.0 + 5.0 * 20.0));
+    }
+
+    #[test]
+    fn partial_sale_keeps_the_rest_of_the_lot_at_its_cost() {
+        let trades = [
+            trade("t1", "a1", TRADE_BUY, 4.0, 10.0, 2.0),
+            trade("t2", "a1", TRADE_SELL, 1.0, 12.0, 0.0),
+        ];
+        let (positions, _) = replay(&trades);
+        let position = &positions[&key("a1")];
+        assert_eq!(position.lots, [(3.0, 10.5)]);
+        assert_eq!(position.realized, 1.5);
+        assert_eq!(position.last_trade, (12.0, "2026-01-10".to_string()));
+    }
+
+    #[test]
+    fn oversale_only_sells_what_was_held() {
+        let trades = [
+            trade("t1", "a1", TRADE_BUY, 5.0, 10.0, 0.0),
+            trade("t2", "a1", TRADE_SELL, 8.0, 12.0, 1.0),
+            trade("t3", "a1", TRADE_SELL, 1.0, 12.0, 0.0),
+        ];
+        let (positions, oversold) = replay(&trades);
+        assert_eq!(oversold.map(|trade| trade.id.as_str()), Some("t2"));
+        let position = &positions[&key("a1")];
+        assert!(position.lots.is_empty());
+        assert_eq!(position.realized, 5.0 * 12.0 - 1.0 - 5.0 * 10.0);
+    }
+
+    #[test]
+    fn accounts_hold_separate_positions() {
+        let trades = [
+            trade("t1", "a1", TRADE_BUY, 5.0, 10.0, 0.0),
+            trade("t2", "a2", TRADE_SELL, 1.0, 10.0, 0.0),
+        ];
+        let (positions, oversold) = replay(&trades);
+        assert_eq!(oversold.map(|trade| trade.id.as_str()), Some("t2"));
+        assert_eq!(positions[&key("a1")].lots, [(5.0, 10.0)]);
+    }
+
+    #[test]
+    fn validate_trade_rejects_impossible_trades() {
+        assert!(validate_trade(&trade("t1", "a1", TRADE_BUY, 1.0, 10.0, 0.0)).is_ok());
+        assert!(validate_trade(&trade("t1", "a1", "dividend", 1.0, 10.0, 0.0)).is_err());
+        assert!(validate_trade(&trade("t1", "a1", TRADE_BUY, 0.0, 10.0, 0.0)).is_err());
+        assert!(validate_trade(&trade("t1", "a1", TRADE_SELL, 1.0, -1.0, 0.0)).is_err());
+        assert!(validate_trade(&trade("t1", "a1", TRADE_BUY, f64::NAN, 10.0, 0.0)).is_err());
+        assert!(validate_trade(&trade("t1", "a1", TRADE_BUY, 1.0, f64::INFINITY, 0.0)).is_err());
+        assert!(validate_trade(&trade("t1", "a1", TRADE_BUY, 1.0, 10.0, f64::NAN)).is_err());
+        assert!(validate_trade(&Trade {
+            date: "2026-02-30".to_string(),
+            ..trade("t1", "a1", TRADE_BUY, 1.0, 10.0, 0.0)
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn prices_csv_skips_the_header() {
+        let prices = parse_prices_csv("symbol,date,price\nCW8,2026-01-10,512.3\n\nESE,2026-01-10,24\n").unwrap();
+        assert_eq!(
+            prices,
+            [
+                ("CW8".to_string(), "2026-01-10".to_string(), 512.3),
+                ("ESE".to_string(), "2026-01-10".to_string(), 24.0)
+            ]
+        );
+        let french = parse_prices_csv("\"CW8\";2026-01-10;\"512,30\"").unwrap();
+        assert_eq!(french, [("CW8".to_string(), "2026-01-10".to_string(), 512.3)]);
+        assert_eq!(
+            parse_prices_csv("CW8,2026-01-10,512.3\nESE,2026-13-10,24"),
+            Err("Ligne 2 invalide : ESE,2026-13-10,24".to_string())
+        );
+        assert!(parse_prices_csv("CW8,2026-01-10,512.3\nESE,2026-01-10,inf").is_err());
+    }
+
+    #[tokio::test]
+    async fn market_values_match_the_holdings_of_each_date() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        sqlx::query(
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'PEA', 'investment', 0);
+             INSERT INTO securities (id, symbol, name) VALUES ('s1', 'CW8', 'MSCI World');
+             INSERT INTO trades (id, \"accountId\", \"securityId\", date, \"type\", quantity, price) VALUES
+             ('t1', 'a1', 's1', '2026-01-10', 'buy', 10, 100), ('t2', 'a1', 's1', '2026-03-10', 'sell', 4, 120);
+             INSERT INTO prices (id, \"securityId\", date, price) VALUES ('s1:2026-02-01', 's1', '2026-02-01', 110)",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+        let dates = ["2026-03-31", "2026-01-05", "2026-02-15", "2026-01-10"].map(str::to_string);
+        let values = market_values(&mut conn, &dates).await.unwrap();
+        for (date, value) in dates.iter().zip(&values) {
+            let holdings = holdings(&mut conn, None, Some(date)).await.unwrap();
+            let expected: HashMap<String, f64> =
+                summaries(&holdings).into_iter().map(|s| (s.account_id, s.market_value)).collect();
+            assert_eq!(value, &expected, "{date}");
+        }
+        let a1: Vec<Option<f64>> = values.iter().map(|v| v.get("a1").copied()).collect();
+        assert_eq!(a1, [Some(720.0), None, Some(1100.0), Some(1000.0)]);
+    }
+}
@@ -1,6 +1,24 @@
+mod accounts;
+mod assets;
+mod attachments;
+mod audit;
+mod balances;
+mod cards;
 mod commands;
+mod dates;
 mod db;
+mod goals;
+mod history;
+mod integrity;
+mod investments;
+mod loans;
 mod models;
+mod payees;
+mod reconciliation;
+mod reports;
+mod rules;
+mod suggestions;
+mod tags;
 
 use tauri::{Manager, WebviewWindowBuilder};
 
@@ -12,6 +30,7 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_os::init())
+        .plugin(tauri_plugin_opener::init())
         .setup(|app| {
             app.handle().plugin(
                 tauri_plugin_log::Builder::default()
@@ -49,22 +68,107 @@ pub fn run() {
             commands::get_accounts,
             commands::add_account,
             commands::update_account,
+            commands::rebase_opening_balance,
+            commands::close_account,
+            commands::reopen_account,
             commands::delete_account,
+            commands::get_account_balances,
+            commands::get_running_balance,
+            commands::get_status_balances,
+            commands::get_net_worth,
+            commands::get_net_worth_history,
             commands::get_transactions,
             commands::add_transaction,
+            commands::add_transfer,
             commands::update_transaction,
             commands::delete_transaction,
+            commands::bulk_update_transactions,
+            commands::bulk_delete_transactions,
+            commands::get_reconciliations,
+            commands::start_reconciliation,
+            commands::get_reconciliation_status,
+            commands::set_transactions_cleared,
+            commands::finish_reconciliation,
+            commands::cancel_reconciliation,
+            commands::get_balance_assertions,
+            commands::add_balance_assertion,
+            commands::delete_balance_assertion,
+            commands::verify_balances,
+            commands::get_card_statement,
+            commands::schedule_card_payment,
+            commands::get_amortization_schedule,
+            commands::get_loan_projection,
+            commands::schedule_loan_payment,
+            commands::record_loan_payment,
+            commands::get_valuations,
+            commands::add_valuation,
+            commands::delete_valuation,
+            commands::get_asset_values,
+            commands::get_goals,
+            commands::add_goal,
+            commands::update_goal,
+            commands::delete_goal,
+            commands::get_goal_progress,
+            commands::get_securities,
+            commands::add_security,
+            commands::update_security,
+            commands::delete_security,
+            commands::get_trades,
+            commands::add_trade,
+            commands::delete_trade,
+            commands::get_prices,
+            commands::set_price,
+            commands::delete_price,
+            commands::import_prices,
+            commands::get_holdings,
+            commands::get_investment_summaries,
             commands::get_categories,
             commands::add_category,
             commands::update_category,
             commands::delete_category,
+            commands::merge_categories,
+            commands::move_category,
+            commands::get_category_totals,
+            commands::suggest_category,
+            commands::suggest_categories,
             commands::get_scheduled,
             commands::add_scheduled,
             commands::update_scheduled,
             commands::delete_scheduled,
+            commands::get_payees,
+            commands::add_payee,
+            commands::update_payee,
+            commands::delete_payee,
+            commands::merge_payees,
+            commands::assign_payees,
+            commands::get_payee_totals,
+            commands::get_rules,
+            commands::add_rule,
+            commands::update_rule,
+            commands::delete_rule,
+            commands::reorder_rules,
+            commands::apply_rules,
+            commands::get_tags,
+            commands::add_tag,
+            commands::update_tag,
+            commands::delete_tag,
+            commands::get_transaction_tags,
+            commands::tag_transactions,
+            commands::untag_transactions,
+            commands::get_attachments,
+            commands::add_attachment,
+            commands::open_attachment,
+            commands::delete_attachment,
+            commands::gc_attachments,
             commands::import_data,
             commands::get_settings,
-            commands::save_settings
+            commands::save_settings,
+            commands::get_audit_log,
+            commands::undo,
+            commands::redo,
+            commands::get_history,
+            commands::check_integrity,
+            commands::repair_integrity
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
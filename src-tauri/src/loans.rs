@@ -0,0 +1,164 @@
+use crate::balances::{balance_at, round_cents};
+use crate::dates::{day_of_month, format_date, parse_date, Date};
+use crate::models::{Account, AmortizationRow};
+use sqlx::SqliteConnection;
+
+/// Longest loan term accepted, a hundred years: schedules are built
+/// payment by payment.
+pub const MAX_TERM_MONTHS: u32 = 1200;
+
+/// Terms of a loan account, once validated.
+pub struct LoanTerms {
+    pub principal: f64,
+    // Monthly rate, as a fraction
+    pub rate: f64,
+    pub term_months: u32,
+    pub start: Date,
+}
+
+impl LoanTerms {
+    pub fn of(account: &Account) -> Option<LoanTerms> {
+        Some(LoanTerms {
+            principal: account.loan_principal?,
+            rate: account.loan_rate? / 100.0 / 12.0,
+            term_months: account.loan_term_months.filter(|term| (1..=MAX_TERM_MONTHS).contains(term))?,
+            start: parse_date(account.loan_start_date.as_deref()?)?,
+        })
+    }
+
+    /// Constant monthly payment repaying the principal over the term.
+    pub fn payment(&self) -> f64 {
+        let n = self.term_months as f64;
+        if self.rate == 0.0 {
+            round_cents(self.principal / n)
+        } else {
+            round_cents(self.principal * self.rate / (1.0 - (1.0 + self.rate).powf(-n)))
+        }
+    }
+
+    pub fn payment_date(&self, number: u32) -> String {
+        format_date(day_of_month(self.start, number as i32 - 1, self.start.2))
+    }
+
+    pub fn last_payment_date(&self) -> String {
+        self.payment_date(self.term_months)
+    }
+}
+
+/// Splits a payment on `owed` into interest and principal; the last one
+/// repays whatever is left so that rounding never leaves a few cents owed.
+fn split(terms: &LoanTerms, number: u32, owed: f64, last: bool) -> AmortizationRow {
+    let interest = round_cents(owed * terms.rate);
+    let principal = if last {
+        owed
+    } else {
+        round_cents((terms.payment() - interest).min(owed))
+    };
+    AmortizationRow {
+        number,
+        date: terms.payment_date(number),
+        payment: round_cents(interest + principal),
+        interest,
+        principal,
+        balance: round_cents(owed - principal),
+    }
+}
+
+/// Amortization schedule of the loan from its terms.
+pub fn schedule(terms: &LoanTerms) -> Vec<AmortizationRow> {
+    let mut owed = terms.principal;
+    let mut rows = Vec::with_capacity(terms.term_months as usize);
+    for number in 1..=terms.term_months {
+        let row = split(terms, number, owed, number == terms.term_months);
+        owed = row.balance;
+        rows.push(row);
+        if owed <= 0.0 {
+            break;
+        }
+    }
+    rows
+}
+
+/// Payments left after `as_of` (YYYY-MM-DD), computed from what is actually
+/// owed on the account at that date rather than from the initial schedule,
+/// so early repayments and missed payments show up.
+pub async fn projection(
+    conn: &mut SqliteConnection,
+    loan: &Account,
+    terms: &LoanTerms,
+    as_of: &str,
+) -> Result<Vec<AmortizationRow>, String> {
+    if parse_date(as_of).is_none() {
+        return Err(format!("Date invalide : {as_of}"));
+    }
+    let mut owed = -balance_at(conn, loan, as_of)
+        .await
+        .map_err(|e| format!("Erreur BDD (projection du prêt): {e}"))?;
+
+    let mut rows = Vec::new();
+    for number in 1..=terms.term_months {
+        if owed <= 0.0 {
+            break;
+        }
+        if terms.payment_date(number).as_str() <= as_of {
+            continue;
+        }
+        let row = split(terms, number, round_cents(owed), number == terms.term_months);
+        owed = row.balance;
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(principal: f64, yearly_rate: f64, term_months: u32, start: Date) -> LoanTerms {
+        LoanTerms {
+            principal,
+            rate: yearly_rate / 100.0 / 12.0,
+            term_months,
+            start,
+        }
+    }
+
+    #[test]
+    fn zero_rate_splits_principal_evenly() {
+        let rows = schedule(&terms(1000.0, 0.0, 3, (2026, 1, 15)));
+        let payments: Vec<f64> = rows.iter().map(|row| row.payment).collect();
+        assert_eq!(payments, [333.33, 333.33, 333.34]);
+        assert!(rows.iter().all(|row| row.interest == 0.0));
+        assert_eq!(rows[2].balance, 0.0);
+    }
+
+    #[test]
+    fn last_payment_repays_the_remainder() {
+        let terms = terms(10_000.0, 12.0, 12, (2026, 1, 15));
+        assert_eq!(terms.payment(), 888.49);
+        let rows = schedule(&terms);
+        assert_eq!(rows.len(), 12);
+        assert_eq!((rows[0].interest, rows[0].principal, rows[0].balance), (100.0, 788.49, 9211.51));
+        let last = &rows[11];
+        assert_eq!(last.principal, rows[10].balance);
+        assert_eq!(last.balance, 0.0);
+        assert_eq!(last.payment, round_cents(last.interest + last.principal));
+        let repaid: f64 = rows.iter().map(|row| row.principal).sum();
+        assert_eq!(round_cents(repaid), 10_000.0);
+    }
+
+    #[test]
+    fn split_never_repays_more_than_owed() {
+        let terms = terms(1000.0, 0.0, 3, (2026, 1, 15));
+        let row = split(&terms, 2, 100.0, false);
+        assert_eq!((row.principal, row.payment, row.balance), (100.0, 100.0, 0.0));
+    }
+
+    #[test]
+    fn payment_dates_clamp_to_month_end() {
+        let terms = terms(1000.0, 0.0, 4, (2026, 1, 31));
+        let dates: Vec<String> = schedule(&terms).into_iter().map(|row| row.date).collect();
+        assert_eq!(dates, ["2026-01-31", "2026-02-28", "2026-03-31", "2026-04-30"]);
+        assert_eq!(terms.last_payment_date(), "2026-04-30");
+    }
+}
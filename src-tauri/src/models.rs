@@ -1,10 +1,11 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Account {
     pub id: String,
     pub name: String,
+    // One of the KIND_* constants
     #[sqlx(rename = "type")]
     #[serde(rename = "type")]
     pub account_type: String,
@@ -16,6 +17,87 @@ pub struct Account {
     pub color: String,
     #[serde(default = "default_icon")]
     pub icon: String,
+    // YYYY-MM-DD the initial balance applies at; earlier transactions are
+    // history it already includes and don't count towards the balance
+    #[sqlx(rename = "openingDate")]
+    #[serde(rename = "openingDate", default)]
+    pub opening_date: Option<String>,
+    // Closed accounts are archived: kept with their history but left out of
+    // the account lists unless asked for
+    #[serde(default)]
+    pub archived: bool,
+    #[sqlx(rename = "closedOn")]
+    #[serde(rename = "closedOn", default)]
+    pub closed_on: Option<String>,
+    // Credit cards only
+    #[sqlx(rename = "creditLimit")]
+    #[serde(rename = "creditLimit", default)]
+    pub credit_limit: Option<f64>,
+    // Days of the month (1-31, the last day for shorter months) a credit
+    // card statement closes and its payment is due
+    #[sqlx(rename = "statementClosingDay")]
+    #[serde(rename = "statementClosingDay", default)]
+    pub statement_closing_day: Option<u32>,
+    #[sqlx(rename = "paymentDueDay")]
+    #[serde(rename = "paymentDueDay", default)]
+    pub payment_due_day: Option<u32>,
+    // Loans only: amount borrowed, yearly rate in percent, number of monthly
+    // payments and date of the first one
+    #[sqlx(rename = "loanPrincipal")]
+    #[serde(rename = "loanPrincipal", default)]
+    pub loan_principal: Option<f64>,
+    #[sqlx(rename = "loanRate")]
+    #[serde(rename = "loanRate", default)]
+    pub loan_rate: Option<f64>,
+    #[sqlx(rename = "loanTermMonths")]
+    #[serde(rename = "loanTermMonths", default)]
+    pub loan_term_months: Option<u32>,
+    #[sqlx(rename = "loanStartDate")]
+    #[serde(rename = "loanStartDate", default)]
+    pub loan_start_date: Option<String>,
+    // Assets only: one of the DEPRECIATION_* methods and its yearly rate,
+    // applied from the last valuation
+    #[sqlx(rename = "depreciationMethod")]
+    #[serde(rename = "depreciationMethod", default)]
+    pub depreciation_method: Option<String>,
+    #[sqlx(rename = "depreciationRate")]
+    #[serde(rename = "depreciationRate", default)]
+    pub depreciation_rate: Option<f64>,
+}
+
+// Depreciation methods of asset accounts
+/// Loses the rate, an amount, every year down to 0.
+pub const DEPRECIATION_STRAIGHT_LINE: &str = "straight_line";
+/// Loses the rate, a percentage of its value, every year.
+pub const DEPRECIATION_PERCENTAGE: &str = "percentage";
+
+// Account kinds. Balances are signed the same way for every kind: money
+// owed on a liability is a negative balance.
+pub const KIND_CHECKING: &str = "checking";
+pub const KIND_SAVINGS: &str = "savings";
+pub const KIND_CREDIT_CARD: &str = "credit_card";
+pub const KIND_CASH: &str = "cash";
+pub const KIND_LOAN: &str = "loan";
+pub const KIND_INVESTMENT: &str = "investment";
+/// Anything else owned, e.g. a house or a car.
+pub const KIND_ASSET: &str = "asset";
+/// Anything else owed.
+pub const KIND_LIABILITY: &str = "liability";
+
+pub const ACCOUNT_KINDS: [&str; 8] = [
+    KIND_CHECKING,
+    KIND_SAVINGS,
+    KIND_CREDIT_CARD,
+    KIND_CASH,
+    KIND_LOAN,
+    KIND_INVESTMENT,
+    KIND_ASSET,
+    KIND_LIABILITY,
+];
+
+/// Kinds whose balance is money owed, subtracted from the net worth.
+pub fn is_liability(kind: &str) -> bool {
+    [KIND_CREDIT_CARD, KIND_LOAN, KIND_LIABILITY].contains(&kind)
 }
 
 fn default_balance() -> f64 {
@@ -30,7 +112,7 @@ fn default_icon() -> String {
     "Wallet".to_string()
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
 pub struct Transaction {
     pub id: String,
     pub date: String,
@@ -44,25 +126,62 @@ pub struct Transaction {
     pub category: String,
     #[serde(default)]
     pub description: Option<String>,
-    #[serde(default)]
-    pub checked: bool,
+    // One of the STATUS_* constants
+    #[serde(default = "default_status")]
+    pub status: String,
+    #[sqlx(rename = "clearedAt")]
+    #[serde(rename = "clearedAt", default)]
+    pub cleared_at: Option<String>,
+    #[sqlx(rename = "reconciledAt")]
+    #[serde(rename = "reconciledAt", default)]
+    pub reconciled_at: Option<String>,
     #[sqlx(rename = "isTransfer")]
     #[serde(rename = "isTransfer", default)]
     pub is_transfer: bool,
     #[sqlx(rename = "linkedTransactionId")]
     #[serde(rename = "linkedTransactionId", default)]
     pub linked_transaction_id: Option<String>,
+    #[sqlx(rename = "payeeId")]
+    #[serde(rename = "payeeId", default)]
+    pub payee_id: Option<String>,
+    // Reconciliation that locked the transaction
+    #[sqlx(rename = "reconciliationId")]
+    #[serde(rename = "reconciliationId", default)]
+    pub reconciliation_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+fn default_status() -> String {
+    STATUS_PENDING.to_string()
+}
+
+// Transaction statuses
+/// Entered but not yet seen on the bank side.
+pub const STATUS_PENDING: &str = "pending";
+/// Seen on the bank side, e.g. on the bank website.
+pub const STATUS_CLEARED: &str = "cleared";
+/// Matched against a statement; only a reconciliation sets it and changing
+/// such a transaction needs confirmation.
+pub const STATUS_RECONCILED: &str = "reconciled";
+
+// System categories, always present in the categories table
+/// Default category of transactions and scheduled transactions; rows whose
+/// category is deleted fall back to it.
+pub const UNCATEGORIZED_CATEGORY_ID: &str = "uncategorized";
+/// Category of both legs of a transfer.
+pub const TRANSFER_CATEGORY_ID: &str = "transfer";
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Category {
     pub id: String,
     pub name: String,
     pub icon: String,
     pub color: String,
+    #[sqlx(rename = "parentId")]
+    #[serde(rename = "parentId", default)]
+    pub parent_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct ScheduledTransaction {
     pub id: String,
     pub description: String,
@@ -87,14 +206,286 @@ pub struct ScheduledTransaction {
     #[sqlx(rename = "endDate")]
     #[serde(rename = "endDate")]
     pub end_date: Option<String>,
+    #[sqlx(rename = "payeeId")]
+    #[serde(rename = "payeeId", default)]
+    pub payee_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppData {
     pub accounts: Vec<Account>,
     pub transactions: Vec<Transaction>,
     pub categories: Vec<Category>,
     pub scheduled: Vec<ScheduledTransaction>,
+    // Older backups have no rules or payees: the current ones are kept on import
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rules: Option<Vec<Rule>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payees: Option<Vec<Payee>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<Tag>>,
+    #[serde(rename = "transactionTags", default, skip_serializing_if = "Option::is_none")]
+    pub transaction_tags: Option<Vec<TransactionTag>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reconciliations: Option<Vec<Reconciliation>>,
+    #[serde(rename = "balanceAssertions", default, skip_serializing_if = "Option::is_none")]
+    pub balance_assertions: Option<Vec<BalanceAssertion>>,
+    // Metadata only: the files stay in the attachments folder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Vec<Attachment>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub securities: Option<Vec<Security>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trades: Option<Vec<Trade>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prices: Option<Vec<Price>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valuations: Option<Vec<Valuation>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goals: Option<Vec<Goal>>,
+}
+
+/// File joined to a transaction. The content is stored once per SHA-256
+/// hash in the attachments folder of the app data dir.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Attachment {
+    pub id: String,
+    #[sqlx(rename = "transactionId")]
+    #[serde(rename = "transactionId")]
+    pub transaction_id: String,
+    pub hash: String,
+    #[sqlx(rename = "mimeType")]
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+    #[sqlx(rename = "originalName")]
+    #[serde(rename = "originalName")]
+    pub original_name: String,
+    pub size: i64,
+    #[sqlx(rename = "createdAt")]
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+/// Comparison of an account with a bank statement. While open, transactions
+/// are marked cleared until the cleared balance matches the statement;
+/// finishing locks them.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Reconciliation {
+    pub id: String,
+    #[sqlx(rename = "accountId")]
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    #[sqlx(rename = "statementDate")]
+    #[serde(rename = "statementDate")]
+    pub statement_date: String,
+    #[sqlx(rename = "statementBalance")]
+    #[serde(rename = "statementBalance")]
+    pub statement_balance: f64,
+    // "open" or "finished"
+    pub status: String,
+    #[sqlx(rename = "createdAt")]
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[sqlx(rename = "finishedAt")]
+    #[serde(rename = "finishedAt", default)]
+    pub finished_at: Option<String>,
+}
+
+/// Balance the bank reported for an account at the end of a day.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct BalanceAssertion {
+    pub id: String,
+    #[sqlx(rename = "accountId")]
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    pub date: String,
+    pub balance: f64,
+    #[sqlx(rename = "createdAt")]
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BalanceCheck {
+    pub assertion: BalanceAssertion,
+    // Balance computed from the transactions at the end of the day
+    pub computed: f64,
+    // Asserted minus computed balance; 0 when they agree
+    pub discrepancy: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReconciliationStatus {
+    pub reconciliation: Reconciliation,
+    // Opening balance plus cleared transactions up to the statement date
+    #[serde(rename = "clearedBalance")]
+    pub cleared_balance: f64,
+    // Statement balance minus cleared balance; 0 when it can be finished
+    pub difference: f64,
+    // Cleared but not yet reconciled, up to the statement date
+    #[serde(rename = "clearedCount")]
+    pub cleared_count: i64,
+    // Not cleared, up to the statement date
+    #[serde(rename = "unclearedCount")]
+    pub uncleared_count: i64,
+}
+
+/// Transfer of the remaining balance made by close_account. The ids of the
+/// two legs come from the caller, like those of add_transfer.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClosingTransfer {
+    #[serde(rename = "toAccountId")]
+    pub to_account_id: String,
+    // Leg in the closed account
+    #[serde(rename = "transactionId")]
+    pub transaction_id: String,
+    // Leg in the receiving account
+    #[serde(rename = "linkedTransactionId")]
+    pub linked_transaction_id: String,
+}
+
+/// Changes made by bulk_update_transactions; fields left out are not touched.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TransactionBulkUpdate {
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(rename = "accountId", default)]
+    pub account_id: Option<String>,
+    // Pending or cleared
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(rename = "addTagIds", default)]
+    pub add_tag_ids: Vec<String>,
+    #[serde(rename = "removeTagIds", default)]
+    pub remove_tag_ids: Vec<String>,
+    // Plain, case-sensitive text replaced in descriptions
+    #[serde(rename = "descriptionFind", default)]
+    pub description_find: Option<String>,
+    #[serde(rename = "descriptionReplace", default)]
+    pub description_replace: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BulkUpdateResult {
+    // Transactions selected
+    pub matched: u64,
+    // Transactions whose fields or tags changed
+    pub updated: u64,
+    #[serde(rename = "tagsAdded")]
+    pub tags_added: u64,
+    #[serde(rename = "tagsRemoved")]
+    pub tags_removed: u64,
+}
+
+/// Cross-cutting label; names are unique regardless of case.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Tag {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct TransactionTag {
+    #[sqlx(rename = "transactionId")]
+    #[serde(rename = "transactionId")]
+    pub transaction_id: String,
+    #[sqlx(rename = "tagId")]
+    #[serde(rename = "tagId")]
+    pub tag_id: String,
+}
+
+/// Filters of get_transactions. Dates are YYYY-MM-DD, both inclusive.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TransactionFilter {
+    #[serde(rename = "accountId", default)]
+    pub account_id: Option<String>,
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+    // Transactions carrying any of these tags, or all of them with matchAllTags
+    #[serde(rename = "tagIds", default)]
+    pub tag_ids: Vec<String>,
+    #[serde(rename = "matchAllTags", default)]
+    pub match_all_tags: bool,
+}
+
+/// Counterparty of transactions. Descriptions are matched against the name
+/// and the aliases: plain text is searched case-insensitively, `/.../` is a
+/// regular expression.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Payee {
+    pub id: String,
+    pub name: String,
+    #[sqlx(json)]
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct PayeeTotal {
+    #[sqlx(rename = "payeeId")]
+    #[serde(rename = "payeeId")]
+    pub payee_id: String,
+    pub income: f64,
+    pub expense: f64,
+    pub count: i64,
+}
+
+/// Auto-categorization rule. Every condition that is set must match; rules
+/// are evaluated by ascending position and all matching rules apply, later
+/// ones overriding earlier ones, until one has `stopProcessing`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Rule {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub position: i64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Conditions
+    #[sqlx(rename = "descriptionContains")]
+    #[serde(rename = "descriptionContains", default)]
+    pub description_contains: Option<String>,
+    #[sqlx(rename = "descriptionRegex")]
+    #[serde(rename = "descriptionRegex", default)]
+    pub description_regex: Option<String>,
+    #[sqlx(rename = "amountMin")]
+    #[serde(rename = "amountMin", default)]
+    pub amount_min: Option<f64>,
+    #[sqlx(rename = "amountMax")]
+    #[serde(rename = "amountMax", default)]
+    pub amount_max: Option<f64>,
+    #[sqlx(rename = "accountId")]
+    #[serde(rename = "accountId", default)]
+    pub account_id: Option<String>,
+    #[sqlx(rename = "type")]
+    #[serde(rename = "type", default)]
+    pub transaction_type: Option<String>,
+    // Actions
+    #[sqlx(rename = "setCategory")]
+    #[serde(rename = "setCategory", default)]
+    pub set_category: Option<String>,
+    // Pending or cleared
+    #[sqlx(rename = "setStatus")]
+    #[serde(rename = "setStatus", default)]
+    pub set_status: Option<String>,
+    #[sqlx(rename = "setPayee")]
+    #[serde(rename = "setPayee", default)]
+    pub set_payee: Option<String>,
+    // Tags added to the transaction
+    #[sqlx(rename = "setTags", json)]
+    #[serde(rename = "setTags", default)]
+    pub set_tags: Vec<String>,
+    #[sqlx(rename = "stopProcessing")]
+    #[serde(rename = "stopProcessing", default)]
+    pub stop_processing: bool,
+}
+
+fn default_enabled() -> bool {
+    true
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -135,3 +526,411 @@ pub struct Settings {
     #[serde(rename = "componentPadding")]
     pub component_padding: i32,
 }
+
+/// Row of the settings table, where the window geometry is stored flat.
+#[derive(FromRow)]
+pub struct SettingsRow {
+    pub theme: String,
+    #[sqlx(rename = "primaryColor")]
+    pub primary_color: String,
+    #[sqlx(rename = "displayStyle")]
+    pub display_style: String,
+    #[sqlx(rename = "windowPositionX")]
+    pub window_position_x: Option<i32>,
+    #[sqlx(rename = "windowPositionY")]
+    pub window_position_y: Option<i32>,
+    #[sqlx(rename = "windowSizeWidth")]
+    pub window_size_width: Option<i32>,
+    #[sqlx(rename = "windowSizeHeight")]
+    pub window_size_height: Option<i32>,
+    #[sqlx(rename = "accountGroups")]
+    pub account_groups: Option<String>,
+    #[sqlx(rename = "customGroups")]
+    pub custom_groups: Option<String>,
+    #[sqlx(rename = "customGroupsOrder")]
+    pub custom_groups_order: Option<String>,
+    #[sqlx(rename = "accountsOrder")]
+    pub accounts_order: Option<String>,
+    #[sqlx(rename = "lastSeenVersion")]
+    pub last_seen_version: Option<String>,
+    #[sqlx(rename = "componentSpacing")]
+    pub component_spacing: i32,
+    #[sqlx(rename = "componentPadding")]
+    pub component_padding: i32,
+}
+
+impl From<SettingsRow> for Settings {
+    fn from(row: SettingsRow) -> Self {
+        let window_position =
+            if let (Some(x), Some(y)) = (row.window_position_x, row.window_position_y) {
+                Some(WindowPosition { x, y })
+            } else {
+                None
+            };
+
+        let window_size = if let (Some(width), Some(height)) =
+            (row.window_size_width, row.window_size_height)
+        {
+            Some(WindowSize { width, height })
+        } else {
+            None
+        };
+
+        Settings {
+            theme: row.theme,
+            primary_color: row.primary_color,
+            display_style: Some(row.display_style),
+            window_position,
+            window_size,
+            account_groups: row.account_groups,
+            custom_groups: row.custom_groups,
+            custom_groups_order: row.custom_groups_order,
+            accounts_order: row.accounts_order,
+            last_seen_version: row.last_seen_version,
+            component_spacing: row.component_spacing,
+            component_padding: row.component_padding,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub timestamp: String,
+    pub entity: String,
+    #[sqlx(rename = "entityId")]
+    #[serde(rename = "entityId")]
+    pub entity_id: String,
+    pub action: String,
+    pub before: Option<String>, // JSON string
+    pub after: Option<String>,  // JSON string
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub label: String,
+    #[sqlx(rename = "createdAt")]
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    pub undone: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IntegrityIssue {
+    pub kind: String,
+    pub entity: String,
+    #[serde(rename = "entityId")]
+    pub entity_id: String,
+    pub reference: String, // The id that could not be resolved
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IntegrityRepair {
+    pub kinds: Vec<String>, // Issue kinds to fix
+    #[serde(rename = "accountId", default)]
+    pub account_id: Option<String>,
+    #[serde(rename = "categoryId", default)]
+    pub category_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct AccountBalance {
+    #[sqlx(rename = "accountId")]
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    pub balance: f64,
+    // Credit limit plus balance, for credit cards with a limit
+    #[sqlx(rename = "availableCredit")]
+    #[serde(rename = "availableCredit")]
+    pub available_credit: Option<f64>,
+}
+
+/// Last closed statement of a credit card.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CardStatement {
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    // First and last day of the statement period, YYYY-MM-DD
+    #[serde(rename = "periodStart")]
+    pub period_start: String,
+    #[serde(rename = "closingDate")]
+    pub closing_date: String,
+    #[serde(rename = "dueDate")]
+    pub due_date: String,
+    // Owed at the closing date, as a positive amount
+    #[serde(rename = "statementBalance")]
+    pub statement_balance: f64,
+    // Payments and refunds since the closing date
+    pub paid: f64,
+    // Left to pay on the statement, never below zero
+    pub remaining: f64,
+    #[serde(rename = "minimumPayment")]
+    pub minimum_payment: f64,
+}
+
+/// Monthly payment of a loan split into interest and principal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmortizationRow {
+    // 1 for the first payment of the loan
+    pub number: u32,
+    pub date: String,
+    pub payment: f64,
+    pub interest: f64,
+    pub principal: f64,
+    // Principal left to repay after the payment
+    pub balance: f64,
+}
+
+/// Loan payment made by record_loan_payment. The ids of the interest
+/// expense and of the two legs of the principal transfer come from the
+/// caller, like those of add_transfer.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoanPayment {
+    #[serde(rename = "fromAccountId")]
+    pub from_account_id: String,
+    pub date: String,
+    pub amount: f64,
+    #[serde(rename = "interestTransactionId")]
+    pub interest_transaction_id: String,
+    // Legs of the principal transfer, in the paying and the loan account
+    #[serde(rename = "fromTransactionId")]
+    pub from_transaction_id: String,
+    #[serde(rename = "toTransactionId")]
+    pub to_transaction_id: String,
+}
+
+/// Net worth at a date: what the asset accounts hold, holdings of investment
+/// accounts and valued assets included, minus what is owed on the liability
+/// accounts.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct NetWorth {
+    pub assets: f64,
+    // Owed, as a positive amount
+    pub liabilities: f64,
+    #[serde(rename = "netWorth")]
+    pub net_worth: f64,
+}
+
+/// Value of an asset account (house, car...) estimated at a date. Asset
+/// values come from valuations rather than transactions.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Valuation {
+    pub id: String,
+    #[sqlx(rename = "accountId")]
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    pub date: String,
+    pub value: f64,
+}
+
+/// Value of an asset account at a date: its last valuation, depreciated
+/// since then.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssetValue {
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    pub date: String,
+    pub value: f64,
+    #[serde(rename = "valuationDate")]
+    pub valuation_date: String,
+    // Value of that valuation, before depreciation
+    pub valuation: f64,
+}
+
+/// Stock, fund or any other security held in investment accounts.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Security {
+    pub id: String,
+    // Ticker or ISIN, unique; matched by the price import
+    pub symbol: String,
+    pub name: String,
+}
+
+// Trade types
+pub const TRADE_BUY: &str = "buy";
+pub const TRADE_SELL: &str = "sell";
+
+/// Purchase or sale of a security in an investment account. Trades don't
+/// move the cash balance of the account, which is kept by its transactions.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Trade {
+    pub id: String,
+    #[sqlx(rename = "accountId")]
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    #[sqlx(rename = "securityId")]
+    #[serde(rename = "securityId")]
+    pub security_id: String,
+    pub date: String,
+    // TRADE_BUY or TRADE_SELL
+    #[sqlx(rename = "type")]
+    #[serde(rename = "type")]
+    pub trade_type: String,
+    pub quantity: f64,
+    // Per unit, fees excluded
+    pub price: f64,
+    #[serde(default)]
+    pub fees: f64,
+}
+
+/// Market price of a security at the end of a day.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Price {
+    // "securityId:date"
+    pub id: String,
+    #[sqlx(rename = "securityId")]
+    #[serde(rename = "securityId")]
+    pub security_id: String,
+    pub date: String,
+    pub price: f64,
+}
+
+/// Position in a security of an investment account at a date. Lots are
+/// sold first in, first out; a position sold off keeps its realized gain
+/// with a quantity of 0.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Holding {
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    #[serde(rename = "securityId")]
+    pub security_id: String,
+    pub quantity: f64,
+    // Price and fees paid for the lots still held
+    #[serde(rename = "costBasis")]
+    pub cost_basis: f64,
+    // Last known price: from the prices table, or the last trade
+    pub price: f64,
+    #[serde(rename = "priceDate")]
+    pub price_date: String,
+    #[serde(rename = "marketValue")]
+    pub market_value: f64,
+    #[serde(rename = "unrealizedGain")]
+    pub unrealized_gain: f64,
+    // Sale proceeds net of fees minus the cost of the lots sold
+    #[serde(rename = "realizedGain")]
+    pub realized_gain: f64,
+}
+
+/// Holdings of an investment account added up.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct InvestmentSummary {
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    #[serde(rename = "marketValue")]
+    pub market_value: f64,
+    #[serde(rename = "costBasis")]
+    pub cost_basis: f64,
+    #[serde(rename = "realizedGain")]
+    pub realized_gain: f64,
+    #[serde(rename = "unrealizedGain")]
+    pub unrealized_gain: f64,
+}
+
+/// Net worth at the end of a day, for charting its history.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetWorthPoint {
+    pub date: String,
+    pub assets: f64,
+    pub liabilities: f64,
+    #[serde(rename = "netWorth")]
+    pub net_worth: f64,
+}
+
+/// Savings goal followed either on the balance of an account or on the
+/// transactions carrying a tag, never both.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Goal {
+    pub id: String,
+    pub name: String,
+    #[sqlx(rename = "targetAmount")]
+    #[serde(rename = "targetAmount")]
+    pub target_amount: f64,
+    #[sqlx(rename = "targetDate")]
+    #[serde(rename = "targetDate")]
+    pub target_date: Option<String>,
+    #[sqlx(rename = "accountId")]
+    #[serde(rename = "accountId")]
+    pub account_id: Option<String>,
+    #[sqlx(rename = "tagId")]
+    #[serde(rename = "tagId")]
+    pub tag_id: Option<String>,
+}
+
+/// Where a goal stands at a date and where it is heading at the pace of
+/// its recent contributions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GoalProgress {
+    #[serde(rename = "goalId")]
+    pub goal_id: String,
+    pub date: String,
+    pub current: f64,
+    // Left to save, 0 once the goal is reached
+    pub remaining: f64,
+    // Average saved per month over the last few months
+    #[serde(rename = "monthlyContribution")]
+    pub monthly_contribution: f64,
+    // Per month to reach the target by the target date; None without one
+    #[serde(rename = "requiredMonthlyContribution")]
+    pub required_monthly_contribution: Option<f64>,
+    // None when the recent contributions never get there
+    #[serde(rename = "projectedDate")]
+    pub projected_date: Option<String>,
+}
+
+/// Balance of an account right after a transaction.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct RunningBalance {
+    #[sqlx(rename = "transactionId")]
+    #[serde(rename = "transactionId")]
+    pub transaction_id: String,
+    pub date: String,
+    // Signed: negative for expenses and outgoing transfers
+    pub amount: f64,
+    pub balance: f64,
+}
+
+/// Balances of an account by transaction status. Each balance includes the
+/// initial balance; `pending` is only the amount still to clear.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct AccountStatusBalances {
+    #[sqlx(rename = "accountId")]
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    // Reconciled transactions only
+    pub reconciled: f64,
+    // Cleared and reconciled transactions
+    pub cleared: f64,
+    // Pending transactions
+    pub pending: f64,
+    // Every transaction
+    pub total: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategorySuggestion {
+    #[serde(rename = "categoryId")]
+    pub category_id: String,
+    // Between 0 and 1
+    pub confidence: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryTotal {
+    #[serde(rename = "categoryId")]
+    pub category_id: String,
+    #[serde(rename = "parentId")]
+    pub parent_id: Option<String>,
+    // Transactions of the category itself
+    pub income: f64,
+    pub expense: f64,
+    pub count: i64,
+    // Including every descendant category
+    #[serde(rename = "totalIncome")]
+    pub total_income: f64,
+    #[serde(rename = "totalExpense")]
+    pub total_expense: f64,
+    #[serde(rename = "totalCount")]
+    pub total_count: i64,
+}
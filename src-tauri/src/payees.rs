@@ -0,0 +1,213 @@
+use crate::audit::{self, Operation};
+use crate::history;
+use crate::tags;
+use crate::models::{Payee, PayeeTotal, Transaction, STATUS_RECONCILED};
+use regex::{Regex, RegexBuilder};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+
+enum Pattern {
+    Text(String),
+    Regex(Regex),
+}
+
+/// Payee names and aliases compiled for matching descriptions.
+pub struct PayeeMatcher {
+    patterns: Vec<(String, Pattern)>,
+}
+
+/// `/.../` aliases are regular expressions, anything else is plain text.
+fn parse_pattern(alias: &str) -> Result<Pattern, regex::Error> {
+    let alias = alias.trim();
+    match alias.strip_prefix('/').and_then(|a| a.strip_suffix('/')) {
+        Some(pattern) if !pattern.is_empty() => RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map(Pattern::Regex),
+        _ => Ok(Pattern::Text(normalize(alias))),
+    }
+}
+
+/// Uppercases and collapses whitespace so "Cb  carrefour" matches "CB CARREFOUR".
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_uppercase()
+}
+
+/// Checks a payee before it is saved.
+pub fn validate(payee: &Payee) -> Result<(), String> {
+    if payee.name.trim().is_empty() {
+        return Err("Le nom du bénéficiaire est obligatoire.".to_string());
+    }
+    for alias in &payee.aliases {
+        parse_pattern(alias).map_err(|e| format!("Alias invalide « {alias} » : {e}"))?;
+    }
+    Ok(())
+}
+
+pub async fn fetch_payees(conn: &mut SqliteConnection) -> Result<Vec<Payee>, sqlx::Error> {
+    sqlx::query_as::<_, Payee>("SELECT * FROM payees ORDER BY name")
+        .fetch_all(&mut *conn)
+        .await
+}
+
+impl PayeeMatcher {
+    pub async fn load(conn: &mut SqliteConnection) -> Result<Self, sqlx::Error> {
+        let mut patterns = Vec::new();
+        for payee in fetch_payees(conn).await? {
+            for alias in std::iter::once(&payee.name).chain(payee.aliases.iter()) {
+                match parse_pattern(alias) {
+                    Ok(Pattern::Text(text)) if text.is_empty() => {}
+                    Ok(pattern) => patterns.push((payee.id.clone(), pattern)),
+                    Err(e) => log::warn!("Skipping invalid alias of payee {}: {e}", payee.id),
+                }
+            }
+        }
+        Ok(Self { patterns })
+    }
+
+    /// Payee whose name or alias matches the description. When several
+    /// match, the longest match wins ("CARREFOUR MARKET" over "CARREFOUR").
+    pub fn find(&self, description: &str) -> Option<&str> {
+        let description = normalize(description);
+        self.patterns
+            .iter()
+            .filter_map(|(payee_id, pattern)| {
+                let length = match pattern {
+                    Pattern::Text(text) => description.contains(text.as_str()).then_some(text.len()),
+                    Pattern::Regex(regex) => regex.find(&description).map(|m| m.len()),
+                }?;
+                Some((length, payee_id.as_str()))
+            })
+            .max_by_key(|(length, _)| *length)
+            .map(|(_, payee_id)| payee_id)
+    }
+
+    /// Fills in the payee of a transaction that has none. Returns whether it changed.
+    pub fn assign(&self, t: &mut Transaction) -> bool {
+        if t.payee_id.is_some() || t.is_transfer {
+            return false;
+        }
+        t.payee_id = t
+            .description
+            .as_deref()
+            .and_then(|d| self.find(d))
+            .map(str::to_string);
+        t.payee_id.is_some()
+    }
+}
+
+/// Matches stored transactions without a payee (all of them with `all`)
+/// against the payees and returns how many changed. Reconciled transactions
+/// are left alone unless `force` is set.
+pub async fn assign_existing(
+    conn: &mut SqliteConnection,
+    op: &mut Operation,
+    account_id: Option<String>,
+    all: bool,
+    force: bool,
+) -> Result<u64, sqlx::Error> {
+    let matcher = PayeeMatcher::load(conn).await?;
+
+    let mut query: QueryBuilder<Sqlite> = QueryBuilder::new("SELECT * FROM transactions WHERE \"isTransfer\" = 0");
+    if !all {
+        query.push(" AND \"payeeId\" IS NULL");
+    }
+    if !force {
+        query.push(" AND status <> ").push_bind(STATUS_RECONCILED);
+    }
+    if let Some(account_id) = account_id {
+        query.push(" AND \"accountId\" = ").push_bind(account_id);
+    }
+    let transactions = query.build_query_as::<Transaction>().fetch_all(&mut *conn).await?;
+
+    let mut changed = 0;
+    for before in transactions {
+        let mut after = Transaction {
+            payee_id: None,
+            ..before.clone()
+        };
+        // With `all`, a transaction that no longer matches keeps its payee
+        if !matcher.assign(&mut after) || after.payee_id == before.payee_id {
+            continue;
+        }
+        history::upsert_transaction(conn, &after).await?;
+        audit::record(conn, op, audit::ENTITY_TRANSACTION, &after.id, audit::ACTION_UPDATE, Some(&before), Some(&after))
+            .await?;
+        changed += 1;
+    }
+    Ok(changed)
+}
+
+/// Income and expense per payee, transfers excluded. Dates are YYYY-MM-DD,
+/// both inclusive; with `tag_ids`, only transactions carrying any of those
+/// tags count.
+pub async fn payee_totals(
+    conn: &mut SqliteConnection,
+    from: Option<String>,
+    to: Option<String>,
+    account_id: Option<String>,
+    tag_ids: &[String],
+) -> Result<Vec<PayeeTotal>, sqlx::Error> {
+    let mut query: QueryBuilder<Sqlite> = QueryBuilder::new(
+        "SELECT \"payeeId\",
+                TOTAL(CASE WHEN \"type\" = 'income' THEN amount END) AS income,
+                TOTAL(CASE WHEN \"type\" = 'expense' THEN amount END) AS expense,
+                COUNT(*) AS count
+         FROM transactions WHERE \"isTransfer\" = 0 AND \"payeeId\" IS NOT NULL",
+    );
+    if let Some(from) = from {
+        query.push(" AND date >= ").push_bind(from);
+    }
+    if let Some(to) = to {
+        query.push(" AND date <= ").push_bind(to);
+    }
+    if let Some(account_id) = account_id {
+        query.push(" AND \"accountId\" = ").push_bind(account_id);
+    }
+    tags::push_filter(&mut query, tag_ids, false);
+    query.push(" GROUP BY \"payeeId\" ORDER BY expense DESC");
+
+    query.build_query_as::<PayeeTotal>().fetch_all(&mut *conn).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    fn payee(id: &str, name: &str, aliases: &[&str]) -> Payee {
+        Payee {
+            id: id.to_string(),
+            name: name.to_string(),
+            aliases: aliases.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn validate_rejects_blank_names_and_bad_regexes() {
+        assert!(validate(&payee("p1", "Carrefour", &["/CARREFOUR( MARKET)?/"])).is_ok());
+        assert!(validate(&payee("p1", "  ", &[])).is_err());
+        assert!(validate(&payee("p1", "Carrefour", &["/CARREFOUR(/"])).is_err());
+    }
+
+    #[tokio::test]
+    async fn the_longest_match_wins() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        for p in [
+            payee("carrefour", "Carrefour", &[]),
+            payee("market", "Carrefour Market", &[]),
+            payee("sncf", "SNCF", &["/^PRLV SNCF.*VOYAGES/"]),
+        ] {
+            history::upsert_payee(&mut conn, &p).await.unwrap();
+        }
+        let matcher = PayeeMatcher::load(&mut conn).await.unwrap();
+
+        assert_eq!(matcher.find("CB carrefour  12/03"), Some("carrefour"));
+        assert_eq!(matcher.find("CB CARREFOUR MARKET LYON"), Some("market"));
+        assert_eq!(matcher.find("prlv sncf internet voyages"), Some("sncf"));
+        assert_eq!(matcher.find("Boulangerie"), None);
+    }
+}
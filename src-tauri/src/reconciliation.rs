@@ -0,0 +1,282 @@
+use crate::audit::{self, Operation};
+use crate::balances::{round_cents, FROM_OPENING, SIGNED_AMOUNT};
+use crate::history;
+use crate::models::{
+    Reconciliation, ReconciliationStatus, Transaction, STATUS_CLEARED, STATUS_PENDING, STATUS_RECONCILED,
+};
+use sqlx::SqliteConnection;
+
+pub const STATUS_OPEN: &str = "open";
+pub const STATUS_FINISHED: &str = "finished";
+
+/// Returned when a reconciled transaction is changed without confirmation;
+/// the frontend asks the user and retries with `force`.
+pub const RECONCILED_WARNING: &str =
+    "Cette transaction a été rapprochée avec un relevé. Confirmez pour la modifier quand même.";
+
+/// Rejects changes to reconciled transactions unless `force` is set.
+pub fn check_unlocked<'a>(transactions: impl IntoIterator<Item = &'a Transaction>, force: bool) -> Result<(), String> {
+    if !force && transactions.into_iter().any(|t| t.status == STATUS_RECONCILED) {
+        return Err(RECONCILED_WARNING.to_string());
+    }
+    Ok(())
+}
+
+/// Checks a status set by the user: reconciled is only reached by finishing
+/// a reconciliation, and kept afterwards.
+pub fn check_status(t: &Transaction, previous: Option<&Transaction>) -> Result<(), String> {
+    match t.status.as_str() {
+        STATUS_PENDING | STATUS_CLEARED => Ok(()),
+        STATUS_RECONCILED if previous.is_some_and(|p| p.status == STATUS_RECONCILED) => Ok(()),
+        STATUS_RECONCILED => Err("Seul un rapprochement peut marquer une transaction comme rapprochée.".to_string()),
+        status => Err(format!("Statut de transaction inconnu : {status}")),
+    }
+}
+
+/// Fills the status dates of `t` from its previous version; a transaction
+/// leaving the reconciled status loses its reconciliation.
+pub fn stamp_status(t: &mut Transaction, previous: Option<&Transaction>, today: &str) {
+    match t.status.as_str() {
+        STATUS_RECONCILED => {
+            if let Some(previous) = previous {
+                t.cleared_at = previous.cleared_at.clone();
+                t.reconciled_at = previous.reconciled_at.clone();
+                t.reconciliation_id = previous.reconciliation_id.clone();
+            }
+        }
+        STATUS_CLEARED => {
+            // A new transaction may come with its clearing date (e.g. a restore)
+            let cleared_at = match previous {
+                Some(p) if p.status != STATUS_PENDING => p.cleared_at.clone(),
+                Some(_) => None,
+                None => t.cleared_at.take(),
+            };
+            t.cleared_at = cleared_at.or_else(|| Some(today.to_string()));
+            t.reconciled_at = None;
+            t.reconciliation_id = None;
+        }
+        _ => {
+            t.cleared_at = None;
+            t.reconciled_at = None;
+            t.reconciliation_id = None;
+        }
+    }
+}
+
+/// Current local date, as YYYY-MM-DD.
+pub async fn today(conn: &mut SqliteConnection) -> Result<String, sqlx::Error> {
+    sqlx::query_scalar("SELECT date('now', 'localtime')").fetch_one(&mut *conn).await
+}
+
+pub async fn fetch_reconciliations(
+    conn: &mut SqliteConnection,
+    account_id: Option<&str>,
+) -> Result<Vec<Reconciliation>, sqlx::Error> {
+    match account_id {
+        Some(account_id) => {
+            sqlx::query_as::<_, Reconciliation>(
+                "SELECT * FROM reconciliations WHERE \"accountId\" = $1 ORDER BY \"statementDate\" DESC, \"createdAt\" DESC",
+            )
+            .bind(account_id)
+            .fetch_all(&mut *conn)
+            .await
+        }
+        None => {
+            sqlx::query_as::<_, Reconciliation>("SELECT * FROM reconciliations ORDER BY \"statementDate\" DESC, \"createdAt\" DESC")
+                .fetch_all(&mut *conn)
+                .await
+        }
+    }
+}
+
+pub async fn fetch_open(conn: &mut SqliteConnection, account_id: &str) -> Result<Option<Reconciliation>, sqlx::Error> {
+    sqlx::query_as::<_, Reconciliation>("SELECT * FROM reconciliations WHERE \"accountId\" = $1 AND status = $2")
+        .bind(account_id)
+        .bind(STATUS_OPEN)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+#[derive(sqlx::FromRow)]
+struct ClearedSums {
+    balance: f64,
+    cleared: i64,
+    uncleared: i64,
+}
+
+/// Cleared balance of the account at the statement date, compared with the
+/// statement. Amounts are rounded to the cent.
+pub async fn status(conn: &mut SqliteConnection, reconciliation: Reconciliation) -> Result<ReconciliationStatus, sqlx::Error> {
+    let sums = sqlx::query_as::<_, ClearedSums>(
+        &format!(
+            "SELECT COALESCE((SELECT \"initialBalance\" FROM accounts WHERE id = $1), 0)
+                      + TOTAL(CASE WHEN t.status <> $3 THEN {SIGNED_AMOUNT} END) AS balance,
+                    COUNT(CASE WHEN t.status = $4 THEN 1 END) AS cleared,
+                    COUNT(CASE WHEN t.status = $3 THEN 1 END) AS uncleared
+             FROM transactions t JOIN accounts a ON a.id = t.\"accountId\"
+             WHERE t.\"accountId\" = $1 AND t.date <= $2 AND {FROM_OPENING}"
+        ),
+    )
+    .bind(&reconciliation.account_id)
+    .bind(&reconciliation.statement_date)
+    .bind(STATUS_PENDING)
+    .bind(STATUS_CLEARED)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let cleared_balance = round_cents(sums.balance);
+    Ok(ReconciliationStatus {
+        difference: round_cents(reconciliation.statement_balance - cleared_balance),
+        cleared_balance,
+        cleared_count: sums.cleared,
+        uncleared_count: sums.uncleared,
+        reconciliation,
+    })
+}
+
+/// Marks the cleared transactions from the opening date of the account up to
+/// the statement date as reconciled and
+/// closes the session. The caller checks that the statement balances first.
+pub async fn finish(
+    conn: &mut SqliteConnection,
+    op: &mut Operation,
+    reconciliation: &Reconciliation,
+) -> Result<Reconciliation, sqlx::Error> {
+    let cleared = sqlx::query_as::<_, Transaction>(
+        &format!(
+            "SELECT t.* FROM transactions t JOIN accounts a ON a.id = t.\"accountId\"
+             WHERE t.\"accountId\" = $1 AND t.date <= $2 AND t.status = $3 AND {FROM_OPENING}"
+        ),
+    )
+    .bind(&reconciliation.account_id)
+    .bind(&reconciliation.statement_date)
+    .bind(STATUS_CLEARED)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let today = today(conn).await?;
+    for before in &cleared {
+        let after = Transaction {
+            status: STATUS_RECONCILED.to_string(),
+            reconciled_at: Some(today.clone()),
+            reconciliation_id: Some(reconciliation.id.clone()),
+            ..before.clone()
+        };
+        history::upsert_transaction(conn, &after).await?;
+        audit::record(conn, op, audit::ENTITY_TRANSACTION, &after.id, audit::ACTION_UPDATE, Some(before), Some(&after))
+            .await?;
+    }
+
+    sqlx::query("UPDATE reconciliations SET status = $1, \"finishedAt\" = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = $2")
+        .bind(STATUS_FINISHED)
+        .bind(&reconciliation.id)
+        .execute(&mut *conn)
+        .await?;
+    let after = audit::fetch_reconciliation(conn, &reconciliation.id)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+    audit::record(conn, op, audit::ENTITY_RECONCILIATION, &after.id, audit::ACTION_UPDATE, Some(reconciliation), Some(&after))
+        .await?;
+
+    Ok(after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    async fn seed(conn: &mut SqliteConnection) -> Reconciliation {
+        for sql in [
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'Compte', 'checking', 100)",
+            "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, status) VALUES
+             ('t1', '2026-01-05', 'a1', 'income', 50.1, 'cleared'),
+             ('t2', '2026-01-20', 'a1', 'expense', 20.05, 'cleared'),
+             ('t3', '2026-01-25', 'a1', 'expense', 7, 'pending'),
+             ('t4', '2026-02-02', 'a1', 'expense', 99, 'cleared')",
+            "INSERT INTO reconciliations (id, \"accountId\", \"statementDate\", \"statementBalance\") VALUES ('r1', 'a1', '2026-01-31', 130.05)",
+        ] {
+            sqlx::query(sql).execute(&mut *conn).await.unwrap();
+        }
+        fetch_open(conn, "a1").await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn status_compares_cleared_rows_with_the_statement() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        let reconciliation = seed(&mut conn).await;
+
+        let status = status(&mut conn, reconciliation).await.unwrap();
+        assert_eq!(status.cleared_balance, 130.05);
+        assert_eq!(status.difference, 0.0);
+        assert_eq!((status.cleared_count, status.uncleared_count), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn finishing_locks_the_cleared_rows() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        let reconciliation = seed(&mut conn).await;
+        let mut op = Operation::new("finish_reconciliation");
+
+        let finished = finish(&mut conn, &mut op, &reconciliation).await.unwrap();
+        assert_eq!(finished.status, STATUS_FINISHED);
+        assert!(fetch_open(&mut conn, "a1").await.unwrap().is_none());
+
+        let mut locked = Vec::new();
+        for id in ["t1", "t2", "t3", "t4"] {
+            locked.push(audit::fetch_transaction(&mut conn, id).await.unwrap().unwrap());
+        }
+        let ids: Vec<&str> = locked.iter().filter(|t| t.reconciliation_id.is_some()).map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, ["t1", "t2"]);
+        assert_eq!(check_unlocked(&locked[..1], false), Err(RECONCILED_WARNING.to_string()));
+        assert!(check_unlocked(&locked[..1], true).is_ok());
+        assert!(check_unlocked(&locked[2..], false).is_ok());
+    }
+
+    fn transaction(status: &str) -> Transaction {
+        serde_json::from_value(serde_json::json!({
+            "id": "t1", "date": "2026-01-10", "accountId": "a1", "type": "expense",
+            "amount": 5.0, "category": "uncategorized", "status": status,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn only_a_reconciliation_marks_rows_reconciled() {
+        let reconciled = Transaction {
+            reconciliation_id: Some("r1".to_string()),
+            ..transaction(STATUS_RECONCILED)
+        };
+        assert!(check_status(&transaction(STATUS_CLEARED), None).is_ok());
+        assert!(check_status(&transaction(STATUS_RECONCILED), None).is_err());
+        assert!(check_status(&transaction(STATUS_RECONCILED), Some(&transaction(STATUS_CLEARED))).is_err());
+        assert!(check_status(&transaction(STATUS_RECONCILED), Some(&reconciled)).is_ok());
+        assert!(check_status(&transaction("done"), None).is_err());
+    }
+
+    #[test]
+    fn status_dates_follow_the_status() {
+        let mut cleared = transaction(STATUS_CLEARED);
+        stamp_status(&mut cleared, Some(&transaction(STATUS_PENDING)), "2026-01-12");
+        assert_eq!(cleared.cleared_at.as_deref(), Some("2026-01-12"));
+
+        // Staying cleared keeps the original date
+        let mut again = transaction(STATUS_CLEARED);
+        stamp_status(&mut again, Some(&cleared), "2026-01-20");
+        assert_eq!(again.cleared_at.as_deref(), Some("2026-01-12"));
+
+        let reconciled = Transaction {
+            reconciled_at: Some("2026-01-31".to_string()),
+            reconciliation_id: Some("r1".to_string()),
+            ..again.clone()
+        };
+        let mut reopened = Transaction {
+            status: STATUS_PENDING.to_string(),
+            ..reconciled.clone()
+        };
+        stamp_status(&mut reopened, Some(&reconciled), "2026-02-01");
+        assert_eq!((reopened.cleared_at, reopened.reconciled_at, reopened.reconciliation_id), (None, None, None));
+    }
+}
@@ -0,0 +1,142 @@
+use crate::models::CategoryTotal;
+use crate::tags;
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+use std::collections::{HashMap, HashSet};
+
+#[derive(sqlx::FromRow)]
+struct CategorySum {
+    category: String,
+    income: f64,
+    expense: f64,
+    count: i64,
+}
+
+#[derive(sqlx::FromRow)]
+struct CategoryNode {
+    id: String,
+    #[sqlx(rename = "parentId")]
+    parent_id: Option<String>,
+}
+
+/// Sums income and expense per category, then adds every category's own
+/// amounts to all of its ancestors. Transfers are left out; with `tag_ids`,
+/// only transactions carrying any of those tags count.
+pub async fn category_totals(
+    conn: &mut SqliteConnection,
+    from: Option<String>,
+    to: Option<String>,
+    account_id: Option<String>,
+    tag_ids: &[String],
+) -> Result<Vec<CategoryTotal>, sqlx::Error> {
+    let nodes = sqlx::query_as::<_, CategoryNode>("SELECT id, \"parentId\" FROM categories ORDER BY name")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let mut query: QueryBuilder<Sqlite> = QueryBuilder::new(
+        "SELECT category,
+                TOTAL(CASE WHEN \"type\" = 'income' THEN amount END) AS income,
+                TOTAL(CASE WHEN \"type\" = 'expense' THEN amount END) AS expense,
+                COUNT(*) AS count
+         FROM transactions WHERE \"isTransfer\" = 0",
+    );
+    if let Some(from) = from {
+        query.push(" AND date >= ").push_bind(from);
+    }
+    if let Some(to) = to {
+        query.push(" AND date <= ").push_bind(to);
+    }
+    if let Some(account_id) = account_id {
+        query.push(" AND \"accountId\" = ").push_bind(account_id);
+    }
+    tags::push_filter(&mut query, tag_ids, false);
+    query.push(" GROUP BY category");
+
+    let sums = query.build_query_as::<CategorySum>().fetch_all(&mut *conn).await?;
+
+    let parents: HashMap<&str, Option<&str>> = nodes
+        .iter()
+        .map(|n| (n.id.as_str(), n.parent_id.as_deref()))
+        .collect();
+    let mut totals: Vec<CategoryTotal> = nodes
+        .iter()
+        .map(|n| CategoryTotal {
+            category_id: n.id.clone(),
+            parent_id: n.parent_id.clone(),
+            income: 0.0,
+            expense: 0.0,
+            count: 0,
+            total_income: 0.0,
+            total_expense: 0.0,
+            total_count: 0,
+        })
+        .collect();
+    let index: HashMap<String, usize> = totals
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.category_id.clone(), i))
+        .collect();
+
+    for sum in &sums {
+        let Some(&i) = index.get(&sum.category) else {
+            continue;
+        };
+        totals[i].income += sum.income;
+        totals[i].expense += sum.expense;
+        totals[i].count += sum.count;
+
+        // Walk up the tree; the visited set guards against a corrupted cycle
+        let mut visited = HashSet::new();
+        let mut current = Some(sum.category.as_str());
+        while let Some(id) = current {
+            if !visited.insert(id) {
+                break;
+            }
+            let Some(&j) = index.get(id) else {
+                break;
+            };
+            totals[j].total_income += sum.income;
+            totals[j].total_expense += sum.expense;
+            totals[j].total_count += sum.count;
+            current = parents.get(id).copied().flatten();
+        }
+    }
+
+    Ok(totals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    #[tokio::test]
+    async fn totals_roll_up_to_every_ancestor() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        for sql in [
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'Compte', 'checking', 0)",
+            "INSERT INTO categories (id, name, icon, color, \"parentId\") VALUES
+                ('food', 'Courses', 'Tag', '#fff', NULL),
+                ('fruit', 'Fruits', 'Tag', '#fff', 'food'),
+                ('apple', 'Pommes', 'Tag', '#fff', 'fruit')",
+            "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, category, \"isTransfer\") VALUES
+                ('t1', '2026-01-10', 'a1', 'expense', 10, 'food', 0),
+                ('t2', '2026-01-10', 'a1', 'expense', 4, 'apple', 0),
+                ('t3', '2026-02-10', 'a1', 'income', 1, 'apple', 0),
+                ('t4', '2026-01-10', 'a1', 'expense', 100, 'fruit', 1)",
+        ] {
+            sqlx::query(sql).execute(&mut *conn).await.unwrap();
+        }
+
+        let totals = category_totals(&mut conn, None, None, None, &[]).await.unwrap();
+        let food = totals.iter().find(|t| t.category_id == "food").unwrap();
+        assert_eq!((food.expense, food.count), (10.0, 1));
+        assert_eq!((food.total_expense, food.total_income, food.total_count), (14.0, 1.0, 3));
+        let fruit = totals.iter().find(|t| t.category_id == "fruit").unwrap();
+        assert_eq!((fruit.count, fruit.total_expense), (0, 4.0));
+
+        let january = category_totals(&mut conn, None, Some("2026-01-31".to_string()), None, &[]).await.unwrap();
+        let food = january.iter().find(|t| t.category_id == "food").unwrap();
+        assert_eq!((food.total_income, food.total_count), (0.0, 2));
+    }
+}
@@ -0,0 +1,363 @@
+use crate::audit::{self, Operation};
+use crate::history;
+use crate::reconciliation;
+use crate::tags;
+use crate::models::{Rule, Transaction, STATUS_CLEARED, STATUS_PENDING, STATUS_RECONCILED, UNCATEGORIZED_CATEGORY_ID};
+use regex::{Regex, RegexBuilder};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+
+struct CompiledRule {
+    rule: Rule,
+    regex: Option<Regex>,
+    contains: Option<String>,
+}
+
+/// Enabled rules in evaluation order, with their patterns compiled once.
+pub struct RuleSet {
+    rules: Vec<CompiledRule>,
+}
+
+/// Outcome of running the rules on one transaction.
+pub struct RuleEffect {
+    // Category, status or payee changed
+    pub changed: bool,
+    // Tags to add
+    pub tag_ids: Vec<String>,
+}
+
+fn compile_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+/// Checks a rule before it is saved.
+pub fn validate(rule: &Rule) -> Result<(), String> {
+    if rule.name.trim().is_empty() {
+        return Err("Le nom de la règle est obligatoire.".to_string());
+    }
+    if let Some(pattern) = &rule.description_regex {
+        compile_regex(pattern).map_err(|e| format!("Expression régulière invalide : {e}"))?;
+    }
+    if let (Some(min), Some(max)) = (rule.amount_min, rule.amount_max) {
+        if min > max {
+            return Err("Le montant minimum dépasse le montant maximum.".to_string());
+        }
+    }
+    if let Some(t) = &rule.transaction_type {
+        if t != "income" && t != "expense" {
+            return Err(format!("Type de transaction inconnu : {t}"));
+        }
+    }
+    if let Some(status) = &rule.set_status {
+        if status != STATUS_PENDING && status != STATUS_CLEARED {
+            return Err(format!("Une règle ne peut pas appliquer le statut {status}."));
+        }
+    }
+    if rule.set_category.is_none() && rule.set_status.is_none() && rule.set_payee.is_none() && rule.set_tags.is_empty() {
+        return Err("La règle doit définir au moins une action.".to_string());
+    }
+    Ok(())
+}
+
+pub async fn fetch_rules(conn: &mut SqliteConnection) -> Result<Vec<Rule>, sqlx::Error> {
+    sqlx::query_as::<_, Rule>("SELECT * FROM rules ORDER BY position, name")
+        .fetch_all(&mut *conn)
+        .await
+}
+
+impl RuleSet {
+    pub async fn load(conn: &mut SqliteConnection) -> Result<Self, sqlx::Error> {
+        let rules = fetch_rules(conn)
+            .await?
+            .into_iter()
+            .filter(|rule| rule.enabled)
+            .filter_map(|rule| {
+                let regex = match rule.description_regex.as_deref().map(compile_regex).transpose() {
+                    Ok(regex) => regex,
+                    Err(e) => {
+                        log::warn!("Skipping rule {} with invalid regex: {e}", rule.id);
+                        return None;
+                    }
+                };
+                let contains = rule.description_contains.as_ref().map(|c| c.to_lowercase());
+                Some(CompiledRule { rule, regex, contains })
+            })
+            .collect();
+        Ok(Self { rules })
+    }
+
+    /// Applies every matching rule to `t`. Tags are returned rather than
+    /// applied since they live in their own table.
+    pub fn apply(&self, t: &mut Transaction) -> RuleEffect {
+        let original = (t.category.clone(), t.status.clone(), t.payee_id.clone());
+        let mut tag_ids: Vec<String> = Vec::new();
+
+        for compiled in &self.rules {
+            if !compiled.matches(t) {
+                continue;
+            }
+            let rule = &compiled.rule;
+            if let Some(category) = &rule.set_category {
+                t.category = category.clone();
+            }
+            // Reconciled transactions keep their status
+            if let Some(status) = rule.set_status.as_ref().filter(|_| t.status != STATUS_RECONCILED) {
+                t.status = status.clone();
+            }
+            if let Some(payee_id) = &rule.set_payee {
+                t.payee_id = Some(payee_id.clone());
+            }
+            for tag_id in &rule.set_tags {
+                if !tag_ids.contains(tag_id) {
+                    tag_ids.push(tag_id.clone());
+                }
+            }
+            if rule.stop_processing {
+                break;
+            }
+        }
+
+        RuleEffect {
+            changed: (t.category.clone(), t.status.clone(), t.payee_id.clone()) != original,
+            tag_ids,
+        }
+    }
+}
+
+impl CompiledRule {
+    fn matches(&self, t: &Transaction) -> bool {
+        let rule = &self.rule;
+        let description = t.description.as_deref().unwrap_or("");
+
+        if let Some(contains) = &self.contains {
+            if !description.to_lowercase().contains(contains.as_str()) {
+                return false;
+            }
+        }
+        if let Some(regex) = &self.regex {
+            if !regex.is_match(description) {
+                return false;
+            }
+        }
+        if rule.amount_min.is_some_and(|min| t.amount < min) || rule.amount_max.is_some_and(|max| t.amount > max) {
+            return false;
+        }
+        if rule.account_id.as_ref().is_some_and(|id| *id != t.account_id) {
+            return false;
+        }
+        if rule.transaction_type.as_ref().is_some_and(|tt| *tt != t.transaction_type) {
+            return false;
+        }
+        true
+    }
+}
+
+/// True for transactions that rules fill in automatically: uncategorized
+/// and not part of a transfer.
+pub fn is_candidate(t: &Transaction) -> bool {
+    !t.is_transfer && t.category == UNCATEGORIZED_CATEGORY_ID
+}
+
+/// Runs the rules over stored transactions and returns how many changed.
+/// Without `all`, only uncategorized transactions are considered; without
+/// `force`, reconciled ones are left alone.
+pub async fn apply_to_existing(
+    conn: &mut SqliteConnection,
+    op: &mut Operation,
+    account_id: Option<String>,
+    all: bool,
+    force: bool,
+) -> Result<u64, sqlx::Error> {
+    let rules = RuleSet::load(conn).await?;
+
+    let mut query: QueryBuilder<Sqlite> = QueryBuilder::new("SELECT * FROM transactions WHERE \"isTransfer\" = 0");
+    if !all {
+        query.push(" AND category = ").push_bind(UNCATEGORIZED_CATEGORY_ID);
+    }
+    if !force {
+        query.push(" AND status <> ").push_bind(STATUS_RECONCILED);
+    }
+    if let Some(account_id) = account_id {
+        query.push(" AND \"accountId\" = ").push_bind(account_id);
+    }
+    let transactions = query.build_query_as::<Transaction>().fetch_all(&mut *conn).await?;
+
+    let today = reconciliation::today(conn).await?;
+    let mut changed = 0;
+    for before in transactions {
+        let mut after = before.clone();
+        let effect = rules.apply(&mut after);
+        reconciliation::stamp_status(&mut after, Some(&before), &today);
+
+        let mut tagged = false;
+        for tag_id in &effect.tag_ids {
+            tagged |= tags::link(conn, op, &after.id, tag_id).await?;
+        }
+        if effect.changed {
+            history::upsert_transaction(conn, &after).await?;
+            audit::record(conn, op, audit::ENTITY_TRANSACTION, &after.id, audit::ACTION_UPDATE, Some(&before), Some(&after))
+                .await?;
+        }
+        if effect.changed || tagged {
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    fn rule(id: &str, position: i64, fields: serde_json::Value) -> Rule {
+        let mut value = serde_json::json!({"id": id, "name": id, "position": position});
+        value.as_object_mut().unwrap().extend(fields.as_object().unwrap().clone());
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn transaction(description: &str, amount: f64) -> Transaction {
+        serde_json::from_value(serde_json::json!({
+            "id": "t1", "date": "2026-01-10", "accountId": "a1", "type": "expense",
+            "amount": amount, "category": UNCATEGORIZED_CATEGORY_ID, "description": description,
+        }))
+        .unwrap()
+    }
+
+    async fn save(conn: &mut SqliteConnection, rules: &[Rule]) {
+        sqlx::query(
+            "INSERT INTO categories (id, name, icon, color) VALUES \
+             ('food', 'Courses', 'Tag', '#fff'), ('big', 'Gros achats', 'Tag', '#fff'), \
+             ('card', 'Carte', 'Tag', '#fff'), ('other', 'Autres', 'Tag', '#fff'), \
+             ('disabled', 'Désactivée', 'Tag', '#fff'), ('broken', 'Cassée', 'Tag', '#fff')",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+        for rule in rules {
+            history::upsert_rule(conn, rule).await.unwrap();
+        }
+    }
+
+    async fn rule_set(rules: &[Rule]) -> RuleSet {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        save(&mut conn, rules).await;
+        RuleSet::load(&mut conn).await.unwrap()
+    }
+
+    #[test]
+    fn validate_rejects_inconsistent_rules() {
+        let valid = rule("r1", 0, serde_json::json!({"descriptionRegex": "^CB ", "setCategory": "c1"}));
+        assert!(validate(&valid).is_ok());
+
+        let invalid = [
+            serde_json::json!({"name": " ", "setCategory": "c1"}),
+            serde_json::json!({"descriptionRegex": "CB (", "setCategory": "c1"}),
+            serde_json::json!({"amountMin": 50.0, "amountMax": 10.0, "setCategory": "c1"}),
+            serde_json::json!({"type": "transfer", "setCategory": "c1"}),
+            serde_json::json!({"descriptionContains": "CB"}),
+        ];
+        for fields in invalid {
+            assert!(validate(&rule("r1", 0, fields.clone())).is_err(), "{fields}");
+        }
+    }
+
+    #[tokio::test]
+    async fn later_rules_override_earlier_ones() {
+        let rules = rule_set(&[
+            rule("r1", 0, serde_json::json!({"descriptionContains": "carrefour", "setCategory": "food"})),
+            rule("r2", 1, serde_json::json!({"amountMin": 100.0, "setCategory": "big", "setStatus": "cleared"})),
+        ])
+        .await;
+
+        let mut small = transaction("CB CARREFOUR", 20.0);
+        assert!(rules.apply(&mut small).changed);
+        assert_eq!((small.category.as_str(), small.status.as_str()), ("food", STATUS_PENDING));
+
+        let mut big = transaction("CB Carrefour", 150.0);
+        assert!(rules.apply(&mut big).changed);
+        assert_eq!((big.category.as_str(), big.status.as_str()), ("big", STATUS_CLEARED));
+
+        let mut other = transaction("Loyer", 20.0);
+        assert!(!rules.apply(&mut other).changed);
+    }
+
+    #[tokio::test]
+    async fn stop_processing_ends_the_evaluation() {
+        let rules = rule_set(&[
+            rule("r1", 0, serde_json::json!({"descriptionRegex": "^cb ", "setCategory": "card", "stopProcessing": true})),
+            rule("r2", 1, serde_json::json!({"setCategory": "other"})),
+        ])
+        .await;
+
+        let mut card = transaction("CB Boulangerie", 5.0);
+        rules.apply(&mut card);
+        assert_eq!(card.category, "card");
+
+        let mut other = transaction("Virement", 5.0);
+        rules.apply(&mut other);
+        assert_eq!(other.category, "other");
+    }
+
+    #[tokio::test]
+    async fn tags_of_every_matching_rule_are_collected_once() {
+        let rules = rule_set(&[
+            rule("r1", 0, serde_json::json!({"descriptionContains": "sncf", "setTags": ["travel", "work"]})),
+            rule("r2", 1, serde_json::json!({"amountMin": 50.0, "setTags": ["work", "big"]})),
+        ])
+        .await;
+
+        let mut t = transaction("PRLV SNCF", 80.0);
+        let effect = rules.apply(&mut t);
+        assert!(!effect.changed);
+        assert_eq!(effect.tag_ids, ["travel", "work", "big"]);
+    }
+
+    #[tokio::test]
+    async fn reconciled_transactions_keep_their_status() {
+        let rules = rule_set(&[rule("r1", 0, serde_json::json!({"setCategory": "food", "setStatus": "cleared"}))]).await;
+
+        let mut t = transaction("CB Carrefour", 20.0);
+        t.status = STATUS_RECONCILED.to_string();
+        assert!(rules.apply(&mut t).changed);
+        assert_eq!((t.category.as_str(), t.status.as_str()), ("food", STATUS_RECONCILED));
+
+        let invalid = rule("r2", 0, serde_json::json!({"setStatus": "reconciled"}));
+        assert!(validate(&invalid).is_err());
+    }
+
+    #[tokio::test]
+    async fn disabled_and_broken_rules_are_skipped() {
+        let mut disabled = rule("r1", 0, serde_json::json!({"setCategory": "disabled"}));
+        disabled.enabled = false;
+        let broken = rule("r2", 1, serde_json::json!({"descriptionRegex": "(", "setCategory": "broken"}));
+        let rules = rule_set(&[disabled, broken]).await;
+
+        let mut t = transaction("CB Boulangerie", 5.0);
+        assert!(!rules.apply(&mut t).changed);
+        assert_eq!(t.category, UNCATEGORIZED_CATEGORY_ID);
+    }
+
+    #[tokio::test]
+    async fn reconciled_transactions_are_left_alone_unless_forced() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        save(&mut conn, &[rule("r1", 0, serde_json::json!({"descriptionContains": "carrefour", "setCategory": "food"}))]).await;
+        sqlx::query(
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'Compte', 'checking', 0);
+             INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, description, status) VALUES
+             ('t1', '2026-01-10', 'a1', 'expense', 20, 'CB CARREFOUR', 'reconciled'),
+             ('t2', '2026-01-11', 'a1', 'expense', 30, 'CB CARREFOUR', 'cleared')",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+        let mut op = Operation::new("apply_rules");
+
+        assert_eq!(apply_to_existing(&mut conn, &mut op, None, false, false).await.unwrap(), 1);
+        assert_eq!(audit::fetch_transaction(&mut conn, "t1").await.unwrap().unwrap().category, UNCATEGORIZED_CATEGORY_ID);
+        assert_eq!(apply_to_existing(&mut conn, &mut op, None, false, true).await.unwrap(), 1);
+        let t1 = audit::fetch_transaction(&mut conn, "t1").await.unwrap().unwrap();
+        assert_eq!((t1.category.as_str(), t1.status.as_str()), ("food", STATUS_RECONCILED));
+    }
+}
@@ -0,0 +1,154 @@
+use crate::models::{CategorySuggestion, TRANSFER_CATEGORY_ID, UNCATEGORIZED_CATEGORY_ID};
+use sqlx::SqliteConnection;
+use std::collections::{HashMap, HashSet};
+
+#[derive(sqlx::FromRow)]
+struct Sample {
+    category: String,
+    description: Option<String>,
+    amount: f64,
+}
+
+#[derive(Default)]
+struct CategoryStats {
+    documents: usize,
+    tokens: usize,
+    counts: HashMap<String, usize>,
+}
+
+/// Splits a description into lowercase words, dropping numbers (dates, card
+/// digits, references) and single letters that carry no signal.
+fn tokenize(description: &str, amount: f64) -> Vec<String> {
+    let mut tokens: Vec<String> = description
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() > 1 && !w.chars().any(|c| c.is_ascii_digit()))
+        .map(str::to_lowercase)
+        .collect();
+
+    // Amount order of magnitude, so 4.50 and 45.00 are told apart
+    if amount > 0.0 {
+        tokens.push(format!("#amount:{}", amount.log2().floor() as i64));
+    }
+    tokens
+}
+
+/// Multinomial naive Bayes model trained on the categorized history, built
+/// once and reused for every transaction to rank.
+pub struct CategoryModel {
+    stats: HashMap<String, CategoryStats>,
+    vocabulary_size: f64,
+    total_documents: f64,
+}
+
+impl CategoryModel {
+    pub async fn load(conn: &mut SqliteConnection) -> Result<CategoryModel, sqlx::Error> {
+        let samples = sqlx::query_as::<_, Sample>(
+            "SELECT category, description, amount FROM transactions
+             WHERE \"isTransfer\" = 0 AND category NOT IN ($1, $2)",
+        )
+        .bind(UNCATEGORIZED_CATEGORY_ID)
+        .bind(TRANSFER_CATEGORY_ID)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut stats: HashMap<String, CategoryStats> = HashMap::new();
+        let mut vocabulary = HashSet::new();
+        for sample in &samples {
+            let entry = stats.entry(sample.category.clone()).or_default();
+            entry.documents += 1;
+            for token in tokenize(sample.description.as_deref().unwrap_or(""), sample.amount) {
+                entry.tokens += 1;
+                *entry.counts.entry(token.clone()).or_default() += 1;
+                vocabulary.insert(token);
+            }
+        }
+        Ok(CategoryModel {
+            stats,
+            vocabulary_size: vocabulary.len() as f64,
+            total_documents: samples.len() as f64,
+        })
+    }
+
+    /// Ranks categories for a new transaction. Confidences sum to 1 over all
+    /// known categories; the `limit` best are returned.
+    pub fn suggest(&self, description: &str, amount: f64, limit: usize) -> Vec<CategorySuggestion> {
+        let tokens = tokenize(description, amount.abs());
+        if tokens.is_empty() || self.stats.is_empty() {
+            return Vec::new();
+        }
+
+        // Log-probabilities with Laplace smoothing
+        let mut scores: Vec<(&String, f64)> = self
+            .stats
+            .iter()
+            .map(|(category, s)| {
+                let prior = (s.documents as f64 / self.total_documents).ln();
+                let likelihood: f64 = tokens
+                    .iter()
+                    .map(|token| {
+                        let count = s.counts.get(token).copied().unwrap_or(0) as f64;
+                        ((count + 1.0) / (s.tokens as f64 + self.vocabulary_size)).ln()
+                    })
+                    .sum();
+                (category, prior + likelihood)
+            })
+            .collect();
+
+        // Softmax turns the scores into confidences
+        let max = scores.iter().map(|(_, score)| *score).fold(f64::NEG_INFINITY, f64::max);
+        let total: f64 = scores.iter().map(|(_, score)| (score - max).exp()).sum();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        scores
+            .into_iter()
+            .take(limit)
+            .map(|(category_id, score)| CategorySuggestion {
+                category_id: category_id.clone(),
+                confidence: (score - max).exp() / total,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    #[test]
+    fn tokenize_keeps_only_meaningful_words() {
+        assert_eq!(
+            tokenize("CB CARREFOUR 12/03 x4521 à Lyon", 45.0),
+            vec!["cb", "carrefour", "lyon", "#amount:5"]
+        );
+        assert_eq!(tokenize("", 0.0), Vec::<String>::new());
+    }
+
+    #[tokio::test]
+    async fn ranks_categories_from_past_descriptions() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        for sql in [
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'Compte', 'checking', 0)",
+            "INSERT INTO categories (id, name, icon, color) VALUES ('food', 'Courses', 'Tag', '#fff'), ('fuel', 'Carburant', 'Tag', '#fff')",
+            "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount, category, description) VALUES
+             ('t1', '2026-01-02', 'a1', 'expense', 42.0, 'food', 'CB CARREFOUR MARKET'),
+             ('t2', '2026-01-09', 'a1', 'expense', 38.5, 'food', 'CB CARREFOUR CITY'),
+             ('t3', '2026-01-12', 'a1', 'expense', 60.0, 'fuel', 'CB TOTAL ENERGIES'),
+             ('t4', '2026-01-15', 'a1', 'expense', 10.0, 'uncategorized', 'CB CARREFOUR')",
+        ] {
+            sqlx::query(sql).execute(&mut *conn).await.unwrap();
+        }
+
+        let model = CategoryModel::load(&mut conn).await.unwrap();
+        let ranked = model.suggest("CB Carrefour Market", -40.0, 5);
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].category_id, "food");
+        assert!(ranked[0].confidence > 0.5);
+        let total: f64 = ranked.iter().map(|s| s.confidence).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        assert_eq!(model.suggest("CB TOTAL", 55.0, 1)[0].category_id, "fuel");
+        assert!(model.suggest("1234", 0.0, 5).is_empty());
+    }
+}
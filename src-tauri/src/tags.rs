@@ -0,0 +1,161 @@
+use crate::audit::{self, Operation};
+use crate::models::{Tag, TransactionTag};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+use std::collections::BTreeSet;
+
+/// Checks a tag before it is saved.
+pub fn validate(tag: &Tag) -> Result<(), String> {
+    if tag.name.trim().is_empty() {
+        return Err("Le nom du tag est obligatoire.".to_string());
+    }
+    Ok(())
+}
+
+pub async fn fetch_tags(conn: &mut SqliteConnection) -> Result<Vec<Tag>, sqlx::Error> {
+    sqlx::query_as::<_, Tag>("SELECT * FROM tags ORDER BY name")
+        .fetch_all(&mut *conn)
+        .await
+}
+
+pub async fn fetch_links(conn: &mut SqliteConnection) -> Result<Vec<TransactionTag>, sqlx::Error> {
+    sqlx::query_as::<_, TransactionTag>("SELECT * FROM transaction_tags")
+        .fetch_all(&mut *conn)
+        .await
+}
+
+/// Audit entity id of a link.
+pub fn link_id(transaction_id: &str, tag_id: &str) -> String {
+    format!("{transaction_id}:{tag_id}")
+}
+
+pub async fn insert_link(conn: &mut SqliteConnection, link: &TransactionTag) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("INSERT OR IGNORE INTO transaction_tags (\"transactionId\", \"tagId\") VALUES ($1, $2)")
+        .bind(&link.transaction_id)
+        .bind(&link.tag_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn delete_link(conn: &mut SqliteConnection, transaction_id: &str, tag_id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM transaction_tags WHERE \"transactionId\" = $1 AND \"tagId\" = $2")
+        .bind(transaction_id)
+        .bind(tag_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Tags a transaction. Returns false when it already had the tag.
+pub async fn link(
+    conn: &mut SqliteConnection,
+    op: &mut Operation,
+    transaction_id: &str,
+    tag_id: &str,
+) -> Result<bool, sqlx::Error> {
+    let link = TransactionTag {
+        transaction_id: transaction_id.to_string(),
+        tag_id: tag_id.to_string(),
+    };
+    if !insert_link(conn, &link).await? {
+        return Ok(false);
+    }
+    audit::record(conn, op, audit::ENTITY_TRANSACTION_TAG, &link_id(transaction_id, tag_id), audit::ACTION_CREATE, None, Some(&link))
+        .await?;
+    Ok(true)
+}
+
+/// Removes a tag from a transaction. Returns false when it did not have it.
+pub async fn unlink(
+    conn: &mut SqliteConnection,
+    op: &mut Operation,
+    transaction_id: &str,
+    tag_id: &str,
+) -> Result<bool, sqlx::Error> {
+    if !delete_link(conn, transaction_id, tag_id).await? {
+        return Ok(false);
+    }
+    let link = TransactionTag {
+        transaction_id: transaction_id.to_string(),
+        tag_id: tag_id.to_string(),
+    };
+    audit::record(conn, op, audit::ENTITY_TRANSACTION_TAG, &link_id(transaction_id, tag_id), audit::ACTION_DELETE, Some(&link), None)
+        .await?;
+    Ok(true)
+}
+
+/// Removes every tag of a transaction about to be deleted, so undoing the
+/// deletion brings its tags back.
+pub async fn unlink_transaction(
+    conn: &mut SqliteConnection,
+    op: &mut Operation,
+    transaction_id: &str,
+) -> Result<(), sqlx::Error> {
+    let tag_ids: Vec<String> = sqlx::query_scalar("SELECT \"tagId\" FROM transaction_tags WHERE \"transactionId\" = $1")
+        .bind(transaction_id)
+        .fetch_all(&mut *conn)
+        .await?;
+    for tag_id in tag_ids {
+        unlink(conn, op, transaction_id, &tag_id).await?;
+    }
+    Ok(())
+}
+
+/// Restricts a query on `transactions` to rows carrying any of `tag_ids`,
+/// or all of them with `match_all`. The query must already have a WHERE.
+pub fn push_filter(query: &mut QueryBuilder<Sqlite>, tag_ids: &[String], match_all: bool) {
+    let tag_ids: BTreeSet<&String> = tag_ids.iter().collect();
+    if tag_ids.is_empty() {
+        return;
+    }
+    query.push(" AND id IN (SELECT \"transactionId\" FROM transaction_tags WHERE \"tagId\" IN (");
+    let mut separated = query.separated(", ");
+    for tag_id in &tag_ids {
+        separated.push_bind((*tag_id).clone());
+    }
+    query.push(")");
+    if match_all {
+        query.push(" GROUP BY \"transactionId\" HAVING COUNT(*) = ").push_bind(tag_ids.len() as i64);
+    }
+    query.push(")");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    #[test]
+    fn validate_requires_a_name() {
+        let tag = |name: &str| -> Tag { serde_json::from_value(serde_json::json!({"id": "g1", "name": name})).unwrap() };
+        assert!(validate(&tag("Vacances")).is_ok());
+        assert!(validate(&tag(" ")).is_err());
+    }
+
+    #[tokio::test]
+    async fn filters_on_any_or_all_tags() {
+        let pool = memory_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        for sql in [
+            "INSERT INTO accounts (id, name, \"type\", \"initialBalance\") VALUES ('a1', 'Compte', 'checking', 0)",
+            "INSERT INTO tags (id, name) VALUES ('travel', 'Voyage'), ('work', 'Travail')",
+            "INSERT INTO transactions (id, date, \"accountId\", \"type\", amount) VALUES
+             ('t1', '2026-01-02', 'a1', 'expense', 1), ('t2', '2026-01-03', 'a1', 'expense', 2), ('t3', '2026-01-04', 'a1', 'expense', 3)",
+            "INSERT INTO transaction_tags (\"transactionId\", \"tagId\") VALUES ('t1', 'travel'), ('t1', 'work'), ('t2', 'travel')",
+        ] {
+            sqlx::query(sql).execute(&mut *conn).await.unwrap();
+        }
+
+        assert_eq!(filtered(&mut conn, &["travel", "work"], false).await, ["t1", "t2"]);
+        assert_eq!(filtered(&mut conn, &["travel", "work", "work"], true).await, ["t1"]);
+        assert_eq!(filtered(&mut conn, &[], true).await, ["t1", "t2", "t3"]);
+    }
+
+    async fn filtered(conn: &mut SqliteConnection, tag_ids: &[&str], match_all: bool) -> Vec<String> {
+        let tag_ids: Vec<String> = tag_ids.iter().map(|t| t.to_string()).collect();
+        let mut query: QueryBuilder<Sqlite> = QueryBuilder::new("SELECT id FROM transactions WHERE 1 = 1");
+        push_filter(&mut query, &tag_ids, match_all);
+        query.push(" ORDER BY id");
+        query.build_query_scalar().fetch_all(&mut *conn).await.unwrap()
+    }
+}
@@ -0,0 +1,18 @@
+import { AccountKind } from '../types';
+
+// Account kinds accepted by the backend, with their default icon and color
+export const ACCOUNT_KINDS: { id: AccountKind; label: string; icon: string; color: string }[] = [
+    { id: 'checking', label: 'Courant', icon: 'Wallet', color: '#3b82f6' },
+    { id: 'savings', label: 'Épargne', icon: 'PiggyBank', color: '#10b981' },
+    { id: 'credit_card', label: 'Carte de crédit', icon: 'CreditCard', color: '#ef4444' },
+    { id: 'cash', label: 'Espèces', icon: 'Banknote', color: '#f59e0b' },
+    { id: 'loan', label: 'Prêt', icon: 'Landmark', color: '#7c2d12' },
+    { id: 'investment', label: 'Investissement', icon: 'TrendingUp', color: '#8b5cf6' },
+    { id: 'asset', label: 'Autre actif', icon: 'Home', color: '#0ea5e9' },
+    { id: 'liability', label: 'Autre dette', icon: 'TrendingDown', color: '#6b7280' },
+];
+
+export const accountKind = (kind: string) => ACCOUNT_KINDS.find(k => k.id === kind) ?? ACCOUNT_KINDS[0];
+
+// Money owed on these is a negative balance, subtracted from the net worth
+export const isLiability = (kind: string) => ['credit_card', 'loan', 'liability'].includes(kind);
@@ -1,10 +1,33 @@
 import React, { createContext, useContext, useState, useEffect, useCallback, useMemo } from 'react';
 import { v4 as uuidv4 } from 'uuid';
 import { Account, Transaction, Category, ScheduledTransaction, BankContextType, AppData } from '../types';
-import { dbService } from '../services/db';
+import { ask } from '@tauri-apps/plugin-dialog';
+import { dbService, RECONCILED_WARNING } from '../services/db';
+import { accountKind } from '../constants/accounts';
+
+// Runs a change, asking for confirmation first when it touches a reconciled
+// transaction. Returns false when the user declined.
+const confirmIfReconciled = async (run: (force: boolean) => Promise<void>): Promise<boolean> => {
+    try {
+        await run(false);
+    } catch (error) {
+        if (error !== RECONCILED_WARNING) throw error;
+        const yes = await ask(RECONCILED_WARNING, {
+            title: 'Transaction rapprochée',
+            kind: 'warning',
+            okLabel: 'Modifier',
+            cancelLabel: 'Annuler'
+        });
+        if (!yes) return false;
+        await run(true);
+    }
+    return true;
+};
 
 const BankContext = createContext<BankContextType | undefined>(undefined);
 
+const SYSTEM_CATEGORY_IDS = ['uncategorized', 'transfer'];
+
 const DEFAULT_DATA: AppData = {
     accounts: [],
     transactions: [],
@@ -79,15 +102,23 @@ export const BankProvider: React.FC<{ children: React.ReactNode }> = ({ children
                 ]);
 
                 let currentAccounts = loadedAccounts;
-                let currentTransactions = loadedTransactions;
+                // Transactions of closed accounts stay out of the lists, like the accounts
+                const activeAccountIds = new Set(loadedAccounts.map(a => a.id));
+                let currentTransactions = loadedTransactions.filter(t => activeAccountIds.has(t.accountId));
                 let currentCategories = loadedCategories;
                 let currentScheduled = loadedScheduled;
 
-                if (loadedAccounts.length === 0 && loadedTransactions.length === 0 && loadedCategories.length === 0 && loadedScheduled.length === 0) {
+                // The backend always seeds the system categories ('uncategorized', 'transfer')
+                const hasUserCategories = loadedCategories.some(c => !SYSTEM_CATEGORY_IDS.includes(c.id));
+
+                if (loadedAccounts.length === 0 && loadedTransactions.length === 0 && !hasUserCategories && loadedScheduled.length === 0) {
                     // If no data loaded, use default data and save it
                     currentAccounts = DEFAULT_DATA.accounts;
                     currentTransactions = DEFAULT_DATA.transactions;
-                    currentCategories = DEFAULT_DATA.categories;
+                    currentCategories = [
+                        ...DEFAULT_DATA.categories,
+                        ...loadedCategories.filter(c => !DEFAULT_DATA.categories.some(d => d.id === c.id))
+                    ];
                     currentScheduled = DEFAULT_DATA.scheduled;
 
                     await Promise.all([
@@ -117,6 +148,7 @@ export const BankProvider: React.FC<{ children: React.ReactNode }> = ({ children
                     nextDate.setHours(0, 0, 0, 0);
 
                     let modified = false;
+                    let stalled = false;
 
                     while (nextDate <= today) {
                         modified = true;
@@ -135,8 +167,28 @@ export const BankProvider: React.FC<{ children: React.ReactNode }> = ({ children
                         }
 
                         const txId = uuidv4();
-
-                        if (scheduledTx.type === 'transfer' && scheduledTx.toAccountId) {
+                        const loan = currentAccounts.find(a => a.id === scheduledTx.toAccountId && a.type === 'loan' && a.loanPrincipal);
+
+                        if (scheduledTx.type === 'transfer' && loan) {
+                            // Loan payment: the backend splits it into interest and principal
+                            let created: Transaction[];
+                            try {
+                                created = await dbService.recordLoanPayment(loan.id, {
+                                    fromAccountId: scheduledTx.accountId,
+                                    date: scheduledTx.nextDate,
+                                    amount: scheduledTx.amount,
+                                    interestTransactionId: txId,
+                                    fromTransactionId: uuidv4(),
+                                    toTransactionId: uuidv4()
+                                });
+                            } catch (error) {
+                                // Left due and retried on the next start, e.g. once the account is reopened
+                                console.error(`Failed to record loan payment ${scheduledTx.id} of ${scheduledTx.nextDate}:`, error);
+                                stalled = true;
+                                break;
+                            }
+                            newTransactions.push(...created);
+                        } else if (scheduledTx.type === 'transfer' && scheduledTx.toAccountId) {
                             // Transfer: Create two linked transactions
                             const linkedId = uuidv4();
 
@@ -149,7 +201,7 @@ export const BankProvider: React.FC<{ children: React.ReactNode }> = ({ children
                                 amount: scheduledTx.amount,
                                 category: 'transfer',
                                 description: scheduledTx.description,
-                                checked: false,
+                                status: 'pending',
                                 isTransfer: true,
                                 linkedTransactionId: linkedId
                             };
@@ -163,7 +215,7 @@ export const BankProvider: React.FC<{ children: React.ReactNode }> = ({ children
                                 amount: scheduledTx.amount,
                                 category: 'transfer',
                                 description: scheduledTx.description,
-                                checked: false,
+                                status: 'pending',
                                 isTransfer: true,
                                 linkedTransactionId: txId
                             };
@@ -183,7 +235,8 @@ export const BankProvider: React.FC<{ children: React.ReactNode }> = ({ children
                                 amount: scheduledTx.amount,
                                 category: scheduledTx.category,
                                 description: scheduledTx.description,
-                                checked: false
+                                status: 'pending',
+                                payeeId: scheduledTx.payeeId
                             };
                             newTransactions.push(newTx);
                             await dbService.addTransaction(newTx);
@@ -257,7 +310,7 @@ export const BankProvider: React.FC<{ children: React.ReactNode }> = ({ children
                     }
 
                     if (modified) {
-                        if (scheduledTx.frequency === 'once') {
+                        if (scheduledTx.frequency === 'once' && !stalled) {
                             await dbService.deleteScheduled(scheduledTx.id);
                             processedScheduled.splice(i, 1);
                             i--; // Adjust index since we removed an item
@@ -281,22 +334,14 @@ export const BankProvider: React.FC<{ children: React.ReactNode }> = ({ children
         init();
     }, []);
 
-    const getAccountDefaults = (type: string) => {
-        switch (type) {
-            case 'Épargne': return { icon: 'PiggyBank', color: '#10b981' };
-            case 'Espèces': return { icon: 'Banknote', color: '#f59e0b' };
-            case 'Investissement': return { icon: 'TrendingUp', color: '#8b5cf6' };
-            default: return { icon: 'Wallet', color: '#3b82f6' };
-        }
-    };
 
     // --- Accounts ---
     const addAccount = useCallback(async (account: Omit<Account, 'id'>) => {
         const newAccount: Account = {
             ...account,
             id: uuidv4(),
-            icon: account.icon || getAccountDefaults(account.type).icon,
-            color: account.color || getAccountDefaults(account.type).color
+            icon: account.icon || accountKind(account.type).icon,
+            color: account.color || accountKind(account.type).color
         };
         await dbService.addAccount(newAccount);
         setAccounts(prev => [...prev, newAccount]);
@@ -317,10 +362,40 @@ export const BankProvider: React.FC<{ children: React.ReactNode }> = ({ children
         setFilterAccount(prev => prev.includes(id) ? prev.filter(a => a !== id) : prev);
     }, []);
 
+    const closeAccount = useCallback(async (id: string, closedOn: string, transferToId?: string) => {
+        const transfer = transferToId
+            ? { toAccountId: transferToId, transactionId: uuidv4(), linkedTransactionId: uuidv4() }
+            : undefined;
+        await dbService.closeAccount(id, closedOn, transfer);
+        // The backend only makes the transfer when some balance is left
+        const received = transfer
+            ? (await dbService.getTransactions({ accountId: transfer.toAccountId })).filter(t => t.id === transfer.linkedTransactionId)
+            : [];
+        setAccounts(prev => prev.filter(a => a.id !== id));
+        setTransactions(prev => [...received, ...prev.filter(t => t.accountId !== id)]);
+        setFilterAccount(prev => prev.includes(id) ? prev.filter(a => a !== id) : prev);
+    }, []);
+
+    const reopenAccount = useCallback(async (id: string) => {
+        const account = await dbService.reopenAccount(id);
+        const accountTransactions = await dbService.getTransactions({ accountId: id });
+        setAccounts(prev => [...prev, account]);
+        setTransactions(prev => [...accountTransactions, ...prev]);
+    }, []);
+
+    const scheduleCardPayment = useCallback(async (accountId: string, fromAccountId: string, amount?: number) => {
+        const newScheduled = await dbService.scheduleCardPayment(uuidv4(), accountId, fromAccountId, amount);
+        setScheduled(prev => [...prev, newScheduled]);
+    }, []);
+
+    const scheduleLoanPayment = useCallback(async (accountId: string, fromAccountId: string) => {
+        const newScheduled = await dbService.scheduleLoanPayment(uuidv4(), accountId, fromAccountId);
+        setScheduled(prev => [...prev, newScheduled]);
+    }, []);
+
     // --- Transactions ---
     const addTransaction = useCallback(async (transaction: Omit<Transaction, 'id'>) => {
-        const newTransaction: Transaction = { ...transaction, id: uuidv4() };
-        await dbService.addTransaction(newTransaction);
+        const newTransaction = await dbService.addTransaction({ ...transaction, id: uuidv4() });
         setTransactions(prev => [newTransaction, ...prev]);
         return newTransaction.id;
     }, []);
@@ -331,41 +406,40 @@ export const BankProvider: React.FC<{ children: React.ReactNode }> = ({ children
 
         const fromTx: Transaction = {
             id: fromTxId, date, accountId: fromAccountId, type: 'expense', amount,
-            category: 'transfer', description, checked: false, isTransfer: true, linkedTransactionId: toTxId
+            category: 'transfer', description, status: 'pending', isTransfer: true, linkedTransactionId: toTxId
         };
 
         const toTx: Transaction = {
             id: toTxId, date, accountId: toAccountId, type: 'income', amount,
-            category: 'transfer', description, checked: false, isTransfer: true, linkedTransactionId: fromTxId
+            category: 'transfer', description, status: 'pending', isTransfer: true, linkedTransactionId: fromTxId
         };
 
-        await Promise.all([
-            dbService.addTransaction(fromTx),
-            dbService.addTransaction(toTx)
-        ]);
+        await dbService.addTransfer(fromTx, toTx);
 
         setTransactions(prev => [fromTx, toTx, ...prev]);
     }, []);
 
     const updateTransaction = useCallback(async (transaction: Transaction) => {
-        await dbService.updateTransaction(transaction);
-        setTransactions(prev => prev.map(t => t.id === transaction.id ? transaction : t));
+        // The backend fills the status dates
+        let stored = transaction;
+        const run = async (force: boolean) => {
+            stored = await dbService.updateTransaction(transaction, force);
+        };
+        if (!await confirmIfReconciled(run)) return;
+        setTransactions(prev => prev.map(t => t.id === transaction.id ? stored : t));
     }, []);
 
     const deleteTransaction = useCallback(async (id: string) => {
-        await dbService.deleteTransaction(id);
+        if (!await confirmIfReconciled(force => dbService.deleteTransaction(id, force))) return;
         setTransactions(prev => prev.filter(t => t.id !== id));
     }, []);
 
     const toggleTransactionCheck = useCallback(async (id: string) => {
-        setTransactions(prev => {
-            const transaction = prev.find(t => t.id === id);
-            if (!transaction) return prev;
-            const updated = { ...transaction, checked: !transaction.checked };
-            dbService.updateTransaction(updated);
-            return prev.map(t => t.id === id ? updated : t);
-        });
-    }, []);
+        const transaction = transactions.find(t => t.id === id);
+        if (!transaction) return;
+        // Unchecking a reconciled transaction asks for confirmation first
+        await updateTransaction({ ...transaction, status: transaction.status === 'pending' ? 'cleared' : 'pending' });
+    }, [transactions, updateTransaction]);
 
     // --- Categories ---
     const addCategory = useCallback(async (category: Omit<Category, 'id'>) => {
@@ -380,9 +454,17 @@ export const BankProvider: React.FC<{ children: React.ReactNode }> = ({ children
         setCategories(prev => prev.map(c => c.id === category.id ? category : c));
     }, []);
 
-    const deleteCategory = useCallback(async (id: string) => {
-        await dbService.deleteCategory(id);
-        setCategories(prev => prev.filter(c => c.id !== id));
+    const deleteCategory = useCallback(async (id: string, targetId = 'uncategorized') => {
+        await dbService.deleteCategory(id, targetId);
+        // Sub-categories move up to the parent of the deleted category
+        setCategories(prev => {
+            const parentId = prev.find(c => c.id === id)?.parentId ?? null;
+            return prev
+                .filter(c => c.id !== id)
+                .map(c => c.parentId === id ? { ...c, parentId } : c);
+        });
+        setTransactions(prev => prev.map(t => t.category === id ? { ...t, category: targetId } : t));
+        setScheduled(prev => prev.map(s => s.category === id ? { ...s, category: targetId } : s));
     }, []);
 
     // --- Scheduled ---
@@ -410,6 +492,10 @@ export const BankProvider: React.FC<{ children: React.ReactNode }> = ({ children
         addAccount,
         updateAccount,
         deleteAccount,
+        closeAccount,
+        reopenAccount,
+        scheduleCardPayment,
+        scheduleLoanPayment,
         addTransaction,
         addTransfer,
         updateTransaction,
@@ -426,7 +512,7 @@ export const BankProvider: React.FC<{ children: React.ReactNode }> = ({ children
         isLoading
     }), [
         accounts, transactions, categories, scheduled, filterAccount, isLoading,
-        addAccount, updateAccount, deleteAccount,
+        addAccount, updateAccount, deleteAccount, closeAccount, reopenAccount, scheduleCardPayment, scheduleLoanPayment,
         addTransaction, addTransfer, updateTransaction, deleteTransaction, toggleTransactionCheck,
         addCategory, updateCategory, deleteCategory,
         addScheduled, updateScheduled, deleteScheduled
@@ -3,6 +3,8 @@ import { Edit2, Trash2, Tag } from 'lucide-react';
 import { Account, Transaction } from '../../types';
 import Button from '../../components/ui/Button';
 import { ICONS } from '../../constants/icons';
+import { countsTowardsBalance } from '../../utils/balance';
+import { accountKind } from '../../constants/accounts';
 
 interface AccountCardProps {
     account: Account;
@@ -24,11 +26,11 @@ const AccountCard: React.FC<AccountCardProps> = ({
     isDragOverlay
 }) => {
     const currentBalance = account.initialBalance + transactions
-        .filter(t => t.accountId === account.id)
+        .filter(t => t.accountId === account.id && countsTowardsBalance(t, account))
         .reduce((sum, t) => sum + (t.type === 'income' ? t.amount : -t.amount), 0);
 
     const clearedBalance = account.initialBalance + transactions
-        .filter(t => t.accountId === account.id && t.checked)
+        .filter(t => t.accountId === account.id && t.status !== 'pending' && countsTowardsBalance(t, account))
         .reduce((sum, t) => sum + (t.type === 'income' ? t.amount : -t.amount), 0);
 
     const Icon = ICONS[account.icon] || Tag;
@@ -48,7 +50,7 @@ const AccountCard: React.FC<AccountCardProps> = ({
                     </div>
                     <div>
                         <h3 className="font-semibold text-gray-900 dark:text-gray-200 app-account-name">{account.name}</h3>
-                        <p className="text-sm text-gray-500 dark:text-gray-400 app-account-type">{account.type}</p>
+                        <p className="text-sm text-gray-500 dark:text-gray-400 app-account-type">{accountKind(account.type).label}</p>
                     </div>
                 </div>
                 <div className="flex gap-1 app-account-actions" onPointerDown={(e) => e.stopPropagation()}>
@@ -81,6 +83,14 @@ const AccountCard: React.FC<AccountCardProps> = ({
                         {new Intl.NumberFormat('fr-FR', { minimumFractionDigits: 2, maximumFractionDigits: 2 }).format(clearedBalance)} €
                     </p>
                 </div>
+                {account.creditLimit != null && (
+                    <div className="flex items-center justify-between app-available-credit-container">
+                        <p className="text-xs text-gray-400 dark:text-gray-500 app-available-credit-label">Crédit disponible</p>
+                        <p className="text-sm font-medium text-gray-600 dark:text-gray-400 app-available-credit-value">
+                            {new Intl.NumberFormat('fr-FR', { minimumFractionDigits: 2, maximumFractionDigits: 2 }).format(account.creditLimit + currentBalance)} €
+                        </p>
+                    </div>
+                )}
             </div>
         </div>
     );
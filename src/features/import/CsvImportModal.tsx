@@ -5,6 +5,9 @@ import SearchableSelect from '../../components/ui/SearchableSelect';
 import { useBank } from '../../context/BankContext';
 import { useSettings } from '../../context/SettingsContext';
 import { useToast } from '../../context/ToastContext';
+import { ACCOUNT_KINDS } from '../../constants/accounts';
+import { AccountKind } from '../../types';
+import SuggestedCategories, { suggestedCategory, useSuggestedCategories } from './SuggestedCategories';
 
 interface CsvImportModalProps {
     isOpen: boolean;
@@ -33,9 +36,10 @@ const CsvImportModal: React.FC<CsvImportModalProps> = ({ isOpen, onClose, file,
     const [mapping, setMapping] = useState<ColumnMapping>({ date: 0, amount: 1, description: 3, category: -1 });
     const [selectedAccountId, setSelectedAccountId] = useState<string>('');
     const [newAccountName, setNewAccountName] = useState('');
-    const [newAccountType, setNewAccountType] = useState('Courant');
+    const [newAccountType, setNewAccountType] = useState<AccountKind>('checking');
     const [finalBalance, setFinalBalance] = useState('');
     const [categoryMapping, setCategoryMapping] = useState<Record<string, string>>({});
+    const [categoryChoices, setCategoryChoices] = useState<Record<number, string>>({});
     const [isImporting, setIsImporting] = useState(false);
     const [error, setError] = useState<string | null>(null);
 
@@ -51,9 +55,10 @@ const CsvImportModal: React.FC<CsvImportModalProps> = ({ isOpen, onClose, file,
             // Reset other states
             setSelectedAccountId('');
             setNewAccountName('');
-            setNewAccountType('Courant');
+            setNewAccountType('checking');
             setFinalBalance('');
             setCategoryMapping({});
+            setCategoryChoices({});
             setError(null);
         }
     }, [isOpen, file]);
@@ -153,24 +158,32 @@ const CsvImportModal: React.FC<CsvImportModalProps> = ({ isOpen, onClose, file,
         return dateStr;
     };
 
+    // Rows whose category comes from the file are not suggested one
+    const suggestionRows = useMemo(() => parsedData.map(row => {
+        if (mapping.category !== -1 && row[mapping.category]) return null;
+        return { description: row[mapping.description] || 'Import CSV', amount: parseAmount(row[mapping.amount]) };
+    }), [parsedData, mapping]);
+    const suggestions = useSuggestedCategories(suggestionRows, currentStep === 'confirm');
+
     const handleFinalImport = async () => {
         setIsImporting(true);
         setError(null);
         try {
             // 1. Parse transactions
-            let processedTransactions = parsedData.map(row => {
+            let processedTransactions = parsedData.map((row, index) => {
                 const amount = parseAmount(row[mapping.amount]);
                 const date = parseDate(row[mapping.date]);
                 const description = row[mapping.description] || 'Import CSV';
                 const rawCategory = mapping.category !== -1 ? row[mapping.category] : undefined;
 
                 return {
+                    index,
                     rawCategory: rawCategory,
                     date,
                     amount: Math.abs(amount),
                     type: amount >= 0 ? 'income' : 'expense',
                     description,
-                    checked: true
+                    status: 'cleared' as const
                 };
             });
 
@@ -215,7 +228,8 @@ const CsvImportModal: React.FC<CsvImportModalProps> = ({ isOpen, onClose, file,
 
             // 4. Finalize
             const transactionsToImport = processedTransactions.map(tx => {
-                let categoryId = categories[0]?.id || 'uncategorized';
+                // Left uncategorized so the auto-categorization rules can fill it in, unless a suggestion was kept
+                let categoryId = suggestedCategory(tx.index, suggestions, categoryChoices);
                 if (mapping.category !== -1 && tx.rawCategory) {
                     categoryId = finalCategoryMapping[tx.rawCategory] || categoryId;
                 }
@@ -227,7 +241,7 @@ const CsvImportModal: React.FC<CsvImportModalProps> = ({ isOpen, onClose, file,
                     description: tx.description,
                     category: categoryId,
                     accountId: targetAccountId,
-                    checked: tx.checked
+                    status: tx.status
                 };
             });
 
@@ -405,13 +419,8 @@ const CsvImportModal: React.FC<CsvImportModalProps> = ({ isOpen, onClose, file,
                                                 />
                                                 <SearchableSelect
                                                     value={newAccountType}
-                                                    onChange={(value) => setNewAccountType(value)}
-                                                    options={[
-                                                        { id: 'Courant', label: 'Courant', icon: 'Wallet', color: '#3b82f6' },
-                                                        { id: 'Épargne', label: 'Épargne', icon: 'PiggyBank', color: '#10b981' },
-                                                        { id: 'Espèces', label: 'Espèces', icon: 'Banknote', color: '#f59e0b' },
-                                                        { id: 'Investissement', label: 'Investissement', icon: 'TrendingUp', color: '#8b5cf6' }
-                                                    ]}
+                                                    onChange={(value) => setNewAccountType(value as AccountKind)}
+                                                    options={ACCOUNT_KINDS}
                                                     placeholder="Sélectionner un type"
                                                 />
                                                 <div className="flex items-center gap-2">
@@ -485,10 +494,17 @@ const CsvImportModal: React.FC<CsvImportModalProps> = ({ isOpen, onClose, file,
                             <div className="bg-yellow-50 dark:bg-yellow-900/20 p-4 rounded-lg flex items-start gap-3 text-left">
                                 <AlertTriangle className="w-5 h-5 text-yellow-600 dark:text-yellow-400 shrink-0 mt-0.5" />
                                 <p className="text-sm text-yellow-700 dark:text-yellow-300">
-                                    Aucune colonne catégorie n'a été sélectionnée. Toutes les transactions seront marquées comme "Non catégorisé".
+                                    Aucune colonne catégorie n'a été sélectionnée. Les transactions sans suggestion retenue seront marquées comme "Non catégorisé".
                                 </p>
                             </div>
                         )}
+                        <SuggestedCategories
+                            rows={suggestionRows}
+                            suggestions={suggestions}
+                            choices={categoryChoices}
+                            onChange={(index, categoryId) => setCategoryChoices(prev => ({ ...prev, [index]: categoryId }))}
+                            categories={categories}
+                        />
                     </div>
                 );
         }
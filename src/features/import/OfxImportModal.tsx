@@ -5,6 +5,9 @@ import SearchableSelect from '../../components/ui/SearchableSelect';
 import { useBank } from '../../context/BankContext';
 import { useSettings } from '../../context/SettingsContext';
 import { useToast } from '../../context/ToastContext';
+import { ACCOUNT_KINDS } from '../../constants/accounts';
+import { AccountKind } from '../../types';
+import SuggestedCategories, { suggestedCategory, useSuggestedCategories } from './SuggestedCategories';
 
 interface OfxImportModalProps {
     isOpen: boolean;
@@ -23,9 +26,10 @@ const OfxImportModal: React.FC<OfxImportModalProps> = ({ isOpen, onClose, file,
     const [currentStep, setCurrentStep] = useState<Step>('preview');
     const [selectedAccountId, setSelectedAccountId] = useState<string>('');
     const [newAccountName, setNewAccountName] = useState('');
-    const [newAccountType, setNewAccountType] = useState('Courant');
+    const [newAccountType, setNewAccountType] = useState<AccountKind>('checking');
     const [finalBalance, setFinalBalance] = useState('');
     const [categoryMapping, setCategoryMapping] = useState<Record<string, string>>({});
+    const [categoryChoices, setCategoryChoices] = useState<Record<number, string>>({});
     const [isImporting, setIsImporting] = useState(false);
     const [error, setError] = useState<string | null>(null);
 
@@ -35,9 +39,10 @@ const OfxImportModal: React.FC<OfxImportModalProps> = ({ isOpen, onClose, file,
             setCurrentStep('preview');
             setSelectedAccountId('');
             setNewAccountName('');
-            setNewAccountType('Courant');
+            setNewAccountType('checking');
             setFinalBalance('');
             setCategoryMapping({});
+            setCategoryChoices({});
             setError(null);
         }
     }, [isOpen, file]);
@@ -117,6 +122,12 @@ const OfxImportModal: React.FC<OfxImportModalProps> = ({ isOpen, onClose, file,
         return Array.from(cats).sort();
     }, [parsedData]);
 
+    // Rows whose category comes from the file are not suggested one
+    const suggestionRows = useMemo(() => parsedData.map(row => (
+        row.category ? null : { description: row.description, amount: row.amount }
+    )), [parsedData]);
+    const suggestions = useSuggestedCategories(suggestionRows, currentStep === 'confirm');
+
     const handleNext = () => {
         if (currentStep === 'preview') {
             if (parsedData.length === 0) {
@@ -161,7 +172,7 @@ const OfxImportModal: React.FC<OfxImportModalProps> = ({ isOpen, onClose, file,
                 ...tx,
                 amount: Math.abs(tx.amount),
                 type: tx.amount >= 0 ? 'income' : 'expense',
-                checked: true
+                status: 'cleared' as const
             }));
 
             // 2. Handle New Account Logic
@@ -207,8 +218,9 @@ const OfxImportModal: React.FC<OfxImportModalProps> = ({ isOpen, onClose, file,
             }
 
             // 4. Finalize transactions
-            const transactionsToImport = processedTransactions.map(tx => {
-                let categoryId = categories[0]?.id || 'uncategorized';
+            const transactionsToImport = processedTransactions.map((tx, index) => {
+                // Left uncategorized so the auto-categorization rules can fill it in, unless a suggestion was kept
+                let categoryId = suggestedCategory(index, suggestions, categoryChoices);
                 if (tx.category) {
                     categoryId = finalCategoryMapping[tx.category] || categoryId;
                 }
@@ -220,7 +232,7 @@ const OfxImportModal: React.FC<OfxImportModalProps> = ({ isOpen, onClose, file,
                     description: tx.description,
                     category: categoryId,
                     accountId: targetAccountId,
-                    checked: tx.checked
+                    status: tx.status
                 };
             });
 
@@ -363,13 +375,8 @@ const OfxImportModal: React.FC<OfxImportModalProps> = ({ isOpen, onClose, file,
                                                 />
                                                 <SearchableSelect
                                                     value={newAccountType}
-                                                    onChange={(value) => setNewAccountType(value)}
-                                                    options={[
-                                                        { id: 'Courant', label: 'Courant', icon: 'Wallet', color: '#3b82f6' },
-                                                        { id: 'Épargne', label: 'Épargne', icon: 'PiggyBank', color: '#10b981' },
-                                                        { id: 'Espèces', label: 'Espèces', icon: 'Banknote', color: '#f59e0b' },
-                                                        { id: 'Investissement', label: 'Investissement', icon: 'TrendingUp', color: '#8b5cf6' }
-                                                    ]}
+                                                    onChange={(value) => setNewAccountType(value as AccountKind)}
+                                                    options={ACCOUNT_KINDS}
                                                     placeholder="Sélectionner un type"
                                                 />
                                                 <div className="flex items-center gap-2">
@@ -446,6 +453,13 @@ const OfxImportModal: React.FC<OfxImportModalProps> = ({ isOpen, onClose, file,
                                 <span className="font-bold text-gray-900 dark:text-gray-200">{parsedData.length}</span> transactions seront importées dans le compte <span className="font-bold text-gray-900 dark:text-gray-200">{selectedAccountId === 'new' ? newAccountName : accounts.find(a => a.id === selectedAccountId)?.name}</span>.
                             </p>
                         </div>
+                        <SuggestedCategories
+                            rows={suggestionRows}
+                            suggestions={suggestions}
+                            choices={categoryChoices}
+                            onChange={(index, categoryId) => setCategoryChoices(prev => ({ ...prev, [index]: categoryId }))}
+                            categories={categories}
+                        />
                     </div>
                 );
         }
@@ -0,0 +1,98 @@
+import React, { useEffect, useState } from 'react';
+import { Sparkles } from 'lucide-react';
+import SearchableSelect from '../../components/ui/SearchableSelect';
+import { dbService } from '../../services/db';
+import { Category, CategorySuggestion } from '../../types';
+
+// Weaker suggestions are not pre-filled: the row stays uncategorized for the rules
+export const SUGGESTION_THRESHOLD = 0.5;
+
+export interface SuggestionRow {
+    description: string;
+    amount: number;
+}
+
+// Best suggestion for each row (null for rows to leave alone), fetched in one batch
+export const useSuggestedCategories = (rows: (SuggestionRow | null)[], enabled: boolean) => {
+    const [suggestions, setSuggestions] = useState<(CategorySuggestion | null)[]>([]);
+
+    useEffect(() => {
+        setSuggestions([]);
+        const asked = rows.flatMap(row => (row ? [[row.description, row.amount] as [string, number]] : []));
+        if (!enabled || asked.length === 0) return;
+
+        let cancelled = false;
+        dbService.suggestCategories(asked, 1)
+            .then(result => {
+                if (cancelled) return;
+                let next = 0;
+                setSuggestions(rows.map(row => {
+                    if (!row) return null;
+                    const best = result[next++][0];
+                    return best && best.confidence >= SUGGESTION_THRESHOLD ? best : null;
+                }));
+            })
+            .catch(error => console.error("Failed to suggest categories:", error));
+        return () => { cancelled = true; };
+    }, [rows, enabled]);
+
+    return suggestions;
+};
+
+// Category to import a row with: the one picked in the preview, else the suggestion
+export const suggestedCategory = (index: number, suggestions: (CategorySuggestion | null)[], choices: Record<number, string>) =>
+    choices[index] ?? suggestions[index]?.categoryId ?? 'uncategorized';
+
+interface SuggestedCategoriesProps {
+    rows: (SuggestionRow | null)[];
+    suggestions: (CategorySuggestion | null)[];
+    choices: Record<number, string>;
+    onChange: (index: number, categoryId: string) => void;
+    categories: Category[];
+}
+
+const SuggestedCategories: React.FC<SuggestedCategoriesProps> = ({ rows, suggestions, choices, onChange, categories }) => {
+    const suggested = suggestions.flatMap((suggestion, index) => (suggestion && rows[index] ? [index] : []));
+    if (suggested.length === 0) return null;
+
+    const options = [
+        { id: 'uncategorized', label: 'Non catégorisé (règles)', icon: 'Tag' },
+        ...categories.filter(c => c.id !== 'uncategorized' && c.id !== 'transfer').map(c => ({ id: c.id, label: c.name, icon: c.icon, color: c.color }))
+    ];
+
+    return (
+        <div className="text-left space-y-2">
+            <div className="flex items-center gap-2 text-sm font-medium text-gray-700 dark:text-gray-300">
+                <Sparkles className="w-4 h-4 text-primary-600 dark:text-primary-400" />
+                {suggested.length} catégorie{suggested.length > 1 ? 's' : ''} suggérée{suggested.length > 1 ? 's' : ''} d'après l'historique
+            </div>
+            <div className="max-h-64 overflow-y-auto space-y-2 pr-1">
+                {suggested.map(index => (
+                    <div key={index} className="flex items-center gap-4 p-3 bg-gray-50 dark:bg-neutral-800 rounded-lg">
+                        <div className="flex-1 min-w-0">
+                            <div className="text-sm font-medium text-gray-900 dark:text-gray-200 truncate" title={rows[index]!.description}>
+                                {rows[index]!.description}
+                            </div>
+                            <div className="text-xs text-gray-500">
+                                {Math.abs(rows[index]!.amount).toFixed(2)} € · confiance {Math.round(suggestions[index]!.confidence * 100)} %
+                            </div>
+                        </div>
+                        <div className="flex-1 min-w-[200px]">
+                            <SearchableSelect
+                                value={suggestedCategory(index, suggestions, choices)}
+                                onChange={(value) => onChange(index, value)}
+                                options={options}
+                                size="sm"
+                            />
+                        </div>
+                    </div>
+                ))}
+            </div>
+            <p className="text-xs text-gray-500">
+                Les transactions laissées non catégorisées passent par les règles de catégorisation.
+            </p>
+        </div>
+    );
+};
+
+export default SuggestedCategories;
@@ -1,6 +1,7 @@
 import { useMemo } from 'react';
 import { useBank } from '../context/BankContext';
 import { isSameMonth } from 'date-fns';
+import { countsTowardsBalance } from '../utils/balance';
 
 export const useFinancialMetrics = () => {
     const { accounts, transactions, filterAccount } = useBank();
@@ -14,20 +15,22 @@ export const useFinancialMetrics = () => {
             ? transactions
             : transactions.filter(t => filterAccount.includes(t.accountId));
 
-        // 2. Calculate Current Balance (Initial + Transactions)
+        // 2. Calculate Current Balance (Initial + Transactions since opening)
+        const accountsById = new Map(accounts.map(acc => [acc.id, acc]));
+        const balanceTransactions = relevantTransactions.filter(t => countsTowardsBalance(t, accountsById.get(t.accountId)));
         const currentBalance = accounts
             .filter(acc => isAllAccounts || filterAccount.includes(acc.id))
             .reduce((sum, acc) => sum + acc.initialBalance, 0) +
-            relevantTransactions.reduce((sum, t) => {
+            balanceTransactions.reduce((sum, t) => {
                 return sum + (t.type === 'income' ? t.amount : -t.amount);
             }, 0);
 
-        // 3. Calculate Checked Balance (Only checked transactions)
+        // 3. Calculate Checked Balance (Only cleared and reconciled transactions)
         const checkedBalance = accounts
             .filter(acc => isAllAccounts || filterAccount.includes(acc.id))
             .reduce((sum, acc) => sum + acc.initialBalance, 0) +
-            relevantTransactions
-                .filter(t => t.checked)
+            balanceTransactions
+                .filter(t => t.status !== 'pending')
                 .reduce((sum, t) => {
                     return sum + (t.type === 'income' ? t.amount : -t.amount);
                 }, 0);
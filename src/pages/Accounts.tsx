@@ -1,5 +1,6 @@
-import React, { useState } from 'react';
-import { Plus, Settings as SettingsIcon, Trash2, Edit2 } from 'lucide-react';
+import React, { useEffect, useState } from 'react';
+import { v4 as uuidv4 } from 'uuid';
+import { Plus, Settings as SettingsIcon, Trash2, Edit2, Archive, RotateCcw, CalendarClock } from 'lucide-react';
 import {
     DndContext,
     closestCenter,
@@ -21,7 +22,9 @@ import {
 } from '@dnd-kit/sortable';
 import { useBank } from '../context/BankContext';
 import { useSettings } from '../context/SettingsContext';
-import { Account } from '../types';
+import { useToast } from '../context/ToastContext';
+import { dbService } from '../services/db';
+import { Account, AccountKind, DepreciationMethod } from '../types';
 import FormPopup from '../components/ui/FormPopup';
 import ConfirmModal from '../components/ui/ConfirmModal';
 import Button from '../components/ui/Button';
@@ -31,6 +34,7 @@ import SearchableSelect from '../components/ui/SearchableSelect';
 import SortableGroupItem from '../features/accounts/SortableGroupItem';
 import AccountCard from '../features/accounts/AccountCard';
 import { COLORS } from '../constants/icons';
+import { ACCOUNT_KINDS, accountKind, isLiability } from '../constants/accounts';
 
 const dropAnimation = {
     sideEffects: defaultDropAnimationSideEffects({
@@ -42,15 +46,9 @@ const dropAnimation = {
     }),
 };
 
-const ACCOUNT_TYPES = [
-    { id: 'Courant', label: 'Courant' },
-    { id: 'Épargne', label: 'Épargne' },
-    { id: 'Investissement', label: 'Investissement' },
-    { id: 'Espèces', label: 'Espèces' },
-];
-
 const Accounts: React.FC = () => {
-    const { accounts: allAccounts, addAccount, updateAccount, deleteAccount, transactions } = useBank();
+    const { accounts: allAccounts, addAccount, updateAccount, deleteAccount, closeAccount, reopenAccount, scheduleCardPayment, scheduleLoanPayment, transactions } = useBank();
+    const { showToast } = useToast();
     const { settings, updateAccountGroup, updateCustomGroups, renameCustomGroup, updateCustomGroupsOrder, updateAccountsOrder } = useSettings();
 
     // Afficher tous les comptes sans filtrage sur cette page
@@ -64,10 +62,47 @@ const Accounts: React.FC = () => {
         accountId: null
     });
 
-    const [formData, setFormData] = useState({
+    const [closing, setClosing] = useState<{ account: Account; closedOn: string; transferTo: string } | null>(null);
+    const [archivedAccounts, setArchivedAccounts] = useState<Account[]>([]);
+    const [paymentFrom, setPaymentFrom] = useState('');
+    const [valuation, setValuation] = useState({ date: new Date().toISOString().split('T')[0], value: '' });
+
+    // Closed accounts are not in the context: list them here so they can be reopened
+    useEffect(() => {
+        dbService.getAccounts(true).then(list => setArchivedAccounts(list.filter(a => a.archived)));
+    }, [allAccounts]);
+
+    const [formData, setFormData] = useState<{
+        name: string;
+        type: AccountKind;
+        initialBalance: string;
+        openingDate: string;
+        creditLimit: string;
+        statementClosingDay: string;
+        paymentDueDay: string;
+        loanPrincipal: string;
+        loanRate: string;
+        loanTermMonths: string;
+        loanStartDate: string;
+        depreciationMethod: DepreciationMethod | '';
+        depreciationRate: string;
+        icon: string;
+        color: string;
+        group: string;
+    }>({
         name: '',
-        type: 'Courant',
+        type: 'checking',
         initialBalance: '',
+        openingDate: '',
+        creditLimit: '',
+        statementClosingDay: '',
+        paymentDueDay: '',
+        loanPrincipal: '',
+        loanRate: '',
+        loanTermMonths: '',
+        loanStartDate: '',
+        depreciationMethod: '',
+        depreciationRate: '',
         icon: 'Wallet',
         color: '#3b82f6',
         group: ''
@@ -102,6 +137,16 @@ const Accounts: React.FC = () => {
                 name: account.name,
                 type: account.type,
                 initialBalance: account.initialBalance.toString(),
+                openingDate: account.openingDate || '',
+                creditLimit: account.creditLimit?.toString() ?? '',
+                statementClosingDay: account.statementClosingDay?.toString() ?? '',
+                paymentDueDay: account.paymentDueDay?.toString() ?? '',
+                loanPrincipal: account.loanPrincipal?.toString() ?? '',
+                loanRate: account.loanRate?.toString() ?? '',
+                loanTermMonths: account.loanTermMonths?.toString() ?? '',
+                loanStartDate: account.loanStartDate ?? '',
+                depreciationMethod: account.depreciationMethod ?? '',
+                depreciationRate: account.depreciationRate?.toString() ?? '',
                 icon: account.icon || 'Wallet',
                 color: account.color || '#3b82f6',
                 group: settings.accountGroups?.[account.id] || ''
@@ -110,8 +155,18 @@ const Accounts: React.FC = () => {
             setEditingAccount(null);
             setFormData({
                 name: '',
-                type: 'Courant',
+                type: 'checking',
                 initialBalance: '',
+                openingDate: '',
+                creditLimit: '',
+                statementClosingDay: '',
+                paymentDueDay: '',
+                loanPrincipal: '',
+                loanRate: '',
+                loanTermMonths: '',
+                loanStartDate: '',
+                depreciationMethod: '',
+                depreciationRate: '',
                 icon: 'Wallet',
                 color: '#3b82f6',
                 group: ''
@@ -122,10 +177,25 @@ const Accounts: React.FC = () => {
 
     const handleSubmit = async (e: React.FormEvent) => {
         e.preventDefault();
+        const isCard = formData.type === 'credit_card';
+        // Loan terms go together; a partly filled form leaves them out
+        const hasLoanTerms = formData.type === 'loan' && !!formData.loanPrincipal && !!formData.loanRate
+            && !!formData.loanTermMonths && !!formData.loanStartDate;
+        const hasDepreciation = formData.type === 'asset' && !!formData.depreciationMethod && !!formData.depreciationRate;
         const accountData = {
             name: formData.name,
             type: formData.type,
             initialBalance: parseFloat(formData.initialBalance) || 0,
+            openingDate: formData.openingDate || null,
+            creditLimit: isCard && formData.creditLimit ? parseFloat(formData.creditLimit) : null,
+            statementClosingDay: isCard && formData.statementClosingDay ? parseInt(formData.statementClosingDay) : null,
+            paymentDueDay: isCard && formData.paymentDueDay ? parseInt(formData.paymentDueDay) : null,
+            loanPrincipal: hasLoanTerms ? parseFloat(formData.loanPrincipal) : null,
+            loanRate: hasLoanTerms ? parseFloat(formData.loanRate) : null,
+            loanTermMonths: hasLoanTerms ? parseInt(formData.loanTermMonths) : null,
+            loanStartDate: hasLoanTerms ? formData.loanStartDate : null,
+            depreciationMethod: hasDepreciation ? formData.depreciationMethod || null : null,
+            depreciationRate: hasDepreciation ? parseFloat(formData.depreciationRate) : null,
             icon: formData.icon,
             color: formData.color
         };
@@ -153,6 +223,62 @@ const Accounts: React.FC = () => {
         }
     };
 
+    const handleStartClosing = (account: Account) => {
+        setIsModalOpen(false);
+        setClosing({ account, closedOn: new Date().toISOString().split('T')[0], transferTo: '' });
+    };
+
+    const handleClose = async (e: React.FormEvent) => {
+        e.preventDefault();
+        if (!closing) return;
+        try {
+            await closeAccount(closing.account.id, closing.closedOn, closing.transferTo || undefined);
+            showToast("Compte clôturé", "success");
+            setClosing(null);
+        } catch (error) {
+            showToast(String(error), "error");
+        }
+    };
+
+    const handleScheduleCardPayment = async (accountId: string) => {
+        try {
+            await scheduleCardPayment(accountId, paymentFrom);
+            showToast("Paiement de la carte programmé", "success");
+            setPaymentFrom('');
+        } catch (error) {
+            showToast(String(error), "error");
+        }
+    };
+
+    const handleScheduleLoanPayment = async (accountId: string) => {
+        try {
+            await scheduleLoanPayment(accountId, paymentFrom);
+            showToast("Remboursement du prêt programmé", "success");
+            setPaymentFrom('');
+        } catch (error) {
+            showToast(String(error), "error");
+        }
+    };
+
+    const handleAddValuation = async (accountId: string) => {
+        try {
+            await dbService.addValuation(uuidv4(), accountId, valuation.date, parseFloat(valuation.value));
+            showToast("Estimation enregistrée", "success");
+            setValuation({ ...valuation, value: '' });
+        } catch (error) {
+            showToast(String(error), "error");
+        }
+    };
+
+    const handleReopen = async (id: string) => {
+        try {
+            await reopenAccount(id);
+            showToast("Compte rouvert", "success");
+        } catch (error) {
+            showToast(String(error), "error");
+        }
+    };
+
     const handleAddGroup = () => {
         if (newGroupName && !settings.customGroups?.includes(newGroupName)) {
             updateCustomGroups([...(settings.customGroups || []), newGroupName]);
@@ -190,28 +316,8 @@ const Accounts: React.FC = () => {
     };
 
     const handleTypeChange = (type: string) => {
-        let newIcon = formData.icon;
-        let newColor = formData.color;
-
-        switch (type) {
-            case 'Épargne':
-                newIcon = 'PiggyBank';
-                newColor = '#10b981';
-                break;
-            case 'Espèces':
-                newIcon = 'Banknote';
-                newColor = '#f59e0b';
-                break;
-            case 'Investissement':
-                newIcon = 'TrendingUp';
-                newColor = '#8b5cf6';
-                break;
-            default:
-                newIcon = 'Wallet';
-                newColor = '#3b82f6';
-                break;
-        }
-        setFormData({ ...formData, type, icon: newIcon, color: newColor });
+        const kind = accountKind(type);
+        setFormData({ ...formData, type: kind.id, icon: kind.icon, color: kind.color });
     };
 
     // Group accounts logic
@@ -460,6 +566,27 @@ const Accounts: React.FC = () => {
                 </div>
             )}
 
+            {archivedAccounts.length > 0 && (
+                <div className="space-y-3">
+                    <h3 className="text-lg font-semibold text-gray-900 dark:text-gray-200">Comptes clôturés</h3>
+                    {archivedAccounts.map(account => (
+                        <div key={account.id} className="app-card p-4 flex items-center justify-between">
+                            <div>
+                                <p className="font-medium text-gray-900 dark:text-gray-200">{account.name}</p>
+                                {account.closedOn && (
+                                    <p className="text-xs text-gray-500 dark:text-gray-400">
+                                        Clôturé le {new Date(account.closedOn).toLocaleDateString('fr-FR')}
+                                    </p>
+                                )}
+                            </div>
+                            <Button variant="ghost" icon={RotateCcw} onClick={() => handleReopen(account.id)}>
+                                Rouvrir
+                            </Button>
+                        </div>
+                    ))}
+                </div>
+            )}
+
             {/* Modals */}
             <FormPopup
                 isOpen={isModalOpen}
@@ -490,7 +617,7 @@ const Accounts: React.FC = () => {
                             <SearchableSelect
                                 value={formData.type}
                                 onChange={handleTypeChange}
-                                options={ACCOUNT_TYPES}
+                                options={ACCOUNT_KINDS}
                                 placeholder="Sélectionner un type"
                             />
                         </div>
@@ -509,6 +636,222 @@ const Accounts: React.FC = () => {
                         </div>
                     </div>
 
+                    {isLiability(formData.type) && (
+                        <p className="text-xs text-gray-500 dark:text-gray-400">
+                            Le montant dû se saisit en négatif : il est déduit du patrimoine.
+                        </p>
+                    )}
+
+                    {formData.type === 'credit_card' && (
+                        <div>
+                            <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+                                Plafond
+                            </label>
+                            <Input
+                                type="number"
+                                step="0.01"
+                                min="0.01"
+                                value={formData.creditLimit}
+                                onChange={(e) => setFormData({ ...formData, creditLimit: e.target.value })}
+                                placeholder="Aucun"
+                            />
+                        </div>
+                    )}
+
+                    {formData.type === 'credit_card' && (
+                        <div className="grid grid-cols-2 gap-4">
+                            <div>
+                                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+                                    Clôture du relevé (jour)
+                                </label>
+                                <Input
+                                    type="number"
+                                    min="1"
+                                    max="31"
+                                    value={formData.statementClosingDay}
+                                    onChange={(e) => setFormData({ ...formData, statementClosingDay: e.target.value })}
+                                />
+                            </div>
+                            <div>
+                                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+                                    Échéance (jour)
+                                </label>
+                                <Input
+                                    type="number"
+                                    min="1"
+                                    max="31"
+                                    value={formData.paymentDueDay}
+                                    onChange={(e) => setFormData({ ...formData, paymentDueDay: e.target.value })}
+                                />
+                            </div>
+                        </div>
+                    )}
+
+                    {editingAccount?.type === 'credit_card' && editingAccount.statementClosingDay && editingAccount.paymentDueDay && (
+                        <div className="flex items-end gap-2">
+                            <div className="flex-1">
+                                <SearchableSelect
+                                    label="Payer depuis"
+                                    value={paymentFrom}
+                                    onChange={setPaymentFrom}
+                                    options={accounts.filter(a => a.type === 'checking').map(a => ({ id: a.id, label: a.name }))}
+                                    placeholder="Sélectionner un compte courant"
+                                />
+                            </div>
+                            <Button
+                                type="button"
+                                variant="secondary"
+                                icon={CalendarClock}
+                                disabled={!paymentFrom}
+                                onClick={() => handleScheduleCardPayment(editingAccount.id)}
+                            >
+                                Programmer le paiement
+                            </Button>
+                        </div>
+                    )}
+
+                    {formData.type === 'loan' && (
+                        <div className="grid grid-cols-2 gap-4">
+                            <div>
+                                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+                                    Capital emprunté
+                                </label>
+                                <Input
+                                    type="number"
+                                    step="0.01"
+                                    min="0.01"
+                                    value={formData.loanPrincipal}
+                                    onChange={(e) => setFormData({ ...formData, loanPrincipal: e.target.value })}
+                                />
+                            </div>
+                            <div>
+                                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+                                    Taux annuel (%)
+                                </label>
+                                <Input
+                                    type="number"
+                                    step="0.001"
+                                    min="0"
+                                    value={formData.loanRate}
+                                    onChange={(e) => setFormData({ ...formData, loanRate: e.target.value })}
+                                />
+                            </div>
+                            <div>
+                                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+                                    Durée (mois)
+                                </label>
+                                <Input
+                                    type="number"
+                                    min="1"
+                                    max="1200"
+                                    value={formData.loanTermMonths}
+                                    onChange={(e) => setFormData({ ...formData, loanTermMonths: e.target.value })}
+                                />
+                            </div>
+                            <div>
+                                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+                                    Première échéance
+                                </label>
+                                <Input
+                                    type="date"
+                                    value={formData.loanStartDate}
+                                    onChange={(e) => setFormData({ ...formData, loanStartDate: e.target.value })}
+                                />
+                            </div>
+                        </div>
+                    )}
+
+                    {editingAccount?.type === 'loan' && editingAccount.loanPrincipal && (
+                        <div className="flex items-end gap-2">
+                            <div className="flex-1">
+                                <SearchableSelect
+                                    label="Rembourser depuis"
+                                    value={paymentFrom}
+                                    onChange={setPaymentFrom}
+                                    options={accounts.filter(a => a.id !== editingAccount.id).map(a => ({ id: a.id, label: a.name }))}
+                                    placeholder="Sélectionner un compte"
+                                />
+                            </div>
+                            <Button
+                                type="button"
+                                variant="secondary"
+                                icon={CalendarClock}
+                                disabled={!paymentFrom}
+                                onClick={() => handleScheduleLoanPayment(editingAccount.id)}
+                            >
+                                Programmer les échéances
+                            </Button>
+                        </div>
+                    )}
+
+                    {formData.type === 'asset' && (
+                        <div className="grid grid-cols-2 gap-4">
+                            <SearchableSelect
+                                label="Amortissement"
+                                value={formData.depreciationMethod}
+                                onChange={(value) => setFormData({ ...formData, depreciationMethod: value as DepreciationMethod | '' })}
+                                options={[
+                                    { id: '', label: 'Aucun' },
+                                    { id: 'straight_line', label: 'Linéaire (montant par an)' },
+                                    { id: 'percentage', label: 'Dégressif (% par an)' }
+                                ]}
+                            />
+                            <div>
+                                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+                                    Taux annuel
+                                </label>
+                                <Input
+                                    type="number"
+                                    step="0.01"
+                                    min="0.01"
+                                    disabled={!formData.depreciationMethod}
+                                    value={formData.depreciationRate}
+                                    onChange={(e) => setFormData({ ...formData, depreciationRate: e.target.value })}
+                                />
+                            </div>
+                        </div>
+                    )}
+
+                    {editingAccount?.type === 'asset' && (
+                        <div className="flex items-end gap-2">
+                            <Input
+                                type="date"
+                                value={valuation.date}
+                                onChange={(e) => setValuation({ ...valuation, date: e.target.value })}
+                            />
+                            <Input
+                                type="number"
+                                step="0.01"
+                                min="0"
+                                value={valuation.value}
+                                onChange={(e) => setValuation({ ...valuation, value: e.target.value })}
+                                placeholder="Valeur estimée"
+                            />
+                            <Button
+                                type="button"
+                                variant="secondary"
+                                disabled={!valuation.date || !valuation.value}
+                                onClick={() => handleAddValuation(editingAccount.id)}
+                            >
+                                Estimer
+                            </Button>
+                        </div>
+                    )}
+
+                    <div>
+                        <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+                            Date d'ouverture
+                        </label>
+                        <Input
+                            type="date"
+                            value={formData.openingDate}
+                            onChange={(e) => setFormData({ ...formData, openingDate: e.target.value })}
+                        />
+                        <p className="mt-1 text-xs text-gray-500 dark:text-gray-400">
+                            Les transactions antérieures sont ignorées dans le solde.
+                        </p>
+                    </div>
+
                     <div>
                         <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
                             Groupe
@@ -530,9 +873,61 @@ const Accounts: React.FC = () => {
                             size="md"
                         />
                     </div>
+
+                    {editingAccount && (
+                        <Button
+                            type="button"
+                            variant="secondary"
+                            icon={Archive}
+                            onClick={() => handleStartClosing(editingAccount)}
+                        >
+                            Clôturer le compte
+                        </Button>
+                    )}
                 </div>
             </FormPopup>
 
+            <FormPopup
+                isOpen={closing !== null}
+                onClose={() => setClosing(null)}
+                title={`Clôturer ${closing?.account.name ?? ''}`}
+                onSubmit={handleClose}
+                submitLabel="Clôturer"
+            >
+                {closing && (
+                    <div className="space-y-4">
+                        <div>
+                            <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+                                Date de clôture
+                            </label>
+                            <Input
+                                type="date"
+                                required
+                                value={closing.closedOn}
+                                onChange={(e) => setClosing({ ...closing, closedOn: e.target.value })}
+                            />
+                        </div>
+                        <div>
+                            <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+                                Virer le solde restant vers
+                            </label>
+                            <SearchableSelect
+                                value={closing.transferTo}
+                                onChange={(value) => setClosing({ ...closing, transferTo: value })}
+                                options={[
+                                    { id: '', label: 'Aucun virement' },
+                                    ...accounts.filter(a => a.id !== closing.account.id).map(a => ({ id: a.id, label: a.name }))
+                                ]}
+                                placeholder="Sélectionner un compte"
+                            />
+                            <p className="mt-1 text-xs text-gray-500 dark:text-gray-400">
+                                Le compte est archivé avec son historique et peut être rouvert.
+                            </p>
+                        </div>
+                    </div>
+                )}
+            </FormPopup>
+
             <ConfirmModal
                 isOpen={deleteConfirmation.isOpen}
                 onClose={() => setDeleteConfirmation({ isOpen: false, accountId: null })}
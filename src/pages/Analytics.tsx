@@ -5,6 +5,7 @@ import { AreaChart, Area, ResponsiveContainer, BarChart, Bar, XAxis, YAxis, Cart
 import { useBank } from '../context/BankContext';
 import { format, subMonths, eachMonthOfInterval, isSameMonth, subWeeks, subYears, isWithinInterval } from 'date-fns';
 import { fr } from 'date-fns/locale';
+import { countsTowardsBalance } from '../utils/balance';
 
 const COLORS = ['#0088FE', '#00C49F', '#FFBB28', '#FF8042', '#8884d8', '#82ca9d', '#ffc658', '#ff7300'];
 
@@ -187,7 +188,7 @@ const Analytics: React.FC = () => {
         const initialBalances: Record<string, number> = {};
         accounts.forEach(acc => {
             const transactionsBeforeStart = transactions.filter(t =>
-                t.accountId === acc.id && new Date(t.date) < dateRange.start
+                t.accountId === acc.id && new Date(t.date) < dateRange.start && countsTowardsBalance(t, acc)
             );
             initialBalances[acc.id] = acc.initialBalance + transactionsBeforeStart.reduce((sum, t) =>
                 sum + (t.type === 'income' ? t.amount : -t.amount), 0
@@ -195,7 +196,8 @@ const Analytics: React.FC = () => {
         });
 
         // Group ALL transactions by day once
-        const txByDay = transactions.reduce((acc, t) => {
+        const accountsById = new Map(accounts.map(acc => [acc.id, acc]));
+        const txByDay = transactions.filter(t => countsTowardsBalance(t, accountsById.get(t.accountId))).reduce((acc, t) => {
             const key = t.date; // already yyyy-MM-dd
             if (!acc[key]) acc[key] = [];
             acc[key].push(t);
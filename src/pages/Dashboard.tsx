@@ -13,6 +13,9 @@ import Card from '../components/ui/Card';
 import { ICONS } from '../constants/icons';
 import { useFinancialMetrics } from '../hooks/useFinancialMetrics';
 import { formatCurrency } from '../utils/format';
+import { countsTowardsBalance } from '../utils/balance';
+import { ACCOUNT_KINDS } from '../constants/accounts';
+import { AccountKind } from '../types';
 
 const Dashboard: React.FC = () => {
     const { accounts, scheduled, categories, addAccount } = useBank();
@@ -21,7 +24,7 @@ const Dashboard: React.FC = () => {
     
     const [isAccountModalOpen, setIsAccountModalOpen] = useState(false);
     const [newAccountName, setNewAccountName] = useState('');
-    const [newAccountType, setNewAccountType] = useState('Courant');
+    const [newAccountType, setNewAccountType] = useState<AccountKind>('checking');
     const [newAccountBalance, setNewAccountBalance] = useState('');
     const [newAccountIcon] = useState('Wallet');
     const [newAccountColor] = useState('#3b82f6');
@@ -30,7 +33,7 @@ const Dashboard: React.FC = () => {
 
     // Accounts with individual balance (for list)
     const accountsWithBalance = useMemo(() => accounts.map(account => {
-        const accountTransactions = relevantTransactions.filter(t => t.accountId === account.id);
+        const accountTransactions = relevantTransactions.filter(t => t.accountId === account.id && countsTowardsBalance(t, account));
         const balance = account.initialBalance + accountTransactions.reduce((sum, t) => {
             return sum + (t.type === 'income' ? t.amount : -t.amount);
         }, 0);
@@ -94,7 +97,7 @@ const Dashboard: React.FC = () => {
         e.preventDefault();
         addAccount({ name: newAccountName, type: newAccountType, initialBalance: parseFloat(newAccountBalance) || 0, icon: newAccountIcon, color: newAccountColor });
         setIsAccountModalOpen(false);
-        setNewAccountName(''); setNewAccountType('Courant'); setNewAccountBalance('');
+        setNewAccountName(''); setNewAccountType('checking'); setNewAccountBalance('');
     };
 
     return (
@@ -294,7 +297,7 @@ const Dashboard: React.FC = () => {
                     <h3 className="text-lg font-semibold">Ajouter un compte</h3>
                     <div className="space-y-4">
                         <Input label="Nom" required value={newAccountName} onChange={e => setNewAccountName(e.target.value)} />
-                        <SearchableSelect label="Type" value={newAccountType} onChange={setNewAccountType} options={[{id:'Courant',label:'Courant'},{id:'Épargne',label:'Épargne'}]} />
+                        <SearchableSelect label="Type" value={newAccountType} onChange={(value) => setNewAccountType(value as AccountKind)} options={ACCOUNT_KINDS} />
                         <Input label="Solde Initial" type="number" required value={newAccountBalance} onChange={e => setNewAccountBalance(e.target.value)} rightElement="€" />
                         <div className="flex gap-3 pt-4">
                             <Button type="button" variant="secondary" fullWidth onClick={() => setIsAccountModalOpen(false)}>Annuler</Button>
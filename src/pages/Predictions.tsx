@@ -3,6 +3,7 @@ import { useBank } from '../context/BankContext';
 import { format, addMonths } from 'date-fns';
 import { fr } from 'date-fns/locale';
 import { AreaChart, Area, XAxis, YAxis, CartesianGrid, Tooltip, ResponsiveContainer, Legend } from 'recharts';
+import { countsTowardsBalance } from '../utils/balance';
 
 const CustomTooltip = ({ active, payload }: any) => {
     if (active && payload && payload.length) {
@@ -32,7 +33,10 @@ const Predictions: React.FC = () => {
     // Calculate current total balance
     const currentTotalBalance = useMemo(() => {
         const initialBalanceSum = accounts.reduce((sum, acc) => sum + acc.initialBalance, 0);
-        const transactionsSum = transactions.reduce((sum, t) => sum + (t.type === 'income' ? t.amount : -t.amount), 0);
+        const accountsById = new Map(accounts.map(acc => [acc.id, acc]));
+        const transactionsSum = transactions
+            .filter(t => countsTowardsBalance(t, accountsById.get(t.accountId)))
+            .reduce((sum, t) => sum + (t.type === 'income' ? t.amount : -t.amount), 0);
         return initialBalanceSum + transactionsSum;
     }, [accounts, transactions]);
 
@@ -44,7 +48,7 @@ const Predictions: React.FC = () => {
 
         // Initialize current balances and dailyImpacts structure
         accounts.forEach(acc => {
-            const accountTransactions = transactions.filter(t => t.accountId === acc.id);
+            const accountTransactions = transactions.filter(t => t.accountId === acc.id && countsTowardsBalance(t, acc));
             const balanceCents = Math.round(acc.initialBalance * 100) + accountTransactions.reduce((sum, t) => sum + Math.round((t.type === 'income' ? t.amount : -t.amount) * 100), 0);
             currentBalances[acc.id] = balanceCents;
             dailyImpacts[acc.id] = {};
@@ -145,6 +145,20 @@ const SettingsPage: React.FC = () => {
                 await addTransaction({ ...tx, accountId });
                 count++;
             }
+            // Catch import errors against the balances the bank reported
+            const mismatches = (await dbService.verifyBalances(accountId)).filter(c => c.discrepancy !== 0);
+            if (mismatches.length > 0) {
+                setAlertState({
+                    isOpen: true,
+                    title: 'Soldes à vérifier',
+                    message: `${count} transactions ont été importées, mais ${mismatches.length} solde(s) constaté(s) ne correspondent plus.`,
+                    type: 'error',
+                    technicalDetails: mismatches
+                        .map(c => `${c.assertion.date} : constaté ${c.assertion.balance}, calculé ${c.computed} (écart ${c.discrepancy})`)
+                        .join('\n')
+                });
+                return;
+            }
             setAlertState({
                 isOpen: true,
                 title: 'Import réussi !',
@@ -1,5 +1,5 @@
 import React, { useState, useMemo, useCallback } from 'react';
-import { Plus, Search, Trash2, Edit2, CheckCircle2, ArrowRightLeft, Tag, Circle } from 'lucide-react';
+import { Plus, Search, Trash2, Edit2, CheckCircle2, ArrowRightLeft, Tag, Circle, Lock } from 'lucide-react';
 import Button from '../components/ui/Button';
 import { useBank } from '../context/BankContext';
 import { useToast } from '../context/ToastContext';
@@ -12,6 +12,7 @@ import Table from '../components/ui/Table';
 import Input from '../components/ui/Input';
 import { useFinancialMetrics } from '../hooks/useFinancialMetrics';
 import { formatCurrency, formatDate } from '../utils/format';
+import { countsTowardsBalance } from '../utils/balance';
 
 const Transactions: React.FC = () => {
     const {
@@ -68,10 +69,12 @@ const Transactions: React.FC = () => {
         const allSorted = [...transactions].sort((a, b) => new Date(a.date).getTime() - new Date(b.date).getTime());
         const accountBalances: Record<string, number> = {};
         accounts.forEach(acc => accountBalances[acc.id] = acc.initialBalance);
+        const accountsById = new Map(accounts.map(acc => [acc.id, acc]));
 
         const withBalance = allSorted.map(t => {
             const currentBal = accountBalances[t.accountId] || 0;
-            const newBal = currentBal + (t.type === 'income' ? t.amount : -t.amount);
+            const counted = countsTowardsBalance(t, accountsById.get(t.accountId));
+            const newBal = counted ? currentBal + (t.type === 'income' ? t.amount : -t.amount) : currentBal;
             accountBalances[t.accountId] = newBal;
             return { ...t, balance: newBal };
         });
@@ -172,13 +175,14 @@ const Transactions: React.FC = () => {
     const handleGroupCheck = async () => {
         const count = selectedIds.size;
         try {
-            // Determine the next state (if all checked, uncheck all, otherwise check all)
-            const selectedTxs = transactions.filter(t => selectedIds.has(t.id));
-            const allChecked = selectedTxs.every(t => t.checked);
+            // Determine the next state (if all cleared, unclear all, otherwise clear all).
+            // Reconciled transactions are left alone.
+            const selectedTxs = transactions.filter(t => selectedIds.has(t.id) && t.status !== 'reconciled');
+            const allCleared = selectedTxs.every(t => t.status === 'cleared');
             
             for (const id of Array.from(selectedIds)) {
                 const tx = selectedTxs.find(t => t.id === id);
-                if (tx && tx.checked === allChecked) {
+                if (tx && (tx.status === 'cleared') === allCleared) {
                     await toggleTransactionCheck(id);
                 }
             }
@@ -379,8 +383,8 @@ const Transactions: React.FC = () => {
                             width: '60px',
                             align: 'center',
                             render: (t) => (
-                                <button onClick={() => toggleTransactionCheck(t.id)} className={`transition-colors ${t.checked ? 'text-emerald-500' : 'text-gray-300 dark:text-gray-600 hover:text-gray-400'}`}>
-                                    {t.checked ? <CheckCircle2 className="w-5 h-5" /> : <Circle className="w-5 h-5" />}
+                                <button onClick={() => toggleTransactionCheck(t.id)} title={t.status === 'reconciled' ? 'Rapprochée' : t.status === 'cleared' ? 'Pointée' : 'En attente'} className={`transition-colors ${t.status === 'reconciled' ? 'text-blue-500' : t.status === 'cleared' ? 'text-emerald-500' : 'text-gray-300 dark:text-gray-600 hover:text-gray-400'}`}>
+                                    {t.status === 'reconciled' ? <Lock className="w-5 h-5" /> : t.status === 'cleared' ? <CheckCircle2 className="w-5 h-5" /> : <Circle className="w-5 h-5" />}
                                 </button>
                             )
                         },
@@ -450,7 +454,7 @@ const Transactions: React.FC = () => {
                                     category: formData.categoryId,
                                     accountId: formData.accountId,
                                     type: formData.type as 'income' | 'expense',
-                                    checked: false
+                                    status: 'pending'
                                 };
                                 await addTransaction(transactionData);
                                 showToast("Transaction ajoutée", "success");
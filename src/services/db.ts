@@ -1,5 +1,17 @@
 import { invoke } from '@tauri-apps/api/core';
-import { Account, Transaction, Category, ScheduledTransaction, Settings } from '../types';
+import { Account, ClosingTransfer, Transaction, Category, ScheduledTransaction, Settings, AuditEntry, AuditLogFilter, HistoryEntry, IntegrityIssue, IntegrityRepair, CategoryTotal, CategoryTotalsFilter, CategorySuggestion, Rule, Payee, PayeeTotal, Tag, TransactionTag, TransactionFilter, Attachment, TransactionBulkUpdate, BulkUpdateResult, TransactionSelection, Reconciliation, ReconciliationStatus, AccountStatusBalances, AccountBalance, RunningBalance, BalanceAssertion, BalanceCheck, NetWorth, CardStatement, AmortizationRow, LoanPayment, Security, Trade, Price, Holding, InvestmentSummary, Valuation, AssetValue, NetWorthPoint, NetWorthInterval, Goal, GoalProgress } from '../types';
+
+// Error returned when a reconciled transaction is changed without `force`
+export const RECONCILED_WARNING = 'Cette transaction a été rapprochée avec un relevé. Confirmez pour la modifier quand même.';
+
+// Backups made before transaction statuses have a `checked` flag instead
+const legacyTransaction = ({ checked, ...t }: any) =>
+    t.status ? t : { ...t, status: checked ? (t.reconciliationId ? 'reconciled' : 'cleared') : 'pending' };
+// Backups made before account kinds hold the French label as type
+const LEGACY_KINDS: Record<string, string> = { 'Courant': 'checking', 'Épargne': 'savings', 'Investissement': 'investment', 'Espèces': 'cash' };
+const legacyAccount = (a: any) => LEGACY_KINDS[a.type] ? { ...a, type: LEGACY_KINDS[a.type] } : a;
+const legacyRule = ({ setChecked, ...r }: any) =>
+    r.setStatus !== undefined || setChecked == null ? r : { ...r, setStatus: setChecked ? 'cleared' : 'pending' };
 
 export class DatabaseService {
     async init(): Promise<void> {
@@ -9,8 +21,9 @@ export class DatabaseService {
     // --- CRUD Operations ---
 
     // Accounts
-    async getAccounts(): Promise<Account[]> {
-        return invoke<Account[]>('get_accounts');
+    // Closed (archived) accounts are only listed with includeArchived
+    async getAccounts(includeArchived = false): Promise<Account[]> {
+        return invoke<Account[]>('get_accounts', { includeArchived });
     }
 
     async addAccount(account: Account): Promise<void> {
@@ -25,22 +38,243 @@ export class DatabaseService {
         await invoke('delete_account', { id });
     }
 
+    // With a transfer, the balance left on closedOn moves to another account
+    async closeAccount(id: string, closedOn: string, transfer?: ClosingTransfer): Promise<Account> {
+        return invoke<Account>('close_account', { id, closedOn, transfer });
+    }
+
+    async reopenAccount(id: string): Promise<Account> {
+        return invoke<Account>('reopen_account', { id });
+    }
+
+    // asOf is an inclusive YYYY-MM-DD date; clearedOnly leaves pending transactions out
+    async getAccountBalances(asOf?: string, clearedOnly = false, includeArchived = false): Promise<AccountBalance[]> {
+        return invoke<AccountBalance[]>('get_account_balances', { asOf, clearedOnly, includeArchived });
+    }
+
+    // Oldest first; transactions before `from` count towards the balance
+    async getRunningBalance(accountId: string, from?: string, to?: string): Promise<RunningBalance[]> {
+        return invoke<RunningBalance[]>('get_running_balance', { accountId, from, to });
+    }
+
+    // Sets the initial balance so that the balance at the end of `date` is `balance`
+    async rebaseOpeningBalance(accountId: string, date: string, balance: number): Promise<Account> {
+        return invoke<Account>('rebase_opening_balance', { accountId, date, balance });
+    }
+
+    // Statement last closed on or before asOf (today by default)
+    async getCardStatement(accountId: string, asOf?: string): Promise<CardStatement> {
+        return invoke<CardStatement>('get_card_statement', { accountId, asOf });
+    }
+
+    // Monthly transfer from a checking account on the due day; the amount
+    // defaults to what is left to pay on the current statement
+    async scheduleCardPayment(id: string, accountId: string, fromAccountId: string, amount?: number): Promise<ScheduledTransaction> {
+        return invoke<ScheduledTransaction>('schedule_card_payment', { id, accountId, fromAccountId, amount });
+    }
+
+    async getAmortizationSchedule(accountId: string): Promise<AmortizationRow[]> {
+        return invoke<AmortizationRow[]>('get_amortization_schedule', { accountId });
+    }
+
+    // Payments left after asOf (today by default), from the current balance of the loan
+    async getLoanProjection(accountId: string, asOf?: string): Promise<AmortizationRow[]> {
+        return invoke<AmortizationRow[]>('get_loan_projection', { accountId, asOf });
+    }
+
+    // Monthly transfer of the loan payment until the last one
+    async scheduleLoanPayment(id: string, accountId: string, fromAccountId: string): Promise<ScheduledTransaction> {
+        return invoke<ScheduledTransaction>('schedule_loan_payment', { id, accountId, fromAccountId });
+    }
+
+    // Interest expense plus principal transfer; returns the transactions created
+    async recordLoanPayment(accountId: string, payment: LoanPayment): Promise<Transaction[]> {
+        return invoke<Transaction[]>('record_loan_payment', { accountId, payment });
+    }
+
+    // Liability accounts count negatively; asOf is an inclusive YYYY-MM-DD date
+    async getNetWorth(asOf?: string): Promise<NetWorth> {
+        return invoke<NetWorth>('get_net_worth', { asOf });
+    }
+
+    // One point per period end between from and to, plus one at to
+    async getNetWorthHistory(from: string, to: string, interval: NetWorthInterval = 'month'): Promise<NetWorthPoint[]> {
+        return invoke<NetWorthPoint[]>('get_net_worth_history', { from, to, interval });
+    }
+
+    async getStatusBalances(includeArchived = false): Promise<AccountStatusBalances[]> {
+        return invoke<AccountStatusBalances[]>('get_status_balances', { includeArchived });
+    }
+
     // Transactions
-    async getTransactions(): Promise<Transaction[]> {
-        return invoke<Transaction[]>('get_transactions');
+    async getTransactions(filter: TransactionFilter = {}): Promise<Transaction[]> {
+        return invoke<Transaction[]>('get_transactions', { filter });
+    }
+
+    // Returns the stored transaction, with rules applied if it was uncategorized
+    async addTransaction(transaction: Transaction): Promise<Transaction> {
+        return invoke<Transaction>('add_transaction', { transaction });
+    }
+
+    async addTransfer(fromTransaction: Transaction, toTransaction: Transaction): Promise<void> {
+        await invoke('add_transfer', { fromTransaction, toTransaction });
+    }
+
+    // Returns the transaction as stored, with its status dates
+    async updateTransaction(transaction: Transaction, force = false): Promise<Transaction> {
+        return invoke<Transaction>('update_transaction', { transaction, force });
+    }
+
+    async deleteTransaction(id: string, force = false): Promise<void> {
+        await invoke('delete_transaction', { id, force });
+    }
+
+    async bulkUpdateTransactions(selection: TransactionSelection, update: TransactionBulkUpdate, force = false): Promise<BulkUpdateResult> {
+        return invoke<BulkUpdateResult>('bulk_update_transactions', { ...selection, update, force });
+    }
+
+    // Returns the number of deleted transactions
+    async bulkDeleteTransactions(selection: TransactionSelection, force = false): Promise<number> {
+        return invoke<number>('bulk_delete_transactions', { ...selection, force });
+    }
+
+    // Reconciliations
+    async getReconciliations(accountId?: string): Promise<Reconciliation[]> {
+        return invoke<Reconciliation[]>('get_reconciliations', { accountId });
     }
 
-    async addTransaction(transaction: Transaction): Promise<string> {
-        await invoke('add_transaction', { transaction });
-        return transaction.id;
+    async startReconciliation(id: string, accountId: string, statementDate: string, statementBalance: number): Promise<ReconciliationStatus> {
+        return invoke<ReconciliationStatus>('start_reconciliation', { id, accountId, statementDate, statementBalance });
     }
 
-    async updateTransaction(transaction: Transaction): Promise<void> {
-        await invoke('update_transaction', { transaction });
+    async getReconciliationStatus(id: string): Promise<ReconciliationStatus> {
+        return invoke<ReconciliationStatus>('get_reconciliation_status', { id });
     }
 
-    async deleteTransaction(id: string): Promise<void> {
-        await invoke('delete_transaction', { id });
+    async setTransactionsCleared(reconciliationId: string, transactionIds: string[], cleared: boolean): Promise<ReconciliationStatus> {
+        return invoke<ReconciliationStatus>('set_transactions_cleared', { reconciliationId, transactionIds, cleared });
+    }
+
+    // Fails while the cleared balance differs from the statement
+    async finishReconciliation(id: string): Promise<Reconciliation> {
+        return invoke<Reconciliation>('finish_reconciliation', { id });
+    }
+
+    async cancelReconciliation(id: string): Promise<void> {
+        await invoke('cancel_reconciliation', { id });
+    }
+
+    // Balance assertions
+    async getBalanceAssertions(accountId?: string): Promise<BalanceAssertion[]> {
+        return invoke<BalanceAssertion[]>('get_balance_assertions', { accountId });
+    }
+
+    // Returns how the asserted balance compares with the transactions
+    async addBalanceAssertion(id: string, accountId: string, date: string, balance: number): Promise<BalanceCheck> {
+        return invoke<BalanceCheck>('add_balance_assertion', { id, accountId, date, balance });
+    }
+
+    async deleteBalanceAssertion(id: string): Promise<void> {
+        await invoke('delete_balance_assertion', { id });
+    }
+
+    async verifyBalances(accountId?: string): Promise<BalanceCheck[]> {
+        return invoke<BalanceCheck[]>('verify_balances', { accountId });
+    }
+
+    // Asset valuations
+    async getValuations(accountId?: string): Promise<Valuation[]> {
+        return invoke<Valuation[]>('get_valuations', { accountId });
+    }
+
+    // Replaces the valuation already entered for that day
+    async addValuation(id: string, accountId: string, date: string, value: number): Promise<Valuation> {
+        return invoke<Valuation>('add_valuation', { id, accountId, date, value });
+    }
+
+    async deleteValuation(id: string): Promise<void> {
+        await invoke('delete_valuation', { id });
+    }
+
+    async getAssetValues(asOf?: string): Promise<AssetValue[]> {
+        return invoke<AssetValue[]>('get_asset_values', { asOf });
+    }
+
+    // Goals
+    async getGoals(): Promise<Goal[]> {
+        return invoke<Goal[]>('get_goals');
+    }
+
+    async addGoal(goal: Goal): Promise<Goal> {
+        return invoke<Goal>('add_goal', { goal });
+    }
+
+    async updateGoal(goal: Goal): Promise<void> {
+        await invoke('update_goal', { goal });
+    }
+
+    async deleteGoal(id: string): Promise<void> {
+        await invoke('delete_goal', { id });
+    }
+
+    async getGoalProgress(id: string, asOf?: string): Promise<GoalProgress> {
+        return invoke<GoalProgress>('get_goal_progress', { id, asOf });
+    }
+
+    // Investments
+    async getSecurities(): Promise<Security[]> {
+        return invoke<Security[]>('get_securities');
+    }
+
+    async addSecurity(security: Security): Promise<Security> {
+        return invoke<Security>('add_security', { security });
+    }
+
+    async updateSecurity(security: Security): Promise<void> {
+        await invoke('update_security', { security });
+    }
+
+    // Fails while trades use the security
+    async deleteSecurity(id: string): Promise<void> {
+        await invoke('delete_security', { id });
+    }
+
+    async getTrades(accountId?: string): Promise<Trade[]> {
+        return invoke<Trade[]>('get_trades', { accountId });
+    }
+
+    async addTrade(trade: Trade): Promise<Trade> {
+        return invoke<Trade>('add_trade', { trade });
+    }
+
+    async deleteTrade(id: string): Promise<void> {
+        await invoke('delete_trade', { id });
+    }
+
+    async getPrices(securityId?: string): Promise<Price[]> {
+        return invoke<Price[]>('get_prices', { securityId });
+    }
+
+    // Replaces the price already entered for that day
+    async setPrice(securityId: string, date: string, price: number): Promise<Price> {
+        return invoke<Price>('set_price', { securityId, date, price });
+    }
+
+    async deletePrice(id: string): Promise<void> {
+        await invoke('delete_price', { id });
+    }
+
+    // CSV content with symbol,date,price lines
+    async importPrices(content: string): Promise<Price[]> {
+        return invoke<Price[]>('import_prices', { content });
+    }
+
+    async getHoldings(accountId?: string, asOf?: string): Promise<Holding[]> {
+        return invoke<Holding[]>('get_holdings', { accountId, asOf });
+    }
+
+    async getInvestmentSummaries(asOf?: string): Promise<InvestmentSummary[]> {
+        return invoke<InvestmentSummary[]>('get_investment_summaries', { asOf });
     }
 
     // Categories
@@ -56,8 +290,142 @@ export class DatabaseService {
         await invoke('update_category', { category });
     }
 
-    async deleteCategory(id: string): Promise<void> {
-        await invoke('delete_category', { id });
+    // Transactions and scheduled items of the deleted category are moved to targetId
+    async deleteCategory(id: string, targetId = 'uncategorized'): Promise<void> {
+        await invoke('delete_category', { id, targetId });
+    }
+
+    async mergeCategories(sourceIds: string[], targetId: string): Promise<void> {
+        await invoke('merge_categories', { sourceIds, targetId });
+    }
+
+    async moveCategory(id: string, parentId: string | null): Promise<void> {
+        await invoke('move_category', { id, parentId });
+    }
+
+    async getCategoryTotals(filter: CategoryTotalsFilter = {}): Promise<CategoryTotal[]> {
+        return invoke<CategoryTotal[]>('get_category_totals', { ...filter });
+    }
+
+    // Best first, learned from already categorized transactions
+    async suggestCategory(description: string, amount: number, limit?: number): Promise<CategorySuggestion[]> {
+        return invoke<CategorySuggestion[]>('suggest_category', { description, amount, limit });
+    }
+
+    // One list per (description, amount) pair, in the same order
+    async suggestCategories(transactions: [string, number][], limit?: number): Promise<CategorySuggestion[][]> {
+        return invoke<CategorySuggestion[][]>('suggest_categories', { transactions, limit });
+    }
+
+    // Payees
+    async getPayees(): Promise<Payee[]> {
+        return invoke<Payee[]>('get_payees');
+    }
+
+    async addPayee(payee: Payee): Promise<void> {
+        await invoke('add_payee', { payee });
+    }
+
+    async updatePayee(payee: Payee): Promise<void> {
+        await invoke('update_payee', { payee });
+    }
+
+    async deletePayee(id: string): Promise<void> {
+        await invoke('delete_payee', { id });
+    }
+
+    async mergePayees(sourceIds: string[], targetId: string): Promise<void> {
+        await invoke('merge_payees', { sourceIds, targetId });
+    }
+
+    // Returns the number of transactions changed
+    // Reconciled transactions are skipped unless forced
+    async assignPayees(accountId?: string, all = false, force = false): Promise<number> {
+        return invoke<number>('assign_payees', { accountId, all, force });
+    }
+
+    async getPayeeTotals(filter: CategoryTotalsFilter = {}): Promise<PayeeTotal[]> {
+        return invoke<PayeeTotal[]>('get_payee_totals', { ...filter });
+    }
+
+    // Tags
+    async getTags(): Promise<Tag[]> {
+        return invoke<Tag[]>('get_tags');
+    }
+
+    async addTag(tag: Tag): Promise<void> {
+        await invoke('add_tag', { tag });
+    }
+
+    async updateTag(tag: Tag): Promise<void> {
+        await invoke('update_tag', { tag });
+    }
+
+    async deleteTag(id: string): Promise<void> {
+        await invoke('delete_tag', { id });
+    }
+
+    async getTransactionTags(): Promise<TransactionTag[]> {
+        return invoke<TransactionTag[]>('get_transaction_tags');
+    }
+
+    // Both return the number of links actually added or removed
+    async tagTransactions(transactionIds: string[], tagIds: string[]): Promise<number> {
+        return invoke<number>('tag_transactions', { transactionIds, tagIds });
+    }
+
+    async untagTransactions(transactionIds: string[], tagIds: string[]): Promise<number> {
+        return invoke<number>('untag_transactions', { transactionIds, tagIds });
+    }
+
+    // Attachments
+    async getAttachments(transactionId?: string): Promise<Attachment[]> {
+        return invoke<Attachment[]>('get_attachments', { transactionId });
+    }
+
+    // `path` is a local PDF, JPEG or PNG file, copied into the app data dir
+    async addAttachment(id: string, transactionId: string, path: string): Promise<Attachment> {
+        return invoke<Attachment>('add_attachment', { id, transactionId, path });
+    }
+
+    async openAttachment(id: string): Promise<void> {
+        await invoke('open_attachment', { id });
+    }
+
+    async deleteAttachment(id: string): Promise<void> {
+        await invoke('delete_attachment', { id });
+    }
+
+    // Returns the number of unreferenced files removed
+    async gcAttachments(): Promise<number> {
+        return invoke<number>('gc_attachments');
+    }
+
+    // Rules
+    async getRules(): Promise<Rule[]> {
+        return invoke<Rule[]>('get_rules');
+    }
+
+    async addRule(rule: Rule): Promise<Rule> {
+        return invoke<Rule>('add_rule', { rule });
+    }
+
+    async updateRule(rule: Rule): Promise<void> {
+        await invoke('update_rule', { rule });
+    }
+
+    async deleteRule(id: string): Promise<void> {
+        await invoke('delete_rule', { id });
+    }
+
+    async reorderRules(ids: string[]): Promise<void> {
+        await invoke('reorder_rules', { ids });
+    }
+
+    // Returns the number of transactions changed
+    // Reconciled transactions are skipped unless forced
+    async applyRules(accountId?: string, all = false, force = false): Promise<number> {
+        return invoke<number>('apply_rules', { accountId, all, force });
     }
 
     // Scheduled
@@ -117,20 +485,60 @@ export class DatabaseService {
         await invoke('save_settings', { settings: settingsToSend });
     }
 
+    // Audit Log
+    async getAuditLog(filter: AuditLogFilter = {}): Promise<AuditEntry[]> {
+        return invoke<AuditEntry[]>('get_audit_log', { ...filter });
+    }
+
+    // Undo / Redo
+    async undo(steps = 1): Promise<HistoryEntry[]> {
+        return invoke<HistoryEntry[]>('undo', { steps });
+    }
+
+    async redo(steps = 1): Promise<HistoryEntry[]> {
+        return invoke<HistoryEntry[]>('redo', { steps });
+    }
+
+    async getHistory(limit?: number): Promise<HistoryEntry[]> {
+        return invoke<HistoryEntry[]>('get_history', { limit });
+    }
+
+    // Integrity
+    async checkIntegrity(): Promise<IntegrityIssue[]> {
+        return invoke<IntegrityIssue[]>('check_integrity');
+    }
+
+    async repairIntegrity(repair: IntegrityRepair): Promise<IntegrityIssue[]> {
+        return invoke<IntegrityIssue[]>('repair_integrity', { repair });
+    }
+
     // --- Data Management ---
     async exportData(): Promise<any> {
-        const [accounts, transactions, categories, scheduled, settings] = await Promise.all([
-            this.getAccounts(),
+        const [accounts, transactions, categories, scheduled, rules, payees, tags, transactionTags, reconciliations, balanceAssertions, attachments, securities, trades, prices, valuations, goals, settings] = await Promise.all([
+            this.getAccounts(true),
             this.getTransactions(),
             this.getCategories(),
             this.getScheduled(),
+            this.getRules(),
+            this.getPayees(),
+            this.getTags(),
+            this.getTransactionTags(),
+            this.getReconciliations(),
+            this.getBalanceAssertions(),
+            this.getAttachments(),
+            this.getSecurities(),
+            this.getTrades(),
+            this.getPrices(),
+            this.getValuations(),
+            this.getGoals(),
             this.getSettings()
         ]);
 
         return {
             version: 1,
             timestamp: new Date().toISOString(),
-            data: { accounts, transactions, categories, scheduled, settings }
+            // Attachment files are not part of the backup, only their metadata
+            data: { accounts, transactions, categories, scheduled, rules, payees, tags, transactionTags, reconciliations, balanceAssertions, attachments, securities, trades, prices, valuations, goals, settings }
         };
     }
 
@@ -140,10 +548,23 @@ export class DatabaseService {
         }
 
         const importPayload = {
-            accounts: backupData.data.accounts || [],
-            transactions: backupData.data.transactions || [],
+            accounts: (backupData.data.accounts || []).map(legacyAccount),
+            transactions: (backupData.data.transactions || []).map(legacyTransaction),
             categories: backupData.data.categories || [],
-            scheduled: backupData.data.scheduled || []
+            scheduled: backupData.data.scheduled || [],
+            // Older backups lack the newer collections: the current ones are kept
+            rules: backupData.data.rules?.map(legacyRule),
+            payees: backupData.data.payees,
+            tags: backupData.data.tags,
+            transactionTags: backupData.data.transactionTags,
+            reconciliations: backupData.data.reconciliations,
+            balanceAssertions: backupData.data.balanceAssertions,
+            attachments: backupData.data.attachments,
+            securities: backupData.data.securities,
+            trades: backupData.data.trades,
+            prices: backupData.data.prices,
+            valuations: backupData.data.valuations,
+            goals: backupData.data.goals
         };
 
         await invoke('import_data', { data: importPayload });
@@ -172,11 +593,23 @@ export class DatabaseService {
             throw new Error('Invalid backup data format');
         }
 
-        const [currentAccounts, currentTransactions, currentCategories, currentScheduled] = await Promise.all([
-            this.getAccounts(),
+        const [currentAccounts, currentTransactions, currentCategories, currentScheduled, currentRules, currentPayees, currentTags, currentTransactionTags, currentReconciliations, currentBalanceAssertions, currentAttachments, currentSecurities, currentTrades, currentPrices, currentValuations, currentGoals] = await Promise.all([
+            this.getAccounts(true),
             this.getTransactions(),
             this.getCategories(),
-            this.getScheduled()
+            this.getScheduled(),
+            this.getRules(),
+            this.getPayees(),
+            this.getTags(),
+            this.getTransactionTags(),
+            this.getReconciliations(),
+            this.getBalanceAssertions(),
+            this.getAttachments(),
+            this.getSecurities(),
+            this.getTrades(),
+            this.getPrices(),
+            this.getValuations(),
+            this.getGoals()
         ]);
 
         const mergeArrays = (current: any[], incoming: any[]) => {
@@ -188,10 +621,26 @@ export class DatabaseService {
         };
 
         const importPayload = {
-            accounts: mergeArrays(currentAccounts, backupData.data.accounts || []),
-            transactions: mergeArrays(currentTransactions, backupData.data.transactions || []),
+            accounts: mergeArrays(currentAccounts, (backupData.data.accounts || []).map(legacyAccount)),
+            transactions: mergeArrays(currentTransactions, (backupData.data.transactions || []).map(legacyTransaction)),
             categories: mergeArrays(currentCategories, backupData.data.categories || []),
-            scheduled: mergeArrays(currentScheduled, backupData.data.scheduled || [])
+            scheduled: mergeArrays(currentScheduled, backupData.data.scheduled || []),
+            rules: mergeArrays(currentRules, (backupData.data.rules || []).map(legacyRule)),
+            payees: mergeArrays(currentPayees, backupData.data.payees || []),
+            tags: mergeArrays(currentTags, backupData.data.tags || []),
+            // Links have no id: keep the union of both sets
+            transactionTags: mergeArrays(
+                currentTransactionTags.map(l => ({ ...l, id: `${l.transactionId}:${l.tagId}` })),
+                (backupData.data.transactionTags || []).map((l: TransactionTag) => ({ ...l, id: `${l.transactionId}:${l.tagId}` }))
+            ).map(({ transactionId, tagId }) => ({ transactionId, tagId })),
+            reconciliations: mergeArrays(currentReconciliations, backupData.data.reconciliations || []),
+            balanceAssertions: mergeArrays(currentBalanceAssertions, backupData.data.balanceAssertions || []),
+            attachments: mergeArrays(currentAttachments, backupData.data.attachments || []),
+            securities: mergeArrays(currentSecurities, backupData.data.securities || []),
+            trades: mergeArrays(currentTrades, backupData.data.trades || []),
+            prices: mergeArrays(currentPrices, backupData.data.prices || []),
+            valuations: mergeArrays(currentValuations, backupData.data.valuations || []),
+            goals: mergeArrays(currentGoals, backupData.data.goals || [])
         };
 
         await invoke('import_data', { data: importPayload });
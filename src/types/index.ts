@@ -14,13 +14,46 @@ export type Periodicity =
     | 'annual'
     | 'biennial';
 
+export type AccountKind = 'checking' | 'savings' | 'credit_card' | 'cash' | 'loan' | 'investment' | 'asset' | 'liability';
+
 export interface Account {
     id: string;
     name: string;
-    type: string;
+    type: AccountKind;
     initialBalance: number;
     color: string;
     icon: string;
+    // YYYY-MM-DD the initial balance applies at; earlier transactions don't count
+    openingDate?: string | null;
+    // Closed accounts are archived and left out of the account lists
+    archived?: boolean;
+    closedOn?: string | null;
+    // Credit cards only
+    creditLimit?: number | null;
+    // Days of the month (1-31) the statement closes and its payment is due
+    statementClosingDay?: number | null;
+    paymentDueDay?: number | null;
+    // Loans only: amount borrowed, yearly rate in percent, number of monthly
+    // payments and date of the first one
+    loanPrincipal?: number | null;
+    loanRate?: number | null;
+    loanTermMonths?: number | null;
+    loanStartDate?: string | null;
+    // Assets only: yearly loss from the last valuation, an amount for
+    // straight_line or a percentage of the value for percentage
+    depreciationMethod?: DepreciationMethod | null;
+    depreciationRate?: number | null;
+}
+
+export type DepreciationMethod = 'straight_line' | 'percentage';
+
+// Transfer of the remaining balance when closing an account
+export interface ClosingTransfer {
+    toAccountId: string;
+    // Leg in the closed account
+    transactionId: string;
+    // Leg in the receiving account
+    linkedTransactionId: string;
 }
 
 export interface Transaction {
@@ -31,11 +64,21 @@ export interface Transaction {
     amount: number;
     category: string;
     description: string;
-    checked: boolean;
+    status: TransactionStatus;
+    // YYYY-MM-DD, set by the backend when the status changes
+    clearedAt?: string | null;
+    reconciledAt?: string | null;
     isTransfer?: boolean;
     linkedTransactionId?: string; // For transfers
+    payeeId?: string | null;
+    // Reconciliation that locked the transaction
+    reconciliationId?: string | null;
 }
 
+// pending: not yet seen on the bank side; cleared: seen, e.g. on the bank
+// website; reconciled: matched against a statement by a reconciliation
+export type TransactionStatus = 'pending' | 'cleared' | 'reconciled';
+
 export interface ScheduledTransaction {
     id: string;
     description: string;
@@ -48,6 +91,21 @@ export interface ScheduledTransaction {
     category: string;
     includeInForecast?: boolean;
     endDate?: string;
+    payeeId?: string | null;
+}
+
+// Aliases are plain text (case-insensitive) or /regex/
+export interface Payee {
+    id: string;
+    name: string;
+    aliases: string[];
+}
+
+export interface PayeeTotal {
+    payeeId: string;
+    income: number;
+    expense: number;
+    count: number;
 }
 
 export interface Category {
@@ -55,6 +113,311 @@ export interface Category {
     name: string;
     icon: string;
     color: string;
+    parentId?: string | null;
+}
+
+export interface CategoryTotal {
+    categoryId: string;
+    parentId: string | null;
+    // Own transactions only
+    income: number;
+    expense: number;
+    count: number;
+    // Including sub-categories
+    totalIncome: number;
+    totalExpense: number;
+    totalCount: number;
+}
+
+export interface CategorySuggestion {
+    categoryId: string;
+    confidence: number; // 0..1
+}
+
+export interface CategoryTotalsFilter {
+    from?: string;
+    to?: string;
+    accountId?: string;
+    // Only transactions carrying any of these tags
+    tagIds?: string[];
+}
+
+export interface Tag {
+    id: string;
+    name: string;
+    color?: string | null;
+}
+
+export interface TransactionTag {
+    transactionId: string;
+    tagId: string;
+}
+
+export interface Reconciliation {
+    id: string;
+    accountId: string;
+    statementDate: string;
+    statementBalance: number;
+    status: 'open' | 'finished';
+    createdAt: string;
+    finishedAt?: string | null;
+}
+
+export interface AccountBalance {
+    accountId: string;
+    balance: number;
+    // Credit limit plus balance, for credit cards with a limit
+    availableCredit: number | null;
+}
+
+// Last closed statement of a credit card
+export interface CardStatement {
+    accountId: string;
+    periodStart: string;
+    closingDate: string;
+    dueDate: string;
+    // Owed at the closing date
+    statementBalance: number;
+    // Payments and refunds since the closing date
+    paid: number;
+    remaining: number;
+    minimumPayment: number;
+}
+
+// Monthly loan payment split into interest and principal
+export interface AmortizationRow {
+    number: number;
+    date: string;
+    payment: number;
+    interest: number;
+    principal: number;
+    // Principal left to repay after the payment
+    balance: number;
+}
+
+export interface LoanPayment {
+    fromAccountId: string;
+    date: string;
+    amount: number;
+    interestTransactionId: string;
+    fromTransactionId: string;
+    toTransactionId: string;
+}
+
+// Assets minus what is owed on liability accounts
+export interface NetWorth {
+    assets: number;
+    liabilities: number;
+    netWorth: number;
+}
+
+export type NetWorthInterval = 'day' | 'week' | 'month' | 'quarter' | 'year';
+
+// Net worth at the end of a period, for charting
+export interface NetWorthPoint {
+    date: string;
+    assets: number;
+    liabilities: number;
+    netWorth: number;
+}
+
+// Savings goal followed on an account balance or on a tag, one of the two
+export interface Goal {
+    id: string;
+    name: string;
+    targetAmount: number;
+    targetDate?: string | null;
+    accountId?: string | null;
+    tagId?: string | null;
+}
+
+export interface GoalProgress {
+    goalId: string;
+    date: string;
+    current: number;
+    remaining: number;
+    // Average over the last few months
+    monthlyContribution: number;
+    requiredMonthlyContribution: number | null;
+    // Null when the recent pace never reaches the target
+    projectedDate: string | null;
+}
+
+// Balance of an account right after a transaction
+export interface RunningBalance {
+    transactionId: string;
+    date: string;
+    // Signed: negative for expenses and outgoing transfers
+    amount: number;
+    balance: number;
+}
+
+// Balance the bank reported for an account at the end of a day
+export interface BalanceAssertion {
+    id: string;
+    accountId: string;
+    date: string;
+    balance: number;
+    createdAt: string;
+}
+
+export interface BalanceCheck {
+    assertion: BalanceAssertion;
+    // Balance computed from the transactions at the end of the day
+    computed: number;
+    // Asserted minus computed balance; 0 when they agree
+    discrepancy: number;
+}
+
+// Estimated value of an asset account (house, car...) at a date
+export interface Valuation {
+    id: string;
+    accountId: string;
+    date: string;
+    value: number;
+}
+
+// Last valuation of an asset, depreciated up to the date
+export interface AssetValue {
+    accountId: string;
+    date: string;
+    value: number;
+    valuationDate: string;
+    valuation: number;
+}
+
+export interface Security {
+    id: string;
+    // Ticker or ISIN, matched by the price import
+    symbol: string;
+    name: string;
+}
+
+// Trades don't move the cash balance of the investment account
+export interface Trade {
+    id: string;
+    accountId: string;
+    securityId: string;
+    date: string;
+    type: 'buy' | 'sell';
+    quantity: number;
+    // Per unit, fees excluded
+    price: number;
+    fees: number;
+}
+
+export interface Price {
+    // "securityId:date"
+    id: string;
+    securityId: string;
+    date: string;
+    price: number;
+}
+
+// Lots are sold first in, first out; a position sold off has a quantity of 0
+export interface Holding {
+    accountId: string;
+    securityId: string;
+    quantity: number;
+    costBasis: number;
+    // Last price entered, or the price of the last trade
+    price: number;
+    priceDate: string;
+    marketValue: number;
+    unrealizedGain: number;
+    realizedGain: number;
+}
+
+export interface InvestmentSummary {
+    accountId: string;
+    marketValue: number;
+    costBasis: number;
+    realizedGain: number;
+    unrealizedGain: number;
+}
+
+// Balances include the initial balance; pending is only the amount still to clear
+export interface AccountStatusBalances {
+    accountId: string;
+    reconciled: number;
+    // Cleared and reconciled transactions
+    cleared: number;
+    pending: number;
+    total: number;
+}
+
+export interface ReconciliationStatus {
+    reconciliation: Reconciliation;
+    // Opening balance plus cleared transactions up to the statement date
+    clearedBalance: number;
+    // Statement balance minus cleared balance; 0 when it can be finished
+    difference: number;
+    clearedCount: number;
+    unclearedCount: number;
+}
+
+// Fields left out are not changed
+export interface TransactionBulkUpdate {
+    category?: string;
+    accountId?: string;
+    status?: Exclude<TransactionStatus, 'reconciled'>;
+    addTagIds?: string[];
+    removeTagIds?: string[];
+    // Plain, case-sensitive text replaced in descriptions
+    descriptionFind?: string;
+    descriptionReplace?: string;
+}
+
+export interface BulkUpdateResult {
+    matched: number;
+    updated: number;
+    tagsAdded: number;
+    tagsRemoved: number;
+}
+
+// Bulk commands target the given ids, the transactions matching the filter, or both
+export interface TransactionSelection {
+    ids?: string[];
+    filter?: TransactionFilter;
+}
+
+// Receipt or document; the file is stored once per content hash
+export interface Attachment {
+    id: string;
+    transactionId: string;
+    hash: string;
+    mimeType: 'application/pdf' | 'image/jpeg' | 'image/png';
+    originalName: string;
+    size: number;
+    createdAt: string;
+}
+
+export interface TransactionFilter {
+    accountId?: string;
+    from?: string;
+    to?: string;
+    tagIds?: string[];
+    // Require every tag instead of any of them
+    matchAllTags?: boolean;
+}
+
+// Auto-categorization rule: all conditions that are set must match
+export interface Rule {
+    id: string;
+    name: string;
+    position: number;
+    enabled: boolean;
+    descriptionContains?: string | null;
+    descriptionRegex?: string | null;
+    amountMin?: number | null;
+    amountMax?: number | null;
+    accountId?: string | null;
+    type?: 'income' | 'expense' | null;
+    setCategory?: string | null;
+    setStatus?: Exclude<TransactionStatus, 'reconciled'> | null;
+    setPayee?: string | null;
+    setTags?: string[];
+    stopProcessing: boolean;
 }
 
 export interface AppData {
@@ -62,6 +425,65 @@ export interface AppData {
     transactions: Transaction[];
     categories: Category[];
     scheduled: ScheduledTransaction[];
+    rules?: Rule[];
+    payees?: Payee[];
+    tags?: Tag[];
+    transactionTags?: TransactionTag[];
+    reconciliations?: Reconciliation[];
+    balanceAssertions?: BalanceAssertion[];
+    attachments?: Attachment[];
+    securities?: Security[];
+    trades?: Trade[];
+    prices?: Price[];
+    valuations?: Valuation[];
+    goals?: Goal[];
+}
+
+export interface AuditEntry {
+    id: number;
+    timestamp: string;
+    entity: 'account' | 'transaction' | 'category' | 'scheduled' | 'settings' | 'import';
+    entityId: string;
+    action: 'create' | 'update' | 'delete' | 'import' | 'undo' | 'redo';
+    before?: string; // JSON snapshot
+    after?: string; // JSON snapshot
+}
+
+export interface AuditLogFilter {
+    entity?: AuditEntry['entity'];
+    entityId?: string;
+    from?: string; // YYYY-MM-DD
+    to?: string; // YYYY-MM-DD
+    limit?: number;
+}
+
+export interface HistoryEntry {
+    id: number;
+    label: string; // Name of the command that performed the operation
+    createdAt: string;
+    undone: boolean;
+}
+
+export type IntegrityIssueKind =
+    | 'orphan_transaction'
+    | 'orphan_scheduled'
+    | 'dangling_scheduled_target'
+    | 'dangling_transfer_link'
+    | 'non_reciprocal_transfer'
+    | 'missing_category';
+
+export interface IntegrityIssue {
+    kind: IntegrityIssueKind;
+    entity: 'transaction' | 'scheduled';
+    entityId: string;
+    reference: string; // The id that could not be resolved
+    message: string;
+}
+
+export interface IntegrityRepair {
+    kinds: IntegrityIssueKind[];
+    accountId?: string; // Orphans are moved here, or deleted when absent
+    categoryId?: string; // Replaces missing categories, defaults to 'uncategorized'
 }
 
 export interface BankContextType {
@@ -72,6 +494,10 @@ export interface BankContextType {
     addAccount: (account: Omit<Account, 'id'>) => Promise<string>;
     updateAccount: (account: Account) => Promise<void>;
     deleteAccount: (id: string) => Promise<void>;
+    closeAccount: (id: string, closedOn: string, transferToId?: string) => Promise<void>;
+    reopenAccount: (id: string) => Promise<void>;
+    scheduleCardPayment: (accountId: string, fromAccountId: string, amount?: number) => Promise<void>;
+    scheduleLoanPayment: (accountId: string, fromAccountId: string) => Promise<void>;
     addTransaction: (transaction: Omit<Transaction, 'id'>) => Promise<string>;
     addTransfer: (fromAccountId: string, toAccountId: string, amount: number, date: string, description: string) => Promise<void>;
     updateTransaction: (transaction: Transaction) => Promise<void>;
@@ -79,7 +505,7 @@ export interface BankContextType {
     toggleTransactionCheck: (id: string) => Promise<void>;
     addCategory: (category: Omit<Category, 'id'>) => Promise<string>;
     updateCategory: (category: Category) => Promise<void>;
-    deleteCategory: (id: string) => Promise<void>;
+    deleteCategory: (id: string, targetId?: string) => Promise<void>;
     addScheduled: (scheduled: Omit<ScheduledTransaction, 'id'>) => Promise<void>;
     updateScheduled: (scheduled: ScheduledTransaction) => Promise<void>;
     deleteScheduled: (id: string) => Promise<void>;
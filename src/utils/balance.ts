@@ -0,0 +1,6 @@
+import { Account, Transaction } from '../types';
+
+// Transactions before the opening date of their account are history its
+// initial balance already includes
+export const countsTowardsBalance = (t: Transaction, account?: Account): boolean =>
+    !account?.openingDate || t.date >= account.openingDate;